@@ -0,0 +1,220 @@
+//! Derive macros for `bevy_http_client`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Type};
+
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"];
+
+/// Implements `HttpEndpoint` from an `#[endpoint(...)]` attribute.
+///
+/// `{name}` placeholders in the path are filled with the `Display` value of the field `name`. A
+/// field marked `#[endpoint(query)]` is sent as the query string and a field marked
+/// `#[endpoint(body)]` as the JSON body.
+///
+/// ```ignore
+/// #[derive(HttpEndpoint)]
+/// #[endpoint(method = "PUT", path = "/users/{id}", response = User)]
+/// struct UpdateUser {
+///     id: u64,
+///     #[endpoint(body)]
+///     user: User,
+/// }
+/// ```
+#[proc_macro_derive(HttpEndpoint, attributes(endpoint))]
+pub fn derive_http_endpoint(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_http_endpoint(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct EndpointAttr {
+    method: LitStr,
+    path: LitStr,
+    response: Type,
+}
+
+fn parse_endpoint_attr(input: &DeriveInput) -> syn::Result<EndpointAttr> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("endpoint"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(&input.ident, "missing #[endpoint(...)] attribute")
+        })?;
+
+    let (mut method, mut path, mut response) = (None, None, None);
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("method") {
+            method = Some(meta.value()?.parse::<LitStr>()?);
+        } else if meta.path.is_ident("path") {
+            path = Some(meta.value()?.parse::<LitStr>()?);
+        } else if meta.path.is_ident("response") {
+            response = Some(meta.value()?.parse::<Type>()?);
+        } else {
+            return Err(meta.error("expected `method`, `path` or `response`"));
+        }
+        Ok(())
+    })?;
+
+    let method = method.ok_or_else(|| syn::Error::new_spanned(attr, "missing `method`"))?;
+    if !METHODS.contains(&method.value().as_str()) {
+        return Err(syn::Error::new_spanned(
+            &method,
+            format!("unsupported method, expected one of {}", METHODS.join(", ")),
+        ));
+    }
+
+    Ok(EndpointAttr {
+        method,
+        path: path.ok_or_else(|| syn::Error::new_spanned(attr, "missing `path`"))?,
+        response: response.ok_or_else(|| syn::Error::new_spanned(attr, "missing `response`"))?,
+    })
+}
+
+/// Finds the field marked `#[endpoint(<marker>)]`, returning its name and type.
+fn find_marked_field<'a>(
+    input: &'a DeriveInput,
+    marker: &str,
+) -> syn::Result<Option<(&'a syn::Ident, &'a Type)>> {
+    let Data::Struct(data) = &input.data else {
+        return Ok(None);
+    };
+    let mut found = None;
+    for field in &data.fields {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("endpoint"))
+        {
+            let mut marked = false;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("query") || meta.path.is_ident("body") {
+                    marked |= meta.path.is_ident(marker);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `query` or `body`"))
+                }
+            })?;
+            if !marked {
+                continue;
+            }
+            let Some(ident) = field.ident.as_ref() else {
+                return Err(syn::Error::new_spanned(attr, "expected a named field"));
+            };
+            if found.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    format!("only one field can be marked `{marker}`"),
+                ));
+            }
+            found = Some((ident, &field.ty));
+        }
+    }
+    Ok(found)
+}
+
+/// Generates the associated type and accessor for the field marked `#[endpoint(<marker>)]`.
+fn expand_marked_field(input: &DeriveInput, marker: &str) -> syn::Result<TokenStream2> {
+    let type_name = syn::Ident::new(
+        if marker == "query" { "Query" } else { "Body" },
+        proc_macro2::Span::call_site(),
+    );
+    let method = syn::Ident::new(marker, proc_macro2::Span::call_site());
+    Ok(match find_marked_field(input, marker)? {
+        Some((ident, ty)) => quote! {
+            type #type_name = #ty;
+
+            fn #method(&self) -> ::std::option::Option<&Self::#type_name> {
+                ::std::option::Option::Some(&self.#ident)
+            }
+        },
+        None => quote! {
+            type #type_name = ();
+        },
+    })
+}
+
+/// Generates the body of `HttpEndpoint::path`, checking that every placeholder names a field.
+fn expand_path(input: &DeriveInput, path: &LitStr) -> syn::Result<TokenStream2> {
+    let field_names: Vec<String> = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.as_ref().map(ToString::to_string))
+                .collect(),
+            _ => vec![],
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "HttpEndpoint can only be derived for structs",
+            ))
+        }
+    };
+
+    let template = path.value();
+    let mut pushes = vec![];
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| syn::Error::new_spanned(path, "unclosed `{` in path"))?;
+        let literal = &rest[..start];
+        let name = &rest[start + 1..end];
+        if !field_names.iter().any(|field| field == name) {
+            return Err(syn::Error::new_spanned(
+                path,
+                format!("path parameter `{name}` is not a field of the struct"),
+            ));
+        }
+        let field = syn::Ident::new(name, path.span());
+        pushes.push(quote! {
+            path.push_str(#literal);
+            path.push_str(&::bevy_http_client::encode_path_param(&self.#field.to_string()));
+        });
+        rest = &rest[end + 1..];
+    }
+
+    Ok(quote! {
+        let mut path = ::std::string::String::new();
+        #(#pushes)*
+        path.push_str(#rest);
+        path
+    })
+}
+
+fn expand_http_endpoint(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let EndpointAttr {
+        method,
+        path,
+        response,
+    } = parse_endpoint_attr(&input)?;
+    let path_body = expand_path(&input, &path)?;
+    let query = expand_marked_field(&input, "query")?;
+    let body = expand_marked_field(&input, "body")?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::bevy_http_client::HttpEndpoint for #ident #ty_generics #where_clause {
+            type Response = #response;
+
+            const METHOD: &'static str = #method;
+
+            const PATH: &'static str = #path;
+
+            fn path(&self) -> ::std::string::String {
+                #path_body
+            }
+
+            #query
+
+            #body
+        }
+    })
+}
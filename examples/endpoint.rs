@@ -0,0 +1,53 @@
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_http_client::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct IpInfo {
+    pub ip: String,
+}
+
+#[derive(Serialize)]
+struct FormatQuery {
+    format: String,
+}
+
+#[derive(HttpEndpoint)]
+#[endpoint(method = "GET", path = "/", response = IpInfo)]
+struct GetIp {
+    #[endpoint(query)]
+    query: FormatQuery,
+}
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HttpClientPlugin))
+        .add_systems(Update, (handle_response, handle_error))
+        .add_systems(
+            Update,
+            send_request.run_if(on_timer(std::time::Duration::from_secs(1))),
+        );
+    app.register_endpoint::<GetIp>();
+    app.run();
+}
+
+fn send_request(mut ev_request: EventWriter<TypedRequest<IpInfo>>) {
+    let endpoint = GetIp {
+        query: FormatQuery {
+            format: "json".to_string(),
+        },
+    };
+    ev_request.send(endpoint.request("https://api.ipify.org"));
+}
+
+fn handle_response(mut ev_response: EventReader<TypedResponse<IpInfo>>) {
+    for response in ev_response.read() {
+        println!("ip: {}", response.ip);
+    }
+}
+
+fn handle_error(mut ev_error: EventReader<TypedResponseError<IpInfo>>) {
+    for error in ev_error.read() {
+        println!("Error retrieving IP: {}", error.err);
+    }
+}
@@ -0,0 +1,119 @@
+use std::{fmt, str::FromStr};
+
+use url::Url;
+
+use crate::error::HttpClientBuildError;
+
+/// An absolute HTTP URL built piece by piece, with every piece encoded as needed.
+///
+/// Path segments and query values may contain spaces, unicode and reserved characters such as
+/// `/`, `?` or `&`, they are percent-encoded. `HttpClient::get`, `post`, … accept an `ApiUrl`
+/// directly.
+///
+/// # Examples
+///
+/// ```
+/// let url = ApiUrl::parse("https://example.com/api")?
+///     .push_path_segment("users")
+///     .push_path_segment("fox & friends")
+///     .set_query("lang", "zh-CN");
+/// assert_eq!(
+///     url.as_str(),
+///     "https://example.com/api/users/fox%20&%20friends?lang=zh-CN"
+/// );
+///
+/// let request = HttpClient::new().get(url).build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApiUrl(Url);
+
+impl ApiUrl {
+    /// Parses an absolute `http` or `https` URL.
+    pub fn parse(url: &str) -> Result<Self, HttpClientBuildError> {
+        let invalid = |reason: String| HttpClientBuildError::InvalidUrl {
+            url: url.to_string(),
+            reason,
+        };
+        let parsed = Url::parse(url).map_err(|e| invalid(e.to_string()))?;
+        match parsed.scheme() {
+            "http" | "https" => {}
+            scheme => return Err(invalid(format!("unsupported scheme `{scheme}`"))),
+        }
+        if !parsed.has_host() {
+            return Err(invalid("missing host".to_string()));
+        }
+        Ok(Self(parsed))
+    }
+
+    /// Resolves `reference` against this URL taken as a directory.
+    ///
+    /// Unlike plain URL resolution, `https://example.com/api` joined with `users` gives
+    /// `https://example.com/api/users`. A reference starting with `/` replaces the whole path and
+    /// an absolute reference replaces the whole URL.
+    pub fn join(&self, reference: &str) -> Result<Self, HttpClientBuildError> {
+        let mut base = self.0.clone();
+        if !base.path().ends_with('/') {
+            let path = format!("{}/", base.path());
+            base.set_path(&path);
+        }
+        let joined = base
+            .join(reference)
+            .map_err(|e| HttpClientBuildError::InvalidUrl {
+                url: reference.to_string(),
+                reason: e.to_string(),
+            })?;
+        Self::parse(joined.as_str())
+    }
+
+    /// Appends a path segment, encoding every character that is not allowed in one, `/`
+    /// included.
+    pub fn push_path_segment(mut self, segment: impl AsRef<str>) -> Self {
+        self.0
+            .path_segments_mut()
+            .expect("an HTTP URL has a path")
+            .pop_if_empty()
+            .push(segment.as_ref());
+        self
+    }
+
+    /// Sets the query parameter `key` to `value`, replacing its previous values.
+    pub fn set_query(mut self, key: &str, value: impl AsRef<str>) -> Self {
+        let pairs: Vec<(String, String)> = self
+            .0
+            .query_pairs()
+            .filter(|(name, _)| name != key)
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        self.0
+            .query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair(key, value.as_ref());
+        self
+    }
+
+    /// The encoded URL.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for ApiUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+impl FromStr for ApiUrl {
+    type Err = HttpClientBuildError;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        Self::parse(url)
+    }
+}
+
+impl From<ApiUrl> for String {
+    fn from(url: ApiUrl) -> Self {
+        url.0.into()
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use bevy_app::{App, Last, Plugin};
+use bevy_asset::{
+    io::{AssetReader, AssetReaderError, AssetSource, PathStream, Reader, VecReader},
+    AssetApp,
+};
+use bevy_ecs::prelude::*;
+use ehttp::Request;
+
+use crate::{
+    middleware::{FetchOptions, HttpMiddleware},
+    HttpClientSetting,
+};
+
+/// Registers the `http` and `https` asset sources, so assets load from URLs with
+/// `asset_server.load("https://cdn.example.com/hero.png")`.
+///
+/// Downloads go through the middleware and are recorded in the metrics and history like the other
+/// requests. At most `HttpClientSetting::client_limits` assets download at once, as set when the
+/// plugin is added.
+///
+/// The plugin must be added before the `AssetPlugin`, and so before `DefaultPlugins`.
+///
+/// # Examples
+///
+/// ```
+/// App::new()
+///     .add_plugins((HttpAssetSourcePlugin, DefaultPlugins, HttpClientPlugin))
+///     .add_systems(Startup, |mut commands: Commands, asset_server: Res<AssetServer>| {
+///         commands.spawn(Sprite::from_image(
+///             asset_server.load("https://cdn.example.com/hero.png"),
+///         ));
+///     });
+/// ```
+pub struct HttpAssetSourcePlugin;
+
+impl Plugin for HttpAssetSourcePlugin {
+    fn build(&self, app: &mut App) {
+        let limit = app.world().get_resource::<HttpClientSetting>().map_or_else(
+            || HttpClientSetting::default().client_limits,
+            |setting| setting.client_limits,
+        );
+        let permits = Permits::new(limit);
+        let shared = SharedMiddleware::default();
+        for scheme in ["http", "https"] {
+            let reader = HttpAssetReader {
+                scheme,
+                middleware: shared.clone(),
+                permits: permits.clone(),
+            };
+            app.register_asset_source(
+                scheme,
+                AssetSource::build().with_reader(move || Box::new(reader.clone())),
+            );
+        }
+        app.insert_resource(shared)
+            .add_systems(Last, sync_middleware);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // share the middleware of the other plugins before the startup systems load assets
+        if let Some(middleware) = app.world().get_resource::<HttpMiddleware>() {
+            *app.world()
+                .resource::<SharedMiddleware>()
+                .0
+                .write()
+                .unwrap() = middleware.clone();
+        }
+    }
+}
+
+/// The middleware used by the asset readers, which run outside of the world.
+#[derive(Resource, Clone, Default)]
+struct SharedMiddleware(Arc<RwLock<HttpMiddleware>>);
+
+/// A system that shares the middleware registered on the app with the asset readers.
+fn sync_middleware(middleware: Option<Res<HttpMiddleware>>, shared: Res<SharedMiddleware>) {
+    if let Some(middleware) = middleware.filter(|middleware| middleware.is_changed()) {
+        *shared.0.write().unwrap() = middleware.clone();
+    }
+}
+
+/// Reads the assets of the `scheme` source from the network.
+#[derive(Clone)]
+struct HttpAssetReader {
+    scheme: &'static str,
+    middleware: SharedMiddleware,
+    permits: Permits,
+}
+
+/// Limits the number of concurrent downloads, holding a message per download in progress.
+#[derive(Clone)]
+struct Permits(async_channel::Sender<()>, async_channel::Receiver<()>);
+
+impl Permits {
+    fn new(limit: usize) -> Self {
+        let (tx, rx) = async_channel::bounded(limit.max(1));
+        Self(tx, rx)
+    }
+
+    /// Waits until a download can start, returning a guard to hold during the download.
+    async fn acquire(&self) -> Permit<'_> {
+        // the channel cannot be closed while `self` holds both sides
+        self.0.send(()).await.ok();
+        Permit(&self.1)
+    }
+}
+
+/// A download slot, released when dropped, including when the load is cancelled.
+struct Permit<'a>(&'a async_channel::Receiver<()>);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.0.try_recv().ok();
+    }
+}
+
+impl AssetReader for HttpAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        let url = format!("{}://{}", self.scheme, path.to_string_lossy());
+        let _permit = self.permits.acquire().await;
+        let fetch = self
+            .middleware
+            .0
+            .read()
+            .unwrap()
+            .fetch(Request::get(url), FetchOptions::default());
+        let result = fetch.await;
+
+        match result {
+            Ok(response) if response.ok => Ok(VecReader::new(response.bytes)),
+            Ok(response) if response.status == 404 => {
+                Err(AssetReaderError::NotFound(path.to_path_buf()))
+            }
+            Ok(response) => Err(AssetReaderError::HttpError(response.status)),
+            Err(e) => Err(AssetReaderError::Io(Arc::new(std::io::Error::other(e)))),
+        }
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        // meta files are not served, loaders use their default settings
+        Err::<VecReader, _>(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        Err(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn is_directory<'a>(&'a self, _path: &'a Path) -> Result<bool, AssetReaderError> {
+        Ok(false)
+    }
+}
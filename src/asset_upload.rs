@@ -0,0 +1,151 @@
+use bevy_app::{App, Plugin, Update};
+use bevy_asset::{Asset, AssetServer, Assets, Handle, LoadState, UntypedHandle};
+use bevy_ecs::prelude::*;
+
+use crate::{
+    error::HttpError,
+    lifecycle::{RequestFailed, RequestInfo},
+    observe::{deliver, Recipient},
+    HttpClient, HttpResponseError,
+};
+
+/// Sends the requests of `HttpAssetUpload` components once their asset is available.
+///
+/// Requires the `HttpClientPlugin` and the asset plugin of Bevy.
+pub struct HttpAssetUploadPlugin;
+
+impl Plugin for HttpAssetUploadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, send_asset_uploads);
+    }
+}
+
+/// An asset that can be uploaded as the body of a request, see `HttpClient::body_from_asset`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Asset, TypePath)]
+/// struct SaveFile(Vec<u8>);
+///
+/// impl AssetBody for SaveFile {
+///     fn body(&self) -> Vec<u8> {
+///         self.0.clone()
+///     }
+/// }
+/// ```
+pub trait AssetBody: Asset {
+    /// The body the asset is uploaded as.
+    fn body(&self) -> Vec<u8>;
+
+    /// The "Content-Type" header of the body, `application/octet-stream` by default.
+    fn content_type(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A request whose body is an asset, sent from the entity it is added to once the asset is
+/// loaded.
+///
+/// The request then goes through the usual pipelines, with its lifecycle events. If the asset
+/// fails to load, an `HttpResponseError` is sent instead, along with a `RequestFailed` event.
+/// The component is removed when the request is sent or failed.
+#[derive(Component, Debug)]
+pub struct HttpAssetUpload {
+    client: HttpClient,
+    handle: UntypedHandle,
+    poll: fn(&World, &UntypedHandle) -> AssetBodyState,
+}
+
+/// Whether the asset of an `HttpAssetUpload` is available.
+enum AssetBodyState {
+    Loading,
+    Loaded(Vec<u8>, Option<String>),
+    Failed(String),
+}
+
+impl HttpClient {
+    /// Uploads the asset of `handle` as the body of the request, once it is loaded, e.g. a
+    /// captured screenshot or an exported save file.
+    ///
+    /// Spawn the returned component to send the request, which requires the
+    /// `HttpAssetUploadPlugin`. The results are delivered to the observers of the entity, unless
+    /// the request was made from another `entity`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// commands
+    ///     .spawn(HttpClient::new().put("https://example.com/saves/1").body_from_asset(save))
+    ///     .observe(|trigger: Trigger<HttpObserved<HttpResponse>>| {
+    ///         println!("uploaded with {}", trigger.status);
+    ///     });
+    /// ```
+    pub fn body_from_asset<A: AssetBody>(self, handle: Handle<A>) -> HttpAssetUpload {
+        HttpAssetUpload {
+            client: self,
+            handle: handle.untyped(),
+            poll: poll_asset_body::<A>,
+        }
+    }
+}
+
+/// Reads the body of the asset of `handle`, if it is loaded.
+fn poll_asset_body<A: AssetBody>(world: &World, handle: &UntypedHandle) -> AssetBodyState {
+    let id = handle.id().typed::<A>();
+    if let Some(asset) = world
+        .get_resource::<Assets<A>>()
+        .and_then(|assets| assets.get(id))
+    {
+        return AssetBodyState::Loaded(asset.body(), asset.content_type());
+    }
+    match world
+        .get_resource::<AssetServer>()
+        .and_then(|server| server.get_load_state(id))
+    {
+        Some(LoadState::Failed(e)) => AssetBodyState::Failed(e.to_string()),
+        _ => AssetBodyState::Loading,
+    }
+}
+
+/// A system that sends the requests of the uploads whose asset is loaded.
+fn send_asset_uploads(
+    world: &World,
+    mut commands: Commands,
+    uploads: Query<(Entity, &HttpAssetUpload)>,
+) {
+    for (entity, upload) in uploads.iter() {
+        let mut client = upload.client.clone();
+        client.from_entity = client.from_entity.or(Some(entity));
+        match (upload.poll)(world, &upload.handle) {
+            AssetBodyState::Loading => continue,
+            AssetBodyState::Loaded(body, content_type) => {
+                client = client.body_bytes(body);
+                if let Some(content_type) = content_type {
+                    client = client.content_type(content_type);
+                }
+                commands.send_event(client.build());
+            }
+            AssetBodyState::Failed(e) => {
+                let request = client.build();
+                let recipient = Recipient {
+                    entity: request.from_entity,
+                    observers_only: request.observers_only,
+                };
+                let error = HttpError::Other(format!("the asset of the body failed to load: {e}"));
+                commands.send_event(RequestFailed {
+                    request: RequestInfo {
+                        method: request.request.method,
+                        url: request.request.url,
+                        from_entity: request.from_entity,
+                        label: request.label.clone(),
+                    },
+                    error: error.clone(),
+                });
+                let response = HttpResponseError::new(error).label(request.label);
+                commands.queue(move |world: &mut World| deliver(world, recipient, response));
+            }
+        }
+        commands.entity(entity).remove::<HttpAssetUpload>();
+    }
+}
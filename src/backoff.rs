@@ -0,0 +1,207 @@
+use bevy_ecs::prelude::*;
+use bevy_utils::{tracing, Duration, HashMap};
+use serde::{Deserialize, Serialize};
+
+/// How the delays of a `Backoff` are randomized, so clients failing together do not retry in
+/// sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Jitter {
+    /// The delays are not randomized.
+    #[default]
+    None,
+    /// A random delay between zero and the backoff delay.
+    Full,
+    /// Half the backoff delay, plus a random delay up to the other half.
+    Equal,
+    /// A random delay between the initial delay and three times the previous delay, capped by
+    /// the maximum, regardless of the multiplier.
+    Decorrelated,
+}
+
+/// A curve of delays between the retries of a request.
+///
+/// The delay before the retry `n`, counted from zero, is `initial * multiplier^n`, capped by
+/// `max`, then randomized by the jitter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backoff {
+    /// The delay before the first retry.
+    pub initial: Duration,
+    /// The longest delay.
+    pub max: Duration,
+    /// How much the delay grows with each retry.
+    pub multiplier: f32,
+    pub jitter: Jitter,
+}
+
+impl Default for Backoff {
+    /// An exponential backoff from 500 milliseconds to 30 seconds, with full jitter.
+    fn default() -> Self {
+        Self::exponential(Duration::from_millis(500), Duration::from_secs(30)).jitter(Jitter::Full)
+    }
+}
+
+impl Backoff {
+    /// Doubles the delay with each retry, from `initial` up to `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            multiplier: 2.0,
+            jitter: Jitter::None,
+        }
+    }
+
+    /// Waits `delay` before each retry.
+    pub fn constant(delay: Duration) -> Self {
+        Self {
+            initial: delay,
+            max: delay,
+            multiplier: 1.0,
+            jitter: Jitter::None,
+        }
+    }
+
+    /// Sets how much the delay grows with each retry.
+    pub fn multiplier(mut self, multiplier: f32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets how the delays are randomized.
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The delay before the retry `retry`, counted from zero, the previous retry having waited
+    /// `previous`.
+    pub fn delay(&self, retry: u32, previous: Option<Duration>) -> Duration {
+        let growth = self.multiplier.max(1.0).powi(retry.min(64) as i32);
+        let delay = self
+            .initial
+            .mul_f64(growth.into())
+            .min(self.max.max(self.initial));
+        match self.jitter {
+            Jitter::None => delay,
+            Jitter::Full => delay.mul_f64(fastrand::f64()),
+            Jitter::Equal => delay / 2 + (delay / 2).mul_f64(fastrand::f64()),
+            Jitter::Decorrelated => {
+                let upper = previous.unwrap_or(self.initial).saturating_mul(3);
+                let spread = upper.saturating_sub(self.initial);
+                (self.initial + spread.mul_f64(fastrand::f64())).min(self.max)
+            }
+        }
+    }
+}
+
+/// The backoffs of the app, configured once and referenced by name from the `RetryPolicy` of
+/// requests.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(
+///     HttpBackoffs::default()
+///         .with(
+///             "matchmaking",
+///             Backoff::exponential(Duration::from_secs(1), Duration::from_secs(60))
+///                 .jitter(Jitter::Decorrelated),
+///         )
+///         .with("telemetry", Backoff::constant(Duration::from_secs(10))),
+/// );
+///
+/// let request = HttpClient::new()
+///     .post("https://example.com/queue")
+///     .retry(RetryPolicy::new(5).backoff("matchmaking"))
+///     .build();
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HttpBackoffs {
+    /// The backoff of the policies that name none, or an unknown one.
+    pub default: Backoff,
+    named: HashMap<String, Backoff>,
+}
+
+impl HttpBackoffs {
+    /// Backoffs with `default` for the policies that name none.
+    pub fn new(default: Backoff) -> Self {
+        Self {
+            default,
+            named: HashMap::new(),
+        }
+    }
+
+    /// Adds the backoff `name`.
+    pub fn with(mut self, name: impl ToString, backoff: Backoff) -> Self {
+        self.insert(name, backoff);
+        self
+    }
+
+    /// Adds or replaces the backoff `name`.
+    pub fn insert(&mut self, name: impl ToString, backoff: Backoff) {
+        self.named.insert(name.to_string(), backoff);
+    }
+
+    /// The backoff `name`, or the default one for `None` or an unknown name.
+    pub fn get(&self, name: Option<&str>) -> &Backoff {
+        let Some(name) = name else {
+            return &self.default;
+        };
+        self.named.get(name).unwrap_or_else(|| {
+            tracing::warn!("unknown backoff {name:?}, using the default one");
+            &self.default
+        })
+    }
+}
+
+/// How a request is retried after a network error or a timeout, see `HttpClient::retry`.
+///
+/// The request is sent again after the delay of its backoff, as a delayed request: it frees its
+/// slot while it waits, and it can be cancelled. Responses with an error status are not retried.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// How many times the request is retried.
+    pub max_retries: u32,
+    /// The name of the backoff in `HttpBackoffs`, the default backoff if `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<String>,
+    /// The retries made so far.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// The delay before the last retry.
+    #[serde(skip)]
+    previous_delay: Option<Duration>,
+}
+
+fn is_zero(retries: &u32) -> bool {
+    *retries == 0
+}
+
+impl RetryPolicy {
+    /// Retries up to `max_retries` times with the default backoff.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Default::default()
+        }
+    }
+
+    /// Waits the delays of the backoff `name` of `HttpBackoffs` between retries.
+    pub fn backoff(mut self, name: impl ToString) -> Self {
+        self.backoff = Some(name.to_string());
+        self
+    }
+
+    /// Counts the next retry, returning its delay, or `None` if no retry is left.
+    pub(crate) fn next_retry(&mut self, backoffs: &HttpBackoffs) -> Option<Duration> {
+        if self.retries >= self.max_retries {
+            return None;
+        }
+        let delay = backoffs
+            .get(self.backoff.as_deref())
+            .delay(self.retries, self.previous_delay);
+        self.retries += 1;
+        self.previous_delay = Some(delay);
+        Some(delay)
+    }
+}
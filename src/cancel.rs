@@ -0,0 +1,124 @@
+use bevy_ecs::prelude::*;
+
+use crate::{
+    dispatch::DispatchRequest, HttpClientSetting, HttpClients, HttpDispatcher, TaskFailure,
+    TaskTags,
+};
+
+/// Cancels the matching requests in flight, queued for a free slot, or held back by a delay or
+/// a debounce, e.g. when unloading a level or logging out.
+///
+/// The slots of the requests in flight are freed right away and their late responses are
+/// dropped, though the transfers themselves run to completion in the background. Cancelled
+/// requests deliver no result, only a `RequestFailed` lifecycle event with
+/// `HttpError::Cancelled`.
+///
+/// Requests sent in the same update as the cancellation are not cancelled.
+///
+/// # Examples
+///
+/// ```
+/// fn log_out(mut commands: Commands) {
+///     commands.cancel_all();
+/// }
+/// ```
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub enum CancelHttpRequests {
+    /// Cancels every request.
+    All,
+    /// Cancels the requests with this label, see `HttpClient::label`.
+    Label(String),
+    /// Cancels the requests sent by the client with this label, see `HttpClient::on_client`.
+    Client(String),
+    /// Cancels the requests made from this entity, see `HttpClient::entity`.
+    Entity(Entity),
+}
+
+impl CancelHttpRequests {
+    /// Whether the request task tagged with `tags` is cancelled.
+    pub(crate) fn matches(&self, tags: &TaskTags) -> bool {
+        match self {
+            Self::All => true,
+            Self::Label(label) => tags.label.as_ref() == Some(label),
+            Self::Client(client) => tags.client.as_ref() == Some(client),
+            Self::Entity(entity) => tags.from_entity == Some(*entity),
+        }
+    }
+
+    pub(crate) fn matches_request(&self, request: &impl DispatchRequest) -> bool {
+        let info = request.info();
+        self.matches(&TaskTags {
+            client: request.client().map(ToString::to_string),
+            label: info.label,
+            from_entity: info.from_entity,
+        })
+    }
+}
+
+/// Cancels requests from systems, see `CancelHttpRequests`.
+pub trait HttpClientCommands {
+    /// Cancels every request.
+    fn cancel_all(&mut self);
+
+    /// Cancels the requests labeled `label` with `HttpClient::label`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn unload_level(mut commands: Commands) {
+    ///     commands.cancel_label("level-assets");
+    /// }
+    /// ```
+    fn cancel_label(&mut self, label: impl ToString);
+
+    /// Cancels the requests sent by the client labeled `client` with `HttpClient::on_client`.
+    fn cancel_client(&mut self, client: impl ToString);
+
+    /// Cancels the requests made from `entity` with `HttpClient::entity`.
+    fn cancel_entity(&mut self, entity: Entity);
+}
+
+impl HttpClientCommands for Commands<'_, '_> {
+    fn cancel_all(&mut self) {
+        self.send_event(CancelHttpRequests::All);
+    }
+
+    fn cancel_label(&mut self, label: impl ToString) {
+        self.send_event(CancelHttpRequests::Label(label.to_string()));
+    }
+
+    fn cancel_client(&mut self, client: impl ToString) {
+        self.send_event(CancelHttpRequests::Client(client.to_string()));
+    }
+
+    fn cancel_entity(&mut self, entity: Entity) {
+        self.send_event(CancelHttpRequests::Entity(entity));
+    }
+}
+
+/// A system that cancels the requests in flight matching the `CancelHttpRequests` events.
+pub(crate) fn cancel_in_flight(
+    mut commands: Commands,
+    mut cancels: EventReader<CancelHttpRequests>,
+    mut req_res: ResMut<HttpClientSetting>,
+    mut clients: ResMut<HttpClients>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+) {
+    for cancel in cancels.read() {
+        let cancelled: Vec<u64> = dispatcher
+            .tasks
+            .iter()
+            .filter(|(_, task)| cancel.matches(&task.tags))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in cancelled {
+            dispatcher.fail(
+                id,
+                TaskFailure::Cancelled,
+                &mut commands,
+                &mut req_res,
+                &mut clients,
+            );
+        }
+    }
+}
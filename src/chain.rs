@@ -0,0 +1,210 @@
+use std::{collections::VecDeque, fmt, sync::Arc};
+
+use bevy_ecs::prelude::*;
+
+use crate::{
+    error::HttpError,
+    lifecycle::RequestFailed,
+    not_modified::HttpNotModified,
+    observe::{HttpObserved, RawResult},
+    HttpRequest, HttpResponse, HttpResponseError,
+};
+
+/// Builds the next request of a chain from the response to the previous one.
+type ChainStep = Arc<dyn Fn(&HttpResponse) -> Result<HttpRequest, HttpError> + Send + Sync>;
+
+/// Requests sent one after the other, each built from the response to the previous one,
+/// completing with a single `ChainComplete`.
+///
+/// Every request goes through the limits, metrics and middleware of its client as usual, but
+/// its result only goes to the chain, it is not sent as an `HttpResponse` or `HttpResponseError`
+/// event. The chain stops at the first error, non-2xx response, or step failing to build its
+/// request. With a label, every request of the chain is labeled with it, so
+/// `HttpClientCommands::cancel_label` cancels the whole chain.
+///
+/// # Examples
+///
+/// ```
+/// fn upload_replay(mut chains: EventWriter<RequestChain>, replay: Res<Replay>) {
+///     let replay = replay.clone();
+///     chains.send(
+///         HttpClient::new()
+///             .post("https://example.com/uploads")
+///             .build()
+///             .then(move |response| {
+///                 let upload: PresignedUpload =
+///                     response.json().map_err(|e| HttpError::Decode(e.to_string()))?;
+///                 Ok(HttpClient::new().put(upload.url).json(&replay).build())
+///             })
+///             .label("replay-upload"),
+///     );
+/// }
+/// ```
+#[derive(Event, Clone)]
+pub struct RequestChain {
+    first: HttpRequest,
+    steps: Vec<ChainStep>,
+    label: Option<String>,
+}
+
+impl RequestChain {
+    /// A chain starting with `request`.
+    pub fn new(request: HttpRequest) -> Self {
+        Self {
+            first: request,
+            steps: vec![],
+            label: None,
+        }
+    }
+
+    /// Sends the request built by `next` from the 2xx response to the previous request.
+    ///
+    /// An error returned by `next` ends the chain with that error.
+    pub fn then(
+        mut self,
+        next: impl Fn(&HttpResponse) -> Result<HttpRequest, HttpError> + Send + Sync + 'static,
+    ) -> Self {
+        self.steps.push(Arc::new(next));
+        self
+    }
+
+    /// Labels every request of the chain, and the `ChainComplete`, with `label`.
+    pub fn label(mut self, label: impl ToString) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+}
+
+impl fmt::Debug for RequestChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestChain")
+            .field("first", &self.first)
+            .field("steps", &self.steps.len())
+            .field("label", &self.label)
+            .finish()
+    }
+}
+
+impl HttpRequest {
+    /// Chains the request built by `next` from the 2xx response to this one, see `RequestChain`.
+    pub fn then(
+        self,
+        next: impl Fn(&HttpResponse) -> Result<HttpRequest, HttpError> + Send + Sync + 'static,
+    ) -> RequestChain {
+        RequestChain::new(self).then(next)
+    }
+}
+
+/// Sent once a `RequestChain` stopped.
+#[derive(Event, Debug, Clone)]
+pub struct ChainComplete {
+    /// label of the chain, see `RequestChain::label`
+    pub label: Option<String>,
+    /// Zero-based index of the request the result is from, or of the step that failed to build
+    /// the next one.
+    pub step: usize,
+    /// The response to the last request, or the error the chain stopped with.
+    pub result: Result<HttpResponse, HttpResponseError>,
+}
+
+/// The steps left of a chain, on the entity its requests are sent from.
+#[derive(Component)]
+pub(crate) struct PendingChain {
+    steps: VecDeque<ChainStep>,
+    label: Option<String>,
+    step: usize,
+    /// Whether the `ChainComplete` was sent, the entity being despawned.
+    complete: bool,
+}
+
+/// A system that sends the first requests of the `RequestChain` events.
+pub(crate) fn handle_request_chains(mut commands: Commands, mut chains: EventReader<RequestChain>) {
+    for chain in chains.read().cloned() {
+        let entity = commands
+            .spawn(PendingChain {
+                steps: chain.steps.into(),
+                label: chain.label.clone(),
+                step: 0,
+                complete: false,
+            })
+            .observe(advance::<HttpResponse>)
+            .observe(advance::<HttpResponseError>)
+            .observe(advance::<HttpNotModified>)
+            .id();
+        commands.send_event(chain_request(chain.first, entity, &chain.label));
+    }
+}
+
+/// Sends `request` from the chain `entity`, with the label of the chain if any.
+fn chain_request(mut request: HttpRequest, entity: Entity, label: &Option<String>) -> HttpRequest {
+    request.from_entity = Some(entity);
+    request.observers_only = true;
+    if label.is_some() {
+        request.label.clone_from(label);
+    }
+    request
+}
+
+/// Sends the next request of the chain after a 2xx response, or completes the chain.
+fn advance<E: RawResult>(
+    trigger: Trigger<HttpObserved<E>>,
+    mut commands: Commands,
+    mut chains: Query<&mut PendingChain>,
+) {
+    let entity = trigger.entity();
+    let Ok(mut chain) = chains.get_mut(entity) else {
+        return;
+    };
+    if chain.complete {
+        return;
+    }
+    let result = (**trigger.event()).clone().into_result();
+    let result = match (result, chain.steps.pop_front()) {
+        (Ok(response), Some(next)) if response.ok => match next(&response) {
+            Ok(request) => {
+                chain.step += 1;
+                commands.send_event(chain_request(request, entity, &chain.label));
+                return;
+            }
+            Err(e) => Err(HttpResponseError::new(e).label(chain.label.clone())),
+        },
+        (result, _) => result,
+    };
+    complete(&mut commands, entity, &mut chain, result);
+}
+
+/// A system that completes the chains whose request was cancelled, which delivers no result.
+pub(crate) fn complete_cancelled_chains(
+    mut commands: Commands,
+    mut failed: EventReader<RequestFailed>,
+    mut chains: Query<&mut PendingChain>,
+) {
+    for event in failed.read() {
+        let (HttpError::Cancelled(_), Some(entity)) = (&event.error, event.from_entity) else {
+            continue;
+        };
+        let Ok(mut chain) = chains.get_mut(entity) else {
+            continue;
+        };
+        if !chain.complete {
+            let error = HttpResponseError::new(event.error.clone()).label(event.label.clone());
+            complete(&mut commands, entity, &mut chain, Err(error));
+        }
+    }
+}
+
+/// Sends the `ChainComplete` of the chain on `entity` and despawns it.
+fn complete(
+    commands: &mut Commands,
+    entity: Entity,
+    chain: &mut PendingChain,
+    result: Result<HttpResponse, HttpResponseError>,
+) {
+    chain.complete = true;
+    commands.send_event(ChainComplete {
+        label: chain.label.take(),
+        step: chain.step,
+        result,
+    });
+    commands.entity(entity).despawn();
+}
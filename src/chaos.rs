@@ -0,0 +1,158 @@
+use std::{
+    ops::ControlFlow,
+    sync::{Arc, Mutex},
+};
+
+use bevy_utils::Duration;
+use ehttp::{Request, Response};
+
+use crate::middleware::ResponseMiddleware;
+
+/// Response middleware simulating a bad network, to playtest loading screens and error handling.
+///
+/// Each response is delayed by the latency, a random jitter and the time its body takes at the
+/// bandwidth cap, blocking the task thread. Some responses are then replaced by a network error,
+/// or have their body corrupted, at the configured rates. Delays are ignored on wasm, where tasks
+/// cannot block.
+///
+/// Simulated failures happen after the request was sent, so they are not retried. Register it
+/// in development builds only.
+///
+/// # Examples
+///
+/// ```
+/// #[cfg(debug_assertions)]
+/// app.add_response_middleware(
+///     HttpChaos::new()
+///         .latency(Duration::from_millis(300))
+///         .jitter(Duration::from_millis(200))
+///         .failure_rate(0.1),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpChaos {
+    latency: Duration,
+    jitter: Duration,
+    bandwidth: Option<u64>,
+    failure_rate: f64,
+    malformed_rate: f64,
+    rng: Arc<Mutex<fastrand::Rng>>,
+}
+
+impl Default for HttpChaos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpChaos {
+    /// Creates a middleware leaving responses untouched until configured.
+    pub fn new() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bandwidth: None,
+            failure_rate: 0.0,
+            malformed_rate: 0.0,
+            rng: Arc::new(Mutex::new(fastrand::Rng::new())),
+        }
+    }
+
+    /// Conditions of a slow mobile connection: 400ms latency, 200ms jitter, 50KB/s and 2% of
+    /// failures.
+    pub fn slow_mobile() -> Self {
+        Self::new()
+            .latency(Duration::from_millis(400))
+            .jitter(Duration::from_millis(200))
+            .bandwidth(50 * 1024)
+            .failure_rate(0.02)
+    }
+
+    /// Conditions of an unreliable connection: 100ms latency, 500ms jitter, 20% of failures and
+    /// 5% of malformed bodies.
+    pub fn flaky() -> Self {
+        Self::new()
+            .latency(Duration::from_millis(100))
+            .jitter(Duration::from_millis(500))
+            .failure_rate(0.2)
+            .malformed_rate(0.05)
+    }
+
+    /// Delays every response by `latency`.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Delays every response by a random duration up to `jitter`, on top of the latency.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Caps the download speed to `bytes_per_second`, delaying responses by their body size.
+    pub fn bandwidth(mut self, bytes_per_second: u64) -> Self {
+        self.bandwidth = Some(bytes_per_second.max(1));
+        self
+    }
+
+    /// Replaces a `rate` fraction of the responses, between 0 and 1, by a network error.
+    pub fn failure_rate(mut self, rate: f64) -> Self {
+        self.failure_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Corrupts the body of a `rate` fraction of the responses, between 0 and 1, by truncating
+    /// it and inserting invalid UTF-8.
+    pub fn malformed_rate(mut self, rate: f64) -> Self {
+        self.malformed_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Seeds the random generator, to replay the same sequence of conditions.
+    pub fn seed(self, seed: u64) -> Self {
+        *self.rng.lock().unwrap() = fastrand::Rng::with_seed(seed);
+        self
+    }
+
+    /// How long the response of `bytes` is delayed.
+    fn delay(&self, rng: &mut fastrand::Rng, bytes: usize) -> Duration {
+        let jitter = self.jitter.mul_f64(rng.f64());
+        let transfer = self.bandwidth.map_or(Duration::ZERO, |bandwidth| {
+            Duration::from_secs_f64(bytes as f64 / bandwidth as f64)
+        });
+        self.latency + jitter + transfer
+    }
+}
+
+impl ResponseMiddleware for HttpChaos {
+    fn handle_response(
+        &self,
+        _request: &Request,
+        result: &mut ehttp::Result<Response>,
+        _elapsed: Duration,
+    ) -> ControlFlow<()> {
+        let mut rng = self.rng.lock().unwrap();
+        let bytes = result.as_ref().map_or(0, |response| response.bytes.len());
+        let delay = self.delay(&mut rng, bytes);
+        let fail = rng.f64() < self.failure_rate;
+        let malform = rng.f64() < self.malformed_rate;
+        let cut = rng.usize(..=bytes);
+        drop(rng);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = delay;
+
+        if fail {
+            *result = Err("chaos: simulated network failure".to_string());
+        } else if let (true, Ok(response)) = (malform, result.as_mut()) {
+            response.bytes.truncate(cut);
+            response.bytes.push(0xff);
+        }
+        ControlFlow::Continue(())
+    }
+}
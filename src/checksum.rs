@@ -0,0 +1,129 @@
+use base64::Engine;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::HttpClient;
+
+/// A digest of the request body sent in a header, for servers checking the integrity of uploads,
+/// e.g. S3-compatible storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BodyChecksum {
+    /// The base64 MD5 digest in "Content-MD5", see RFC 1864.
+    ContentMd5,
+    /// The base64 SHA-256 digest in "x-amz-checksum-sha256".
+    AmzChecksumSha256,
+    /// The hex SHA-256 digest in "x-amz-content-sha256", as signed by AWS Signature Version 4.
+    AmzContentSha256,
+}
+
+impl BodyChecksum {
+    /// The name of the header.
+    pub fn header(self) -> &'static str {
+        match self {
+            Self::ContentMd5 => "Content-MD5",
+            Self::AmzChecksumSha256 => "x-amz-checksum-sha256",
+            Self::AmzContentSha256 => "x-amz-content-sha256",
+        }
+    }
+
+    /// The value of the header for `body`.
+    pub fn compute(self, body: &[u8]) -> String {
+        let base64 = base64::engine::general_purpose::STANDARD;
+        match self {
+            Self::ContentMd5 => base64.encode(Md5::digest(body)),
+            Self::AmzChecksumSha256 => base64.encode(Sha256::digest(body)),
+            Self::AmzContentSha256 => Sha256::digest(body)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+        }
+    }
+}
+
+impl HttpClient {
+    /// Sends the `checksum` of the body in its header, computed when the request is built, so
+    /// the body can be set before or after.
+    ///
+    /// The header replaces one of the same name set with `header`. Bodies streamed with
+    /// `body_from_file` are not read when the request is built, and get no checksum.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .put("https://bucket.s3.example.com/saves/1.sav")
+    ///     .body_bytes(save)
+    ///     .body_checksum(BodyChecksum::ContentMd5)
+    ///     .body_checksum(BodyChecksum::AmzChecksumSha256)
+    ///     .build();
+    /// ```
+    pub fn body_checksum(mut self, checksum: BodyChecksum) -> Self {
+        if !self.checksums.contains(&checksum) {
+            self.checksums.push(checksum);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_matches_known_digests() {
+        assert_eq!(
+            BodyChecksum::ContentMd5.compute(b"hello"),
+            "XUFAKrxLKna5cZ2REBfFkg=="
+        );
+        assert_eq!(
+            BodyChecksum::AmzChecksumSha256.compute(b""),
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert_eq!(
+            BodyChecksum::AmzContentSha256.compute(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn build_sends_the_checksum_of_the_body() {
+        let request = HttpClient::new()
+            .body_checksum(BodyChecksum::ContentMd5)
+            .put("https://example.com/saves/1.sav")
+            .body_bytes(b"hello".to_vec())
+            .build();
+        assert_eq!(
+            request.request.headers.get("Content-MD5"),
+            Some("XUFAKrxLKna5cZ2REBfFkg==")
+        );
+    }
+
+    #[test]
+    fn build_replaces_a_checksum_header_set_by_hand() {
+        let request = HttpClient::new()
+            .put("https://example.com/saves/1.sav")
+            .header("content-md5", "stale")
+            .body_bytes(b"hello".to_vec())
+            .body_checksum(BodyChecksum::ContentMd5)
+            .build();
+        let values: Vec<_> = request
+            .request
+            .headers
+            .headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("Content-MD5"))
+            .map(|(_, value)| value.as_str())
+            .collect();
+        assert_eq!(values, ["XUFAKrxLKna5cZ2REBfFkg=="]);
+    }
+
+    #[test]
+    fn file_bodies_get_no_checksum() {
+        let request = HttpClient::new()
+            .put("https://example.com/saves/1.sav")
+            .body_from_file("saves/1.sav")
+            .body_checksum(BodyChecksum::ContentMd5)
+            .build();
+        assert_eq!(request.request.headers.get("Content-MD5"), None);
+    }
+}
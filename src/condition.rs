@@ -0,0 +1,56 @@
+use bevy_ecs::prelude::{Event, EventReader, Res};
+use serde::Deserialize;
+
+use crate::{
+    typed::{TypedResponse, TypedResponseError},
+    HttpClientSetting, HttpClients, HttpResponse, HttpResponseError,
+};
+
+/// Runs the system when an `HttpResponse` was received since it last ran.
+pub fn on_http_response() -> impl FnMut(EventReader<HttpResponse>) -> bool + Clone {
+    received::<HttpResponse>()
+}
+
+/// Runs the system when an `HttpResponseError` was received since it last ran.
+pub fn on_http_error() -> impl FnMut(EventReader<HttpResponseError>) -> bool + Clone {
+    received::<HttpResponseError>()
+}
+
+/// Runs the system when a `TypedResponse<T>` was received since it last ran.
+///
+/// # Examples
+///
+/// ```
+/// app.add_systems(
+///     Update,
+///     (
+///         show_ip.run_if(on_typed_response::<IpInfo>()),
+///         spin_loading_icon.run_if(any_request_in_flight),
+///     ),
+/// );
+/// ```
+pub fn on_typed_response<T>() -> impl FnMut(EventReader<TypedResponse<T>>) -> bool + Clone
+where
+    T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    received::<TypedResponse<T>>()
+}
+
+/// Runs the system when a `TypedResponseError<T>` was received since it last ran.
+pub fn on_typed_error<T>() -> impl FnMut(EventReader<TypedResponseError<T>>) -> bool + Clone
+where
+    TypedResponseError<T>: Event,
+{
+    received::<TypedResponseError<T>>()
+}
+
+/// Runs the system while at least one request is in flight, on any client.
+pub fn any_request_in_flight(setting: Res<HttpClientSetting>, clients: Res<HttpClients>) -> bool {
+    setting.in_flight() + clients.in_flight() > 0
+}
+
+/// Runs the system when an `E` was received since it last ran.
+fn received<E: Event>() -> impl FnMut(EventReader<E>) -> bool + Clone {
+    // consume the events so each one triggers the system once
+    |mut reader: EventReader<E>| reader.read().count() > 0
+}
@@ -0,0 +1,263 @@
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+use bevy_utils::{Duration, Instant};
+use ehttp::{Headers, Request};
+
+use crate::join_url;
+
+/// Default client settings of an entity, merged into every request originating from it.
+///
+/// The base URL can be balanced over several mirrors of the API, see `mirror`.
+///
+/// Settings of the request itself take precedence: a header set on the request is kept, an
+/// absolute URL is not resolved against the base URL and an explicit timeout is not replaced. The
+/// headers replace the defaults of the crate though, e.g. `Accept: */*`, which are only added to
+/// the requests that set neither.
+///
+/// # Examples
+///
+/// ```
+/// commands.spawn((
+///     ApiConnection,
+///     HttpClientConfig::new()
+///         .base_url("https://example.com/api")
+///         .header("Authorization", "Bearer token")
+///         .timeout(Duration::from_secs(5))
+///         .retries(2),
+/// ));
+///
+/// // requests https://example.com/api/status with the headers, timeout and retries above
+/// ev_request.send(HttpClient::new().entity(connection).get("/status").build());
+/// ```
+#[derive(Component, Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    /// Base URL that relative request URLs are resolved against.
+    pub base_url: Option<String>,
+    /// Headers added to requests that do not set them.
+    pub headers: Headers,
+    /// Timeout of requests that do not set one.
+    pub timeout: Option<Duration>,
+    /// How many times a request is retried after a network error.
+    pub retries: u32,
+    /// Base URLs balanced instead of `base_url`, see `mirror`.
+    mirrors: Mirrors,
+}
+
+impl HttpClientConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the base URL that relative request URLs are resolved against.
+    pub fn base_url(mut self, base_url: impl ToString) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Adds a header to requests that do not set it.
+    pub fn header(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Sets the timeout of requests that do not set one.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many times a request is retried after a network error.
+    ///
+    /// Responses with an error status are not retried.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Adds a mirror of the API, relative request URLs being resolved against one of the mirrors
+    /// instead of the base URL.
+    ///
+    /// Requests are spread over the mirrors in proportion to their `weight`, in a smooth round
+    /// robin. A mirror whose request failed with a network error or a timeout is left out for the
+    /// cooldown, 30 seconds by default, unless all mirrors are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// commands.spawn((
+    ///     ApiConnection,
+    ///     HttpClientConfig::new()
+    ///         .mirror("https://eu.api.example.com", 3)
+    ///         .mirror("https://us.api.example.com", 1)
+    ///         .mirror_cooldown(Duration::from_secs(60)),
+    /// ));
+    /// ```
+    pub fn mirror(mut self, base_url: impl ToString, weight: u32) -> Self {
+        self.mirrors
+            .urls
+            .push((base_url.to_string(), weight.max(1)));
+        self
+    }
+
+    /// Sets how long a mirror is left out after a failed request.
+    pub fn mirror_cooldown(mut self, cooldown: Duration) -> Self {
+        self.mirrors.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Merges these settings into `request` and `timeout`.
+    ///
+    /// Returns the mirror the request is sent to, if any, to report its failures to.
+    pub(crate) fn apply(
+        &self,
+        request: &mut Request,
+        timeout: &mut Option<Duration>,
+        now: Instant,
+    ) -> Option<MirrorPick> {
+        let mut pick = None;
+        if request.url.contains("://") {
+            // absolute URLs are kept as they are
+        } else if let Some(index) = self.mirrors.pick(now) {
+            let base_url = &self.mirrors.urls[index].0;
+            request.url = join_url(base_url, std::mem::take(&mut request.url));
+            pick = Some(MirrorPick {
+                mirrors: self.mirrors.clone(),
+                index,
+            });
+        } else if let Some(base_url) = &self.base_url {
+            request.url = join_url(base_url, std::mem::take(&mut request.url));
+        }
+        for (key, value) in &self.headers {
+            if request.headers.get(key).is_none() {
+                request.headers.insert(key, value);
+            }
+        }
+        if timeout.is_none() {
+            *timeout = self.timeout;
+        }
+        pick
+    }
+}
+
+/// The mirrors of an `HttpClientConfig`, sharing their balancing state between clones.
+#[derive(Debug, Clone, Default)]
+struct Mirrors {
+    /// The base URLs and their weights.
+    urls: Vec<(String, u32)>,
+    cooldown: Option<Duration>,
+    state: Arc<Mutex<MirrorState>>,
+}
+
+#[derive(Debug, Default)]
+struct MirrorState {
+    /// The current weights of the smooth weighted round robin.
+    current: Vec<i64>,
+    /// Until when each mirror is left out.
+    unhealthy_until: Vec<Option<Instant>>,
+}
+
+impl Mirrors {
+    const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+    /// Picks the mirror of the next request, among the healthy ones if any.
+    fn pick(&self, now: Instant) -> Option<usize> {
+        if self.urls.is_empty() {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.current.resize(self.urls.len(), 0);
+        state.unhealthy_until.resize(self.urls.len(), None);
+        let healthy: Vec<usize> = (0..self.urls.len())
+            .filter(|i| state.unhealthy_until[*i].is_none_or(|until| until <= now))
+            .collect();
+        let candidates = if healthy.is_empty() {
+            (0..self.urls.len()).collect()
+        } else {
+            healthy
+        };
+        let mut total = 0;
+        for &i in &candidates {
+            let weight = i64::from(self.urls[i].1);
+            state.current[i] += weight;
+            total += weight;
+        }
+        let best = candidates
+            .into_iter()
+            .max_by_key(|i| (state.current[*i], std::cmp::Reverse(*i)))?;
+        state.current[best] -= total;
+        Some(best)
+    }
+}
+
+/// The mirror a request was sent to.
+#[derive(Clone)]
+pub(crate) struct MirrorPick {
+    mirrors: Mirrors,
+    index: usize,
+}
+
+impl MirrorPick {
+    /// Leaves the mirror out for the cooldown, after a network error or a timeout at `now`.
+    pub(crate) fn report_failure(&self, now: Instant) {
+        let cooldown = self.mirrors.cooldown.unwrap_or(Mirrors::DEFAULT_COOLDOWN);
+        let mut state = self.mirrors.state.lock().unwrap();
+        if let Some(until) = state.unhealthy_until.get_mut(self.index) {
+            *until = Some(now + cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picks(config: &HttpClientConfig, now: Instant, count: usize) -> Vec<usize> {
+        (0..count)
+            .map(|_| config.mirrors.pick(now).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn mirrors_are_picked_in_proportion_to_their_weight() {
+        let config = HttpClientConfig::new()
+            .mirror("https://eu.example.com", 3)
+            .mirror("https://us.example.com", 1);
+        let picks = picks(&config, Instant::now(), 8);
+        assert_eq!(picks.iter().filter(|&&i| i == 0).count(), 6);
+        // smooth round robin, the light mirror is not starved until the end
+        assert_eq!(&picks[..4], &[0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn failed_mirror_is_left_out_for_the_cooldown() {
+        let config = HttpClientConfig::new()
+            .mirror("https://eu.example.com", 1)
+            .mirror("https://us.example.com", 1)
+            .mirror_cooldown(Duration::from_secs(10));
+        let now = Instant::now();
+        let mut request = Request::get("/status");
+        let pick = config.apply(&mut request, &mut None, now).unwrap();
+        assert_eq!(request.url, "https://eu.example.com/status");
+        pick.report_failure(now);
+        assert_eq!(picks(&config, now, 3), vec![1, 1, 1]);
+        // back once the cooldown is over
+        let later = now + Duration::from_secs(10);
+        assert!(picks(&config, later, 4).contains(&0));
+    }
+
+    #[test]
+    fn request_settings_take_precedence() {
+        let config = HttpClientConfig::new()
+            .base_url("https://example.com/api")
+            .header("Authorization", "Bearer token")
+            .timeout(Duration::from_secs(5));
+        let mut request = Request::get("https://other.com/x");
+        request.headers.insert("Authorization", "Basic abc");
+        let mut timeout = Some(Duration::from_secs(1));
+        config.apply(&mut request, &mut timeout, Instant::now());
+        assert_eq!(request.url, "https://other.com/x");
+        assert_eq!(request.headers.get("Authorization"), Some("Basic abc"));
+        assert_eq!(timeout, Some(Duration::from_secs(1)));
+    }
+}
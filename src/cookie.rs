@@ -0,0 +1,96 @@
+use ehttp::{Headers, Response};
+
+/// A cookie set by a `Set-Cookie` response header.
+///
+/// Browsers hide `Set-Cookie` headers from pages, so responses have no cookies on wasm.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// The `Expires` attribute, as the HTTP date it is written as.
+    pub expires: Option<String>,
+    /// The `Max-Age` attribute, in seconds. Zero or less removes the cookie.
+    pub max_age: Option<i64>,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    pub secure: bool,
+    pub http_only: bool,
+    /// The `SameSite` attribute, e.g. `Strict` or `Lax`.
+    pub same_site: Option<String>,
+    /// Other attributes, such as `Partitioned`, with an empty value when they have none.
+    pub attributes: Vec<(String, String)>,
+}
+
+/// Access to the cookies set by a response.
+///
+/// The cookies are parsed from the headers of the response, whether or not anything stores them.
+///
+/// # Examples
+///
+/// ```
+/// fn handle_login(mut ev_response: EventReader<HttpResponse>, mut session: ResMut<Session>) {
+///     for response in ev_response.read() {
+///         if let Some(cookie) = response.cookie("session") {
+///             session.token = cookie.value;
+///         }
+///     }
+/// }
+/// ```
+pub trait ResponseCookies {
+    /// Returns the cookies of the `Set-Cookie` headers, in order.
+    fn cookies(&self) -> Vec<Cookie>;
+
+    /// Returns the last cookie named `name`, the one a client would keep.
+    fn cookie(&self, name: &str) -> Option<Cookie> {
+        self.cookies()
+            .into_iter()
+            .rev()
+            .find(|cookie| cookie.name == name)
+    }
+}
+
+impl ResponseCookies for Response {
+    fn cookies(&self) -> Vec<Cookie> {
+        parse_set_cookie_headers(&self.headers)
+    }
+}
+
+/// Parses RFC 6265 `Set-Cookie` headers, skipping those without a cookie name.
+pub(crate) fn parse_set_cookie_headers(headers: &Headers) -> Vec<Cookie> {
+    headers
+        .get_all("set-cookie")
+        .filter_map(parse_set_cookie)
+        .collect()
+}
+
+fn parse_set_cookie(value: &str) -> Option<Cookie> {
+    let mut parts = value.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().to_string(),
+        ..Default::default()
+    };
+    for attribute in parts {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let (key, value) = (key.trim(), value.trim());
+        match key.to_ascii_lowercase().as_str() {
+            "" => {}
+            "expires" => cookie.expires = Some(value.to_string()),
+            // an invalid Max-Age is ignored, as RFC 6265 requires
+            "max-age" => cookie.max_age = value.parse().ok().or(cookie.max_age),
+            "domain" => cookie.domain = Some(value.trim_start_matches('.').to_string()),
+            "path" => cookie.path = Some(value.to_string()),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" => cookie.same_site = Some(value.to_string()),
+            _ => cookie.attributes.push((key.to_string(), value.to_string())),
+        }
+    }
+    Some(cookie)
+}
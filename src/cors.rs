@@ -0,0 +1,162 @@
+use ehttp::Response;
+
+use crate::HttpClient;
+
+/// What a server allows cross-origin requests to do, from the `Access-Control-*` headers of its
+/// response to a CORS preflight, see `HttpClient::cors_preflight`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorsPolicy {
+    /// The `Access-Control-Allow-Origin` header, an origin or "*".
+    pub allow_origin: Option<String>,
+    /// The methods of `Access-Control-Allow-Methods`, uppercase.
+    pub allow_methods: Vec<String>,
+    /// The headers of `Access-Control-Allow-Headers`, lowercase.
+    pub allow_headers: Vec<String>,
+    /// The headers of `Access-Control-Expose-Headers` pages can read, lowercase.
+    pub expose_headers: Vec<String>,
+    /// Whether `Access-Control-Allow-Credentials` is "true".
+    pub allow_credentials: bool,
+    /// The `Access-Control-Max-Age` header, in seconds.
+    pub max_age: Option<u64>,
+}
+
+impl CorsPolicy {
+    /// Whether pages from `origin`, e.g. "https://game.example.com", may read the responses.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allow_origin
+            .as_deref()
+            .is_some_and(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// Whether cross-origin requests may use `method`.
+    ///
+    /// GET, HEAD and POST are always allowed.
+    pub fn allows_method(&self, method: &str) -> bool {
+        ["GET", "HEAD", "POST"]
+            .iter()
+            .any(|simple| simple.eq_ignore_ascii_case(method))
+            || self
+                .allow_methods
+                .iter()
+                .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(method))
+    }
+
+    /// Whether cross-origin requests may set the header `name`.
+    ///
+    /// As in browsers, "*" allows any header but `Authorization`, which must be listed.
+    pub fn allows_header(&self, name: &str) -> bool {
+        self.allow_headers.iter().any(|allowed| {
+            (allowed == "*" && !name.eq_ignore_ascii_case("authorization"))
+                || allowed.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Lists why a request with `method` and `headers` from pages of `origin` would be blocked,
+    /// empty if it would not.
+    pub fn blocked(&self, origin: &str, method: &str, headers: &[&str]) -> Vec<String> {
+        let mut reasons = vec![];
+        if !self.allows_origin(origin) {
+            reasons.push(format!(
+                "origin {origin} is not allowed, Access-Control-Allow-Origin is {:?}",
+                self.allow_origin
+            ));
+        }
+        if !self.allows_method(method) {
+            reasons.push(format!("method {method} is not allowed"));
+        }
+        for header in headers.iter().filter(|header| !self.allows_header(header)) {
+            reasons.push(format!("header {header} is not allowed"));
+        }
+        reasons
+    }
+}
+
+/// Access to the CORS policy advertised by a response.
+pub trait ResponseCors {
+    /// Returns the policy of the `Access-Control-*` headers.
+    fn cors_policy(&self) -> CorsPolicy;
+}
+
+impl ResponseCors for Response {
+    fn cors_policy(&self) -> CorsPolicy {
+        let list = |name: &str, normalize: fn(&str) -> String| {
+            self.headers
+                .get_all(name)
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(normalize)
+                .collect()
+        };
+        CorsPolicy {
+            allow_origin: self
+                .headers
+                .get("Access-Control-Allow-Origin")
+                .map(|origin| origin.trim().to_string()),
+            allow_methods: list("Access-Control-Allow-Methods", str::to_uppercase),
+            allow_headers: list("Access-Control-Allow-Headers", str::to_lowercase),
+            expose_headers: list("Access-Control-Expose-Headers", str::to_lowercase),
+            allow_credentials: self
+                .headers
+                .get("Access-Control-Allow-Credentials")
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case("true")),
+            max_age: self
+                .headers
+                .get("Access-Control-Max-Age")
+                .and_then(|value| value.trim().parse().ok()),
+        }
+    }
+}
+
+impl HttpClient {
+    /// Turns the builder into the CORS preflight a browser sends before a `method` request to
+    /// `url` with `headers` from pages of `origin`, to find out why a wasm build fails to fetch.
+    ///
+    /// Browsers do not let pages send preflights themselves, so the probe is sent from a native
+    /// build. The `CorsPolicy` of the response tells what the server allows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn probe(mut ev_request: EventWriter<HttpRequest>) {
+    ///     ev_request.send(
+    ///         HttpClient::new()
+    ///             .cors_preflight(
+    ///                 "https://api.example.com/scores",
+    ///                 "https://game.example.com",
+    ///                 "PUT",
+    ///                 ["content-type", "authorization"],
+    ///             )
+    ///             .build(),
+    ///     );
+    /// }
+    ///
+    /// fn report(mut ev_response: EventReader<HttpResponse>) {
+    ///     for response in ev_response.read() {
+    ///         let policy = response.cors_policy();
+    ///         let headers = ["content-type", "authorization"];
+    ///         for reason in policy.blocked("https://game.example.com", "PUT", &headers) {
+    ///             warn!("CORS: {reason}");
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn cors_preflight(
+        self,
+        url: impl ToString,
+        origin: impl ToString,
+        method: impl ToString,
+        headers: impl IntoIterator<Item = impl ToString>,
+    ) -> Self {
+        let headers: Vec<String> = headers.into_iter().map(|h| h.to_string()).collect();
+        let client = self
+            .options(url)
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", method);
+        if headers.is_empty() {
+            client
+        } else {
+            client.header("Access-Control-Request-Headers", headers.join(", "))
+        }
+    }
+}
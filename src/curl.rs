@@ -0,0 +1,89 @@
+use ehttp::Request;
+
+use crate::history::HttpRecord;
+
+const REDACTED: &str = "<redacted>";
+
+/// Options of `HttpRecord::to_curl`.
+#[derive(Debug, Clone)]
+pub struct CurlOptions {
+    /// Names of the headers whose values are redacted, compared case-insensitively.
+    pub redacted_headers: Vec<String>,
+    /// Whether the request body is included.
+    pub include_body: bool,
+}
+
+impl Default for CurlOptions {
+    fn default() -> Self {
+        Self {
+            redacted_headers: ["authorization", "proxy-authorization", "cookie"]
+                .map(String::from)
+                .to_vec(),
+            include_body: true,
+        }
+    }
+}
+
+impl CurlOptions {
+    /// Keeps all header values, e.g. to reproduce an authenticated request locally.
+    pub fn unredacted() -> Self {
+        Self {
+            redacted_headers: vec![],
+            ..Default::default()
+        }
+    }
+
+    /// Redacts the value of the header `name`.
+    pub fn redact_header(mut self, name: impl ToString) -> Self {
+        self.redacted_headers.push(name.to_string());
+        self
+    }
+
+    /// Sets whether the request body is included.
+    pub fn include_body(mut self, enabled: bool) -> Self {
+        self.include_body = enabled;
+        self
+    }
+}
+
+impl HttpRecord {
+    /// Returns an equivalent `curl` command, with the credential headers redacted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn print_failed(history: Res<HttpHistory>) {
+    ///     for record in history.records().filter(|record| record.error().is_some()) {
+    ///         println!("{}", record.to_curl(&CurlOptions::default()));
+    ///     }
+    /// }
+    /// ```
+    pub fn to_curl(&self, options: &CurlOptions) -> String {
+        request_to_curl(&self.request, options)
+    }
+}
+
+/// Formats `request` as a `curl` command line for a POSIX shell.
+pub(crate) fn request_to_curl(request: &Request, options: &CurlOptions) -> String {
+    let mut command = format!("curl -X {} {}", request.method, shell_quote(&request.url));
+    for (key, value) in &request.headers {
+        let redacted = options
+            .redacted_headers
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(key));
+        let value = if redacted { REDACTED } else { value };
+        command += &format!(" -H {}", shell_quote(&format!("{key}: {value}")));
+    }
+    if options.include_body && !request.body.is_empty() {
+        command += &format!(
+            " --data-raw {}",
+            shell_quote(&String::from_utf8_lossy(&request.body))
+        );
+    }
+    command
+}
+
+/// Quotes `value` as a single shell word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
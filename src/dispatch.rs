@@ -0,0 +1,389 @@
+use std::path::PathBuf;
+
+use bevy_ecs::{prelude::*, world::CommandQueue};
+use bevy_utils::{tracing, Duration, HashMap, Instant};
+use ehttp::{Request, Response};
+
+use crate::{
+    attach_request_id,
+    backoff::HttpBackoffs,
+    cancel::CancelHttpRequests,
+    config::HttpClientConfig,
+    endpoint::EndpointInfo,
+    error::HttpError,
+    lifecycle::{RequestCompleted, RequestFailed, RequestInfo, RequestQueued, RequestStarted},
+    middleware::{FetchOptions, HttpMiddleware},
+    observe::Recipient,
+    rate_limit::RateLimitState,
+    transport::{FileBody, RequestProxy},
+    typed::ResponseEnvelopes,
+    HttpClientSetting, HttpClients, HttpClock, HttpDispatcher, RequestCoalescing, RequestDelay,
+    TaskFailure, TaskTags,
+};
+
+/// A request event sent by `dispatch_requests`.
+pub(crate) trait DispatchRequest: Event + Clone {
+    /// How the result of the request is delivered.
+    type Route: ResponseRoute;
+
+    /// The label of the client sending the request, the default client if `None`.
+    fn client(&self) -> Option<&str>;
+
+    /// Takes the delay of the request, leaving it due right away.
+    fn take_delay(&mut self) -> Option<RequestDelay>;
+
+    /// Takes the debounce or throttle of the request.
+    fn take_coalescing(&mut self) -> Option<RequestCoalescing>;
+
+    /// The request to send again after a network error, delayed by its backoff, or `None` if
+    /// it has no retry left.
+    fn retry(&self, backoffs: &HttpBackoffs) -> Option<Self>;
+
+    /// The request as described by the lifecycle events.
+    fn info(&self) -> RequestInfo;
+
+    /// Splits the request into what is fetched and the route of its result.
+    fn into_dispatch(self) -> (Dispatch, Self::Route);
+}
+
+/// Delivers the result of a dispatched request to the world.
+pub(crate) trait ResponseRoute: Clone + Send + Sync + 'static {
+    /// Prepares the delivery of `result` in the task of the request, off the main thread, e.g.
+    /// deserializes a typed body.
+    fn decode(&mut self, _result: &Result<Response, HttpError>, _envelopes: &ResponseEnvelopes) {}
+
+    /// Delivers the response, or the error the request failed with, e.g. a network error or a
+    /// timeout, `duration` after the request started.
+    fn deliver(
+        self,
+        world: &mut World,
+        recipient: Recipient,
+        result: Result<Response, HttpError>,
+        request_id: Option<String>,
+        duration: Duration,
+    );
+}
+
+/// The requests of type `R` held back by `dispatch_requests`.
+pub(crate) struct HeldRequests<R> {
+    /// The delayed and debounced requests, with when they are due and their debounce key.
+    delayed: Vec<(Instant, Option<String>, R)>,
+    /// When the throttle window of each key ends.
+    throttled: HashMap<String, Instant>,
+}
+
+impl<R> Default for HeldRequests<R> {
+    fn default() -> Self {
+        Self {
+            delayed: Vec::new(),
+            throttled: HashMap::new(),
+        }
+    }
+}
+
+impl<R: DispatchRequest> HeldRequests<R> {
+    /// Holds `request`, read at `now`, until it is due, or drops it if it is throttled.
+    fn hold(&mut self, mut request: R, now: Instant) {
+        let due = request.take_delay().map_or(now, |delay| delay.due(now));
+        match request.take_coalescing() {
+            Some(RequestCoalescing::Debounce(window)) => {
+                let key = coalescing_key(&request);
+                // the latest request replaces the pending one and restarts the window
+                self.delayed
+                    .retain(|(_, pending, _)| pending.as_ref() != Some(&key));
+                self.delayed.push((due + window, Some(key), request));
+            }
+            Some(RequestCoalescing::Throttle(window)) => {
+                let key = coalescing_key(&request);
+                if self.throttled.get(&key).is_some_and(|end| now < *end) {
+                    return;
+                }
+                self.throttled.insert(key, now + window);
+                self.delayed.push((due, None, request));
+            }
+            None => self.delayed.push((due, None, request)),
+        }
+    }
+
+    /// Takes the held requests matching `cancel`.
+    fn cancel(&mut self, cancel: &CancelHttpRequests) -> Vec<R> {
+        let (cancelled, kept) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|(_, _, request)| cancel.matches_request(request));
+        self.delayed = kept;
+        cancelled
+            .into_iter()
+            .map(|(_, _, request)| request)
+            .collect()
+    }
+
+    /// Takes the requests due at `now`, in the order they were held.
+    fn take_due(&mut self, now: Instant) -> Vec<R> {
+        self.throttled.retain(|_, end| now < *end);
+        let (due, later) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|(due, _, _)| *due <= now);
+        self.delayed = later;
+        due.into_iter().map(|(_, _, request)| request).collect()
+    }
+}
+
+/// The key requests are debounced and throttled by, their label or else their method and URL.
+fn coalescing_key(request: &impl DispatchRequest) -> String {
+    let info = request.info();
+    info.label
+        .unwrap_or_else(|| format!("{} {}", info.method, info.url))
+}
+
+/// What `dispatch_requests` fetches for a request.
+pub(crate) struct Dispatch {
+    pub(crate) request: Request,
+    pub(crate) from_entity: Option<Entity>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) observers_only: bool,
+    pub(crate) endpoint: Option<EndpointInfo>,
+    pub(crate) client: Option<String>,
+    pub(crate) fallback_urls: Vec<String>,
+    pub(crate) proxy: Option<RequestProxy>,
+    pub(crate) body_file: Option<PathBuf>,
+}
+
+/// A system that sends the requests of type `R`, or queues them while their client has no free
+/// slot, or while the `RateLimitState` of their host paces them.
+///
+/// Delayed and debounced requests are held back until they are due, then handled like new
+/// requests. Throttled requests are dropped, and so are the queued and held requests matching a
+/// `CancelHttpRequests` event.
+///
+/// The request events are drained rather than read, so their bodies are moved into the request
+/// tasks instead of copied. Other systems reading them must run before `HttpClientSet::Dispatch`.
+///
+/// The `HttpClientConfig` of the entity a request is made from is applied first, then the default
+/// timeout of its client. The results go through the `HttpDispatcher` and are delivered by the
+/// route of the request, after the lifecycle event.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn dispatch_requests<R: DispatchRequest>(
+    mut commands: Commands,
+    mut req_res: ResMut<HttpClientSetting>,
+    mut clients: ResMut<HttpClients>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+    mut requests: ResMut<Events<R>>,
+    mut cancels: EventReader<CancelHttpRequests>,
+    mut waiting: Local<Vec<R>>,
+    mut held: Local<HeldRequests<R>>,
+    configs: Query<&HttpClientConfig>,
+    middleware: Res<HttpMiddleware>,
+    clock: Res<HttpClock>,
+    backoffs: Res<HttpBackoffs>,
+    mut rate_limits: ResMut<RateLimitState>,
+    envelopes: Res<ResponseEnvelopes>,
+) {
+    // the requests waiting for a free slot go first
+    for request in waiting.iter() {
+        clients.setting_mut(&mut req_res, request.client()).queued -= 1;
+    }
+    // cancelled before the requests read below, which are kept
+    for cancel in cancels.read() {
+        let (mut cancelled, kept): (Vec<_>, _) = std::mem::take(&mut *waiting)
+            .into_iter()
+            .partition(|request| cancel.matches_request(request));
+        *waiting = kept;
+        cancelled.extend(held.cancel(cancel));
+        for request in cancelled {
+            commands.send_event(RequestFailed {
+                request: request.info(),
+                error: TaskFailure::Cancelled.into(),
+            });
+        }
+    }
+    let now = clock.now();
+    // past the memory budget, the requests wait for the buffered responses to be delivered
+    let over_budget = dispatcher.over_budget(&req_res);
+    for request in requests.drain() {
+        held.hold(request, now);
+    }
+    let waiting_requests = std::mem::take(&mut *waiting);
+    let requests = waiting_requests
+        .into_iter()
+        .map(|request| (request, true))
+        .chain(
+            held.take_due(now)
+                .into_iter()
+                .map(|request| (request, false)),
+        );
+    for (request, was_queued) in requests {
+        let setting = clients.setting_mut(&mut req_res, request.client());
+        let info = request.info();
+        let paced = setting.adaptive_throttling && !rate_limits.can_send(&info.url, clock.now());
+        if !paced && !over_budget && setting.try_start(clock.now()) {
+            rate_limits.record_sent(&info.url, clock.now());
+            commands.send_event(RequestStarted {
+                request: info.clone(),
+            });
+            let started = Instant::now();
+            let default_timeout = setting.default_timeout;
+            let slow_request_warning = setting.slow_request_warning;
+            let request_id_header = setting.request_id_header.clone();
+            let client_proxy = setting.proxy.clone();
+            let fetch_pool = setting.fetch_pool;
+            let decode_pool = setting.decode_pool;
+            let retry = request.retry(&backoffs);
+            let (mut dispatch, mut route) = request.into_dispatch();
+            let mut retries = 0;
+            let mut mirror = None;
+            if let Some(config) = dispatch
+                .from_entity
+                .and_then(|entity| configs.get(entity).ok())
+            {
+                mirror = config.apply(&mut dispatch.request, &mut dispatch.timeout, clock.now());
+                retries = config.retries;
+            }
+            let timeout = dispatch.timeout.or(default_timeout);
+            let request_id = request_id_header
+                .as_deref()
+                .map(|header| attach_request_id(&mut dispatch.request, header));
+            let recipient = Recipient {
+                entity: dispatch.from_entity,
+                observers_only: dispatch.observers_only,
+            };
+            let url = dispatch.request.url.clone();
+            let endpoint = dispatch.endpoint;
+            let client = dispatch.client;
+            let fetch = middleware.fetch(
+                dispatch.request,
+                FetchOptions {
+                    retries,
+                    request_id: request_id.clone(),
+                    endpoint,
+                    client: client.clone(),
+                    fallback_urls: dispatch.fallback_urls,
+                    proxy: dispatch.proxy.map_or(client_proxy, RequestProxy::url),
+                    body_file: dispatch.body_file.map(|path| FileBody {
+                        path,
+                        progress: middleware.upload_progress.clone(),
+                        info: info.clone(),
+                    }),
+                },
+            );
+            let failure_route = route.clone();
+            let envelopes = envelopes.clone();
+            let failure_request_id = request_id.clone();
+            let failure_client = client.clone();
+            let failure_info = info.clone();
+            let failure_mirror = mirror.clone();
+            let failure_retry = retry.clone();
+            let tags = TaskTags {
+                client,
+                label: info.label.clone(),
+                from_entity: info.from_entity,
+            };
+            let sender = dispatcher.start(clock.now(), timeout, tags, move |world, failure| {
+                world.send_event(RequestFailed {
+                    request: failure_info.clone(),
+                    error: failure.into(),
+                });
+                let retry = failure_retry.filter(|_| matches!(failure, TaskFailure::TimedOut(_)));
+                // the result of a cancelled request is suppressed, nothing waits for it, and the
+                // one of a retried request is the result of its last attempt
+                if let Some(retry) = retry {
+                    tracing::debug!(
+                        "retrying {} {} after {failure}",
+                        failure_info.method,
+                        failure_info.url
+                    );
+                    world.send_event(retry);
+                } else if !matches!(failure, TaskFailure::Cancelled) {
+                    failure_route.deliver(
+                        world,
+                        recipient,
+                        Err(failure.into()),
+                        failure_request_id,
+                        started.elapsed(),
+                    );
+                }
+                if let TaskFailure::TimedOut(_) = failure {
+                    if let Some(mirror) = failure_mirror {
+                        mirror.report_failure(world.resource::<HttpClock>().now());
+                    }
+                }
+                if let TaskFailure::TimedOut(_) | TaskFailure::Cancelled = failure {
+                    world.resource::<HttpMiddleware>().record_cancelled(
+                        &url,
+                        endpoint,
+                        failure_client,
+                    );
+                }
+            });
+
+            fetch_pool
+                .get()
+                .spawn(async move {
+                    let response = fetch.await.map_err(HttpError::transport);
+                    let duration = started.elapsed();
+                    if let Some(threshold) = slow_request_warning.filter(|t| duration > *t) {
+                        tracing::warn!(
+                            "slow request: {} {} took {duration:?}, over {threshold:?}",
+                            info.method,
+                            info.url
+                        );
+                    }
+                    let complete = move || {
+                        route.decode(&response, &envelopes);
+                        let bytes = response.as_ref().map_or(0, |response| response.bytes.len());
+                        let mut command_queue = CommandQueue::default();
+                        command_queue.push(move |world: &mut World| {
+                            match &response {
+                                Ok(response) => {
+                                    let now = world.resource::<HttpClock>().now();
+                                    world.resource_mut::<RateLimitState>().update(response, now);
+                                    world.send_event(RequestCompleted {
+                                        request: info,
+                                        status: response.status,
+                                        duration,
+                                        bytes: response.bytes.len(),
+                                    });
+                                }
+                                Err(e) => {
+                                    if let Some(mirror) = mirror {
+                                        mirror.report_failure(world.resource::<HttpClock>().now());
+                                    }
+                                    world.send_event(RequestFailed {
+                                        request: info.clone(),
+                                        error: e.clone(),
+                                    });
+                                    // only the result of the last attempt is delivered
+                                    if let Some(retry) = retry {
+                                        tracing::debug!(
+                                            "retrying {} {} after {e}",
+                                            info.method,
+                                            info.url
+                                        );
+                                        world.send_event(retry);
+                                        return;
+                                    }
+                                }
+                            }
+                            route.deliver(world, recipient, response, request_id, duration);
+                        });
+
+                        sender.send_buffered(command_queue, bytes);
+                    };
+                    // the response is decoded on its own pool, if any, off the network threads
+                    if decode_pool == fetch_pool {
+                        complete();
+                    } else {
+                        decode_pool.get().spawn(async move { complete() }).detach();
+                    }
+                })
+                .detach();
+        } else {
+            setting.queued += 1;
+            if !was_queued {
+                commands.send_event(RequestQueued {
+                    request: request.info(),
+                });
+            }
+            waiting.push(request);
+        }
+    }
+}
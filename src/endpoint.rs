@@ -0,0 +1,181 @@
+use bevy_app::App;
+use bevy_ecs::system::Resource;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    typed::{HttpTypedRequestTrait, TypedRequest},
+    HttpClient,
+};
+
+/// Characters escaped in a path parameter, everything but RFC 3986 unreserved characters.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'|')
+    .add(b'\\')
+    .add(b'!')
+    .add(b'$')
+    .add(b'&')
+    .add(b'\'')
+    .add(b'(')
+    .add(b')')
+    .add(b'*')
+    .add(b'+')
+    .add(b',')
+    .add(b';')
+    .add(b'=')
+    .add(b':')
+    .add(b'@');
+
+/// A typed API endpoint with a path that may contain `{field}` parameters, and optional typed
+/// query and body.
+///
+/// Usually implemented with `#[derive(HttpEndpoint)]`, where the query and body are fields marked
+/// with `#[endpoint(query)]` and `#[endpoint(body)]`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(HttpEndpoint)]
+/// #[endpoint(method = "GET", path = "/users/{id}/friends", response = Vec<User>)]
+/// struct GetFriends {
+///     id: u64,
+///     #[endpoint(query)]
+///     page: PageQuery,
+/// }
+///
+/// app.register_endpoint::<GetFriends>();
+///
+/// fn send_request(mut ev_request: EventWriter<TypedRequest<Vec<User>>>) {
+///     let endpoint = GetFriends { id: 7, page: PageQuery { limit: 20 } };
+///     ev_request.send(endpoint.request("https://example.com/api"));
+/// }
+/// ```
+pub trait HttpEndpoint: 'static {
+    /// The query parameters, `()` when the endpoint takes none.
+    type Query: Serialize;
+
+    /// The JSON request body, `()` when the endpoint takes none.
+    type Body: Serialize;
+
+    /// The type the response is deserialized into.
+    type Response: for<'a> Deserialize<'a> + Send + Sync + 'static;
+
+    /// The HTTP method, e.g. "GET".
+    const METHOD: &'static str;
+
+    /// The path template, e.g. "/users/{id}".
+    const PATH: &'static str;
+
+    /// Returns the path with its parameters filled in and escaped.
+    fn path(&self) -> String;
+
+    /// Returns the query parameters of this request, if any.
+    fn query(&self) -> Option<&Self::Query> {
+        None
+    }
+
+    /// Returns the body of this request, if any.
+    fn body(&self) -> Option<&Self::Body> {
+        None
+    }
+
+    /// Describes this endpoint.
+    fn info() -> EndpointInfo {
+        EndpointInfo {
+            name: std::any::type_name::<Self>(),
+            method: Self::METHOD,
+            path: Self::PATH,
+        }
+    }
+
+    /// Builds a typed request for this endpoint relative to `base_url`.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the query cannot be URL-encoded or the body cannot be
+    /// serialized to JSON.
+    fn request(&self, base_url: &str) -> TypedRequest<Self::Response> {
+        let mut url = format!("{}{}", base_url.trim_end_matches('/'), self.path());
+        if let Some(query) = self.query() {
+            let query = serde_urlencoded::to_string(query).expect("query must be URL-encodable");
+            if !query.is_empty() {
+                url.push(if url.contains('?') { '&' } else { '?' });
+                url.push_str(&query);
+            }
+        }
+
+        let mut client = HttpClient::new();
+        client.method = Some(Self::METHOD.to_string());
+        client.url = Some(url);
+        if let Some(body) = self.body() {
+            client = client.json(body);
+        }
+
+        let mut request = client.with_type::<Self::Response>();
+        request.endpoint = Some(Self::info());
+        request
+    }
+}
+
+/// Identifies the logical endpoint a request was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EndpointInfo {
+    /// Type name of the endpoint.
+    pub name: &'static str,
+    pub method: &'static str,
+    /// The path template, before parameters are filled in.
+    pub path: &'static str,
+}
+
+/// All endpoints registered with `register_endpoint`.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct EndpointRegistry {
+    endpoints: Vec<EndpointInfo>,
+}
+
+impl EndpointRegistry {
+    /// Returns the registered endpoints in registration order.
+    pub fn endpoints(&self) -> &[EndpointInfo] {
+        &self.endpoints
+    }
+
+    /// Returns the endpoint registered with the type name `name`.
+    pub fn get(&self, name: &str) -> Option<&EndpointInfo> {
+        self.endpoints.iter().find(|info| info.name == name)
+    }
+}
+
+/// Escapes a value for use as a single path segment.
+pub fn encode_path_param(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+pub trait HttpEndpointAppExt {
+    /// Registers the endpoint `E` and its response type.
+    fn register_endpoint<E: HttpEndpoint>(&mut self) -> &mut Self;
+}
+
+impl HttpEndpointAppExt for App {
+    fn register_endpoint<E: HttpEndpoint>(&mut self) -> &mut Self {
+        let info = E::info();
+        let mut registry = self.world_mut().get_resource_or_init::<EndpointRegistry>();
+        if !registry.endpoints.contains(&info) {
+            registry.endpoints.push(info);
+        }
+        self.register_request_type::<E::Response>()
+    }
+}
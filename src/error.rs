@@ -0,0 +1,271 @@
+use std::fmt;
+
+use crate::{verify::VERIFICATION_FAILED, TaskFailure};
+
+/// Why a request failed without a usable response.
+///
+/// Displays as the message of the underlying failure, e.g. the transport error.
+///
+/// # Examples
+///
+/// ```
+/// fn handle_error(mut ev_error: EventReader<HttpResponseError>) {
+///     for error in ev_error.read() {
+///         match &error.err {
+///             HttpError::Timeout(_) | HttpError::ConnectionRefused(_) => retry_later(),
+///             err => println!("request failed: {err}"),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpError {
+    /// The host name could not be resolved.
+    Dns(String),
+    /// The server refused the connection.
+    ConnectionRefused(String),
+    /// The request did not complete in time.
+    Timeout(String),
+    /// The TLS handshake failed, e.g. on an invalid certificate.
+    TlsError(String),
+    /// The request was dropped before it completed.
+    Cancelled(String),
+    /// The server answered with an error status.
+    Status(u16),
+    /// The response body could not be decoded.
+    Decode(String),
+    /// The response was rejected by a `ResponseVerifier`.
+    Verification(String),
+    /// Any other failure.
+    Other(String),
+}
+
+impl HttpError {
+    /// Classifies an error message of the transport.
+    ///
+    /// The messages the native transport classified from its structured errors start with their
+    /// tag, see `TransportFailure`. The others, e.g. those of ehttp, are classified from their
+    /// text after the URL they start with, so a host or a path of the URL is not mistaken for the
+    /// kind of the failure.
+    pub(crate) fn transport(message: String) -> Self {
+        if message.starts_with(VERIFICATION_FAILED) {
+            return Self::Verification(message);
+        }
+        if let Some(failure) = TransportFailure::ALL
+            .into_iter()
+            .find(|failure| message.starts_with(failure.tag()))
+        {
+            return failure.error(message);
+        }
+        let lowercase = after_url(&message).to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| lowercase.contains(needle));
+        if has(&[
+            "dns",
+            "failed to lookup address",
+            "name or service not known",
+        ]) {
+            Self::Dns(message)
+        } else if has(&["connection refused"]) {
+            Self::ConnectionRefused(message)
+        } else if has(&["timed out", "timeout"]) {
+            Self::Timeout(message)
+        } else if has(&["tls", "ssl", "certificate"]) {
+            Self::TlsError(message)
+        } else {
+            Self::Other(message)
+        }
+    }
+}
+
+/// The part of `message` after the URL it starts with, as `ureq` formats its errors, or all of
+/// it.
+fn after_url(message: &str) -> &str {
+    match message.split_once(": ") {
+        Some((url, rest)) if url.contains("://") && !url.contains(char::is_whitespace) => rest,
+        _ => message,
+    }
+}
+
+/// A kind of failure the native transport tells from the `ureq` error kind and its source,
+/// tagged at the start of the message so it survives the middleware, which only see strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TransportFailure {
+    Dns,
+    ConnectionRefused,
+    Timeout,
+    Tls,
+}
+
+impl TransportFailure {
+    const ALL: [Self; 4] = [Self::Dns, Self::ConnectionRefused, Self::Timeout, Self::Tls];
+
+    fn tag(self) -> &'static str {
+        match self {
+            Self::Dns => "dns error: ",
+            Self::ConnectionRefused => "connection refused: ",
+            Self::Timeout => "timed out: ",
+            Self::Tls => "tls error: ",
+        }
+    }
+
+    /// `message` tagged with this kind.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(crate) fn tagged(self, message: impl fmt::Display) -> String {
+        format!("{}{message}", self.tag())
+    }
+
+    fn error(self, message: String) -> HttpError {
+        match self {
+            Self::Dns => HttpError::Dns(message),
+            Self::ConnectionRefused => HttpError::ConnectionRefused(message),
+            Self::Timeout => HttpError::Timeout(message),
+            Self::Tls => HttpError::TlsError(message),
+        }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dns(message)
+            | Self::ConnectionRefused(message)
+            | Self::Timeout(message)
+            | Self::TlsError(message)
+            | Self::Cancelled(message)
+            | Self::Decode(message)
+            | Self::Verification(message)
+            | Self::Other(message) => f.write_str(message),
+            Self::Status(status) => write!(f, "HTTP status {status}"),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl From<String> for HttpError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for HttpError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+impl From<TaskFailure> for HttpError {
+    fn from(failure: TaskFailure) -> Self {
+        let message = failure.to_string();
+        match failure {
+            TaskFailure::TimedOut(_) => Self::Timeout(message),
+            TaskFailure::Overflowed(_) | TaskFailure::Aborted | TaskFailure::Cancelled => {
+                Self::Cancelled(message)
+            }
+        }
+    }
+}
+
+/// Why an `HttpClient` could not be built into a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpClientBuildError {
+    /// No method was set, e.g. with `get` or `post`.
+    MissingMethod,
+    /// The method is not a valid HTTP token, see `HttpClient::method`.
+    InvalidMethod(String),
+    /// No URL was set.
+    MissingUrl,
+    /// The URL does not parse or its scheme is not supported.
+    InvalidUrl { url: String, reason: String },
+    /// The API error type was not registered with `register_api_error_type`.
+    UnregisteredErrorType(&'static str),
+}
+
+impl fmt::Display for HttpClientBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingMethod => f.write_str("the request has no method"),
+            Self::InvalidMethod(method) => write!(f, "invalid method `{method}`"),
+            Self::MissingUrl => f.write_str("the request has no URL"),
+            Self::InvalidUrl { url, reason } => write!(f, "invalid URL `{url}`: {reason}"),
+            Self::UnregisteredErrorType(name) => {
+                write!(f, "the API error type `{name}` is not registered")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HttpClientBuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transport_classifies_messages() {
+        let classify = |message: &str| HttpError::transport(message.to_string());
+        assert!(matches!(
+            classify("dns error: failed to lookup address information"),
+            HttpError::Dns(_)
+        ));
+        assert!(matches!(
+            classify("Connection Refused (os error 111)"),
+            HttpError::ConnectionRefused(_)
+        ));
+        assert!(matches!(
+            classify("operation timed out"),
+            HttpError::Timeout(_)
+        ));
+        assert!(matches!(
+            classify("invalid peer certificate: UnknownIssuer"),
+            HttpError::TlsError(_)
+        ));
+        assert!(matches!(
+            classify(&format!("{VERIFICATION_FAILED}: bad signature")),
+            HttpError::Verification(_)
+        ));
+        assert!(matches!(classify("broken pipe"), HttpError::Other(_)));
+    }
+
+    #[test]
+    fn transport_ignores_the_url() {
+        let classify = |message: &str| HttpError::transport(message.to_string());
+        assert!(matches!(
+            classify(
+                "https://dns.example.com/api/timeout-settings: Connection Failed: Connect error: \
+                 Connection refused (os error 111)"
+            ),
+            HttpError::ConnectionRefused(_)
+        ));
+        assert!(matches!(
+            classify("https://tls.example.com/certificate: Bad Status: Unexpected EOF"),
+            HttpError::Other(_)
+        ));
+    }
+
+    #[test]
+    fn transport_keeps_the_tagged_kind() {
+        let message = TransportFailure::Tls
+            .tagged("https://timeout.example.com/: Connection Failed: tls connection init failed");
+        assert!(matches!(
+            HttpError::transport(message),
+            HttpError::TlsError(_)
+        ));
+    }
+
+    #[test]
+    fn task_failures_become_timeouts_or_cancellations() {
+        assert!(matches!(
+            HttpError::from(TaskFailure::TimedOut(std::time::Duration::from_secs(1))),
+            HttpError::Timeout(_)
+        ));
+        assert!(matches!(
+            HttpError::from(TaskFailure::Overflowed(8)),
+            HttpError::Cancelled(_)
+        ));
+        assert!(matches!(
+            HttpError::from(TaskFailure::Cancelled),
+            HttpError::Cancelled(_)
+        ));
+    }
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use bevy_ecs::prelude::*;
+use ehttp::Response;
+
+use crate::{
+    error::HttpError,
+    lifecycle::RequestFailed,
+    not_modified::HttpNotModified,
+    observe::{HttpObserved, RawResult},
+    sender::HttpRequestSender,
+    HttpRequest, HttpResponse, HttpResponseError,
+};
+
+/// Sends requests from async code and awaits their response, e.g. from async ECS frameworks.
+///
+/// Cloned from the resource added by the `HttpClientPlugin`. The requests go through the
+/// plugin like `HttpRequest` events, so the limits, the middleware, the mocks and the metrics
+/// apply, but their results are only returned to the caller, not sent as events.
+///
+/// # Examples
+///
+/// ```
+/// async fn load_motd(fetcher: HttpFetcher) -> Result<String, HttpError> {
+///     let response = fetcher
+///         .fetch(HttpClient::new().get("https://api.example.com/motd").build())
+///         .await?;
+///     Ok(response.text().unwrap_or_default().to_string())
+/// }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct HttpFetcher(HttpRequestSender);
+
+impl HttpFetcher {
+    pub(crate) fn new(sender: HttpRequestSender) -> Self {
+        Self(sender)
+    }
+
+    /// Sends `request` at the next update and waits for its response.
+    ///
+    /// The entity the request was made from, if any, is replaced: the result is not delivered
+    /// to its observers. A 304 to a conditional request is returned as a response, and a
+    /// cancelled request, or one sent after the app is gone, fails with `HttpError::Cancelled`.
+    pub async fn fetch(&self, mut request: HttpRequest) -> Result<Response, HttpError> {
+        let (tx, rx) = async_channel::bounded(1);
+        let sent = self.0.send_command(move |world: &mut World| {
+            let entity = world
+                .spawn(PendingFetch(tx))
+                .observe(complete::<HttpResponse>)
+                .observe(complete::<HttpResponseError>)
+                .observe(complete::<HttpNotModified>)
+                .id();
+            request.from_entity = Some(entity);
+            request.observers_only = true;
+            world.send_event(request);
+        });
+        if !sent {
+            return Err(HttpError::Cancelled("the app is gone".to_string()));
+        }
+        rx.recv()
+            .await
+            .unwrap_or_else(|_| Err(HttpError::Cancelled("request dropped".to_string())))
+    }
+}
+
+/// The entity a request of an `HttpFetcher` is made from, until its result is returned.
+#[derive(Component)]
+pub(crate) struct PendingFetch(Sender<Result<Response, HttpError>>);
+
+/// Returns the result of the request made from a `PendingFetch` entity.
+fn complete<E: RawResult>(
+    trigger: Trigger<HttpObserved<E>>,
+    mut commands: Commands,
+    fetches: Query<&PendingFetch>,
+) {
+    let result = (**trigger.event())
+        .clone()
+        .into_result()
+        .map(|response| Arc::unwrap_or_clone(response.response))
+        .map_err(|error| error.err);
+    finish(&mut commands, trigger.entity(), result, &fetches);
+}
+
+/// A system that fails the fetched requests that were cancelled, which deliver no result.
+pub(crate) fn fail_cancelled_fetches(
+    mut commands: Commands,
+    mut failed: EventReader<RequestFailed>,
+    fetches: Query<&PendingFetch>,
+) {
+    for event in failed.read() {
+        let (HttpError::Cancelled(_), Some(entity)) = (&event.error, event.from_entity) else {
+            continue;
+        };
+        finish(&mut commands, entity, Err(event.error.clone()), &fetches);
+    }
+}
+
+/// Returns `result` to the caller waiting on `entity`, the first result only.
+fn finish(
+    commands: &mut Commands,
+    entity: Entity,
+    result: Result<Response, HttpError>,
+    fetches: &Query<&PendingFetch>,
+) {
+    let Ok(fetch) = fetches.get(entity) else {
+        return;
+    };
+    // the caller may have stopped waiting
+    fetch.0.try_send(result).ok();
+    commands.entity(entity).despawn();
+}
@@ -0,0 +1,191 @@
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::{BuildChildren, DespawnRecursiveExt, Parent};
+
+use crate::{
+    error::HttpError,
+    lifecycle::RequestFailed,
+    not_modified::HttpNotModified,
+    observe::{HttpObserved, RawResult},
+    HttpRequest, HttpResponse, HttpResponseError,
+};
+
+/// Requests sent together, completing with a single `GroupComplete` once each of them has a
+/// result.
+///
+/// The requests go through the limits of their clients as usual, but their results only go to
+/// the group, they are not sent as `HttpResponse` or `HttpResponseError` events. Each request is
+/// sent from an entity of the group, replacing its own `from_entity`. A 304 to a conditional
+/// request counts as a response, a cancelled request as an error. A throttled request is
+/// dropped without a result, so the group would never complete.
+///
+/// # Examples
+///
+/// ```
+/// fn load_player(mut groups: EventWriter<RequestGroup>) {
+///     groups.send(
+///         RequestGroup::new()
+///             .add(HttpClient::new().get("https://example.com/profile").build())
+///             .add(HttpClient::new().get("https://example.com/inventory").build())
+///             .add(HttpClient::new().get("https://example.com/settings").build())
+///             .label("player"),
+///     );
+/// }
+///
+/// fn enter_game(mut complete: EventReader<GroupComplete>) {
+///     for group in complete.read() {
+///         if group.all_ok() {
+///             let [profile, inventory, settings] = &group.results[..] else {
+///                 unreachable!()
+///             };
+///             // …
+///         }
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone, Default)]
+pub struct RequestGroup {
+    requests: Vec<HttpRequest>,
+    label: Option<String>,
+}
+
+impl RequestGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `request` to the group, its result is at the same index in `GroupComplete::results`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(mut self, request: HttpRequest) -> Self {
+        self.requests.push(request);
+        self
+    }
+
+    /// Sets the label sent with the `GroupComplete`.
+    pub fn label(mut self, label: impl ToString) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+}
+
+/// Sent once every request of a `RequestGroup` has a result.
+#[derive(Event, Debug, Clone)]
+pub struct GroupComplete {
+    /// label of the group, see `RequestGroup::label`
+    pub label: Option<String>,
+    /// The result of each request, in the order they were added.
+    pub results: Vec<Result<HttpResponse, HttpResponseError>>,
+}
+
+impl GroupComplete {
+    /// Whether every request got a 2xx response.
+    pub fn all_ok(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.as_ref().is_ok_and(|response| response.ok))
+    }
+}
+
+/// The results collected so far by a group.
+#[derive(Component)]
+pub(crate) struct PendingGroup {
+    label: Option<String>,
+    results: Vec<Option<Result<HttpResponse, HttpResponseError>>>,
+}
+
+/// The entity a request of a group is sent from, a child of the group.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct GroupItem {
+    index: usize,
+}
+
+/// A system that sends the requests of the `RequestGroup` events.
+pub(crate) fn handle_request_groups(
+    mut commands: Commands,
+    mut groups: EventReader<RequestGroup>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    for group in groups.read().cloned() {
+        if group.requests.is_empty() {
+            commands.send_event(GroupComplete {
+                label: group.label,
+                results: vec![],
+            });
+            continue;
+        }
+
+        let entity = commands
+            .spawn(PendingGroup {
+                label: group.label,
+                results: vec![None; group.requests.len()],
+            })
+            .id();
+        for (index, mut request) in group.requests.into_iter().enumerate() {
+            let item = commands
+                .spawn(GroupItem { index })
+                .set_parent(entity)
+                .observe(record::<HttpResponse>)
+                .observe(record::<HttpResponseError>)
+                .observe(record::<HttpNotModified>)
+                .id();
+            request.from_entity = Some(item);
+            request.observers_only = true;
+            requests.send(request);
+        }
+    }
+}
+
+/// Records the result of the request sent from a group entity.
+fn record<E: RawResult>(
+    trigger: Trigger<HttpObserved<E>>,
+    mut commands: Commands,
+    items: Query<(&GroupItem, &Parent)>,
+    mut groups: Query<&mut PendingGroup>,
+) {
+    let result = (**trigger.event()).clone().into_result();
+    record_result(&mut commands, trigger.entity(), result, &items, &mut groups);
+}
+
+/// A system that records the cancelled requests of groups, which deliver no result.
+pub(crate) fn record_cancelled_group_requests(
+    mut commands: Commands,
+    mut failed: EventReader<RequestFailed>,
+    items: Query<(&GroupItem, &Parent)>,
+    mut groups: Query<&mut PendingGroup>,
+) {
+    for event in failed.read() {
+        let (HttpError::Cancelled(_), Some(item)) = (&event.error, event.from_entity) else {
+            continue;
+        };
+        let error = HttpResponseError::new(event.error.clone()).label(event.label.clone());
+        record_result(&mut commands, item, Err(error), &items, &mut groups);
+    }
+}
+
+/// Records the result of the request sent from `item`, completing its group with the last one.
+fn record_result(
+    commands: &mut Commands,
+    item: Entity,
+    result: Result<HttpResponse, HttpResponseError>,
+    items: &Query<(&GroupItem, &Parent)>,
+    groups: &mut Query<&mut PendingGroup>,
+) {
+    let Ok((item, parent)) = items.get(item) else {
+        return;
+    };
+    let Ok(mut group) = groups.get_mut(parent.get()) else {
+        return;
+    };
+    // the group is empty once complete, and a request failing for several reasons, e.g.
+    // overflowing the result channel, keeps the first
+    let Some(slot) = group.results.get_mut(item.index) else {
+        return;
+    };
+    slot.get_or_insert(result);
+    if group.results.iter().all(Option::is_some) {
+        commands.send_event(GroupComplete {
+            label: group.label.take(),
+            results: group.results.drain(..).flatten().collect(),
+        });
+        commands.entity(parent.get()).despawn_recursive();
+    }
+}
@@ -0,0 +1,199 @@
+use base64::Engine;
+use bevy_utils::SystemTime;
+use ehttp::Headers;
+use serde_json::{json, Value};
+
+use crate::history::{HttpHistory, HttpRecord};
+
+impl HttpHistory {
+    /// Returns the completed requests of the history as a HAR 1.2 document.
+    ///
+    /// Requests that failed with a network error have a response with status 0 and the error in
+    /// the custom `_error` field. In-flight requests are left out.
+    pub fn to_har(&self) -> Value {
+        let entries: Vec<Value> = self
+            .records()
+            .filter(|record| !record.is_in_flight())
+            .map(har_entry)
+            .collect();
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": entries,
+            }
+        })
+    }
+
+    /// Writes the history as a HAR file, to be attached to bug reports.
+    ///
+    /// On wasm, the file is downloaded by the browser with the file name of `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn export(keys: Res<ButtonInput<KeyCode>>, history: Res<HttpHistory>) {
+    ///     if keys.just_pressed(KeyCode::F11) {
+    ///         history.export_har("network.har").unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn export_har(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let har = serde_json::to_string_pretty(&self.to_har())?;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::fs::write(path, har)
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let file_name = path
+                .as_ref()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "network.har".to_string());
+            download(&file_name, &har).map_err(|e| std::io::Error::other(format!("{e:?}")))
+        }
+    }
+}
+
+fn har_entry(record: &HttpRecord) -> Value {
+    let time = record
+        .duration
+        .map_or(0.0, |duration| duration.as_secs_f64() * 1000.0);
+    let request = &record.request;
+    let query: Vec<Value> = url::Url::parse(&request.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut har_request = json!({
+        "method": request.method,
+        "url": request.url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": har_headers(&request.headers),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": request.body.len(),
+    });
+    if !request.body.is_empty() {
+        har_request["postData"] = json!({
+            "mimeType": request.headers.get("content-type").unwrap_or_default(),
+            "text": String::from_utf8_lossy(&request.body),
+        });
+    }
+
+    let har_response = match record.response() {
+        Some(response) => {
+            let mut content = json!({
+                "size": response.bytes.len(),
+                "mimeType": response.content_type().unwrap_or_default(),
+            });
+            match std::str::from_utf8(&response.bytes) {
+                Ok(text) => content["text"] = text.into(),
+                Err(_) => {
+                    content["text"] = base64::engine::general_purpose::STANDARD
+                        .encode(&response.bytes)
+                        .into();
+                    content["encoding"] = "base64".into();
+                }
+            }
+            json!({
+                "status": response.status,
+                "statusText": response.status_text,
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": har_headers(&response.headers),
+                "content": content,
+                "redirectURL": response.headers.get("location").unwrap_or_default(),
+                "headersSize": -1,
+                "bodySize": response.bytes.len(),
+            })
+        }
+        None => json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+            "_error": record.error(),
+        }),
+    };
+
+    json!({
+        "startedDateTime": iso_8601(record.started),
+        "time": time,
+        "request": har_request,
+        "response": har_response,
+        "cache": {},
+        "timings": { "send": 0, "wait": time, "receive": 0 },
+        "_requestId": record.request_id,
+    })
+}
+
+fn har_headers(headers: &Headers) -> Vec<Value> {
+    headers
+        .headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+/// Formats a time as an ISO 8601 UTC date, e.g. `2024-05-01T12:30:00.250Z`.
+fn iso_8601(time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Makes the browser download `contents` as a file.
+#[cfg(target_arch = "wasm32")]
+fn download(file_name: &str, contents: &str) -> Result<(), wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+
+    let parts = js_sys::Array::of1(&contents.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/json");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document")?;
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
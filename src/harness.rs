@@ -0,0 +1,343 @@
+use std::{
+    ops::ControlFlow,
+    sync::{atomic::Ordering, Arc, Mutex},
+};
+
+use bevy_app::App;
+use bevy_ecs::{
+    event::{EventRegistry, ShouldUpdateEvents},
+    prelude::*,
+};
+use bevy_tasks::{IoTaskPool, TaskPoolBuilder};
+use bevy_utils::{Duration, Instant};
+use crossbeam_channel::Sender;
+use ehttp::{Request, Response};
+
+use crate::{
+    middleware::{HttpMiddleware, HttpMiddlewareAppExt, RequestMiddleware},
+    mock::{HttpClientMockPlugin, HttpMocks, Mock},
+    HttpClientPlugin, HttpClock, HttpDispatcher,
+};
+
+/// IO threads of the task pool the harness creates when none exists yet.
+const HARNESS_THREADS: usize = 8;
+
+/// How long `settle` waits for request tasks in real time before giving up.
+const SETTLE_LIMIT: Duration = Duration::from_secs(5);
+
+/// An app driving the plugin deterministically in tests.
+///
+/// Time is a manual `HttpClock` moved with `advance`. Requests matching a `Mock` are answered
+/// right away, the others are held until the test answers them with `respond`, so timeouts can
+/// be observed. A held request occupies an IO thread until answered, so the harness creates
+/// the `IoTaskPool` with several threads when the app has not done so already. Every request is
+/// recorded as sent, after the request middleware.
+///
+/// # Examples
+///
+/// ```
+/// let mut harness = HttpTestHarness::new();
+/// harness.send(
+///     HttpClient::new()
+///         .post("https://example.com/login")
+///         .json(&credentials)
+///         .timeout(Duration::from_secs(5))
+///         .build(),
+/// );
+///
+/// let request = harness.assert_sent("POST", "https://example.com/login");
+/// assert_eq!(request.headers.get("Content-Type"), Some("application/json"));
+///
+/// harness.advance(Duration::from_secs(6));
+/// assert_eq!(harness.drain_events::<HttpResponseError>().len(), 1);
+/// ```
+pub struct HttpTestHarness {
+    /// The app under test, with `HttpClientPlugin` and `HttpClientMockPlugin` added.
+    pub app: App,
+    state: Arc<HarnessState>,
+}
+
+#[derive(Default)]
+struct HarnessState {
+    sent: Mutex<Vec<Request>>,
+    held: Mutex<Vec<HeldRequest>>,
+}
+
+struct HeldRequest {
+    request: Request,
+    respond: Sender<ehttp::Result<Response>>,
+}
+
+impl Default for HttpTestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpTestHarness {
+    /// Creates an app with the plugin, a manual clock and no mocks.
+    pub fn new() -> Self {
+        Self::with_app(App::new())
+    }
+
+    /// Adds the plugin, a manual clock and the harness transport to `app`.
+    ///
+    /// Request middleware registered on `app` before runs first.
+    pub fn with_app(mut app: App) -> Self {
+        // held requests each block an IO thread until answered
+        IoTaskPool::get_or_init(|| TaskPoolBuilder::new().num_threads(HARNESS_THREADS).build());
+        let state = Arc::<HarnessState>::default();
+        app.add_plugins(HttpClientPlugin)
+            .add_request_middleware(HarnessRecorder(state.clone()))
+            .add_plugins(HttpClientMockPlugin)
+            .insert_resource(HttpClock::manual())
+            .add_request_middleware(HarnessTransport(state.clone()));
+        app.world().resource::<HttpMocks>().passthrough(true);
+        // keep events until the test drains them
+        app.world_mut()
+            .resource_mut::<EventRegistry>()
+            .should_update = ShouldUpdateEvents::Waiting;
+        Self { app, state }
+    }
+
+    /// Answers the requests matching `mock` right away from now on.
+    pub fn mock(&mut self, mock: Mock) -> &mut Self {
+        self.app.world().resource::<HttpMocks>().add(mock);
+        self
+    }
+
+    /// Sends an event, usually a request, and settles.
+    pub fn send<E: Event>(&mut self, event: E) -> &mut Self {
+        self.app.world_mut().send_event(event);
+        self.settle();
+        self
+    }
+
+    /// Runs the app until every request is either completed and delivered, or held.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the requests did not settle within 5 seconds of real time.
+    pub fn settle(&mut self) -> &mut Self {
+        let start = Instant::now();
+        loop {
+            self.app.update();
+            if self.is_settled() {
+                // a task may be between completing its fetch and handing over its response
+                std::thread::sleep(Duration::from_millis(1));
+                self.app.update();
+                if self.is_settled() {
+                    return self;
+                }
+            }
+            assert!(
+                start.elapsed() < SETTLE_LIMIT,
+                "requests did not settle within {SETTLE_LIMIT:?}"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Whether every fetch is held and every completed one has been delivered.
+    fn is_settled(&mut self) -> bool {
+        let world = self.app.world_mut();
+        let pending = world
+            .resource::<HttpMiddleware>()
+            .pending
+            .load(Ordering::SeqCst);
+        let held = self.state.held.lock().unwrap().len();
+        let dispatcher = world.resource::<HttpDispatcher>();
+        // a task between two fetches, or done fetching but not sent its result yet, still runs
+        pending == held && dispatcher.tasks.len() <= held && !dispatcher.has_results()
+    }
+
+    /// Moves the clock forward by `duration` and settles.
+    pub fn advance(&mut self, duration: Duration) -> &mut Self {
+        self.app
+            .world_mut()
+            .resource_mut::<HttpClock>()
+            .advance(duration);
+        self.settle()
+    }
+
+    /// Returns every request sent so far, oldest first.
+    pub fn sent_requests(&self) -> Vec<Request> {
+        self.state.sent.lock().unwrap().clone()
+    }
+
+    /// Returns the requests waiting for `respond`, oldest first.
+    pub fn held_requests(&self) -> Vec<Request> {
+        let held = self.state.held.lock().unwrap();
+        held.iter().map(|held| held.request.clone()).collect()
+    }
+
+    /// Returns the last request of `method` whose URL matches `pattern`, where `*` matches any
+    /// characters.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic, listing the sent requests, if there is none.
+    pub fn assert_sent(&self, method: &str, pattern: &str) -> Request {
+        let sent = self.sent_requests();
+        let matcher = Mock::new(method, pattern);
+        match sent.iter().rev().find(|request| matcher.matches(request)) {
+            Some(request) => request.clone(),
+            None => panic!(
+                "no {method} request matching `{pattern}` was sent, sent: {:?}",
+                sent.iter()
+                    .map(|request| format!("{} {}", request.method, request.url))
+                    .collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    /// Answers the oldest held request matching `mock` with its response, and settles.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if no held request matches.
+    pub fn respond(&mut self, mock: Mock) -> &mut Self {
+        let held = {
+            let mut held = self.state.held.lock().unwrap();
+            let index = held
+                .iter()
+                .position(|held| mock.matches(&held.request))
+                .unwrap_or_else(|| panic!("no held request matches {mock:?}"));
+            held.remove(index)
+        };
+        held.respond.send(mock.result_for(&held.request)).ok();
+        self.settle()
+    }
+
+    /// Takes the events of type `E` sent so far.
+    ///
+    /// Events are kept until drained, unless the app runs fixed update schedules.
+    pub fn drain_events<E: Event>(&mut self) -> Vec<E> {
+        self.app
+            .world_mut()
+            .resource_mut::<Events<E>>()
+            .drain()
+            .collect()
+    }
+}
+
+impl Drop for HttpTestHarness {
+    fn drop(&mut self) {
+        // dropping the senders releases the task threads of the held requests
+        self.state.held.lock().unwrap().clear();
+    }
+}
+
+/// Request middleware recording every request, before the mocks answer them.
+struct HarnessRecorder(Arc<HarnessState>);
+
+impl RequestMiddleware for HarnessRecorder {
+    fn handle_request(&self, request: &mut Request) -> ControlFlow<ehttp::Result<Response>> {
+        self.0.sent.lock().unwrap().push(request.clone());
+        ControlFlow::Continue(())
+    }
+}
+
+/// Request middleware holding the requests without a mock.
+struct HarnessTransport(Arc<HarnessState>);
+
+impl RequestMiddleware for HarnessTransport {
+    fn handle_request(&self, request: &mut Request) -> ControlFlow<ehttp::Result<Response>> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.0.held.lock().unwrap().push(HeldRequest {
+            request: request.clone(),
+            respond: tx,
+        });
+        // blocks the task thread until the test responds
+        let result = rx
+            .recv()
+            .unwrap_or_else(|_| Err("request dropped by the test harness".to_string()));
+        ControlFlow::Break(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cancel::CancelHttpRequests, error::HttpError, lifecycle::RequestFailed, HttpClient,
+        HttpClientSetting, HttpResponse, HttpResponseError,
+    };
+
+    #[test]
+    fn requests_over_the_limit_wait_for_a_free_slot() {
+        let mut app = App::new();
+        app.insert_resource(HttpClientSetting::new(1));
+        let mut harness = HttpTestHarness::with_app(app);
+        harness
+            .send(HttpClient::new().get("https://example.com/a").build())
+            .send(HttpClient::new().get("https://example.com/b").build());
+        assert_eq!(harness.held_requests().len(), 1);
+        harness.assert_sent("GET", "https://example.com/a");
+
+        harness.respond(Mock::new("GET", "https://example.com/a"));
+        let held = harness.held_requests();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].url, "https://example.com/b");
+
+        harness.respond(Mock::new("GET", "https://example.com/b"));
+        assert_eq!(harness.drain_events::<HttpResponse>().len(), 2);
+    }
+
+    #[test]
+    fn cancelled_request_delivers_no_result() {
+        let mut harness = HttpTestHarness::new();
+        harness.send(
+            HttpClient::new()
+                .get("https://example.com/level")
+                .label("level")
+                .build(),
+        );
+        harness.send(CancelHttpRequests::Label("level".to_string()));
+        let failed = harness.drain_events::<RequestFailed>();
+        assert_eq!(failed.len(), 1);
+        assert!(matches!(failed[0].error, HttpError::Cancelled(_)));
+
+        // the late response is dropped
+        harness.respond(Mock::new("GET", "https://example.com/level"));
+        assert!(harness.drain_events::<HttpResponse>().is_empty());
+        assert!(harness.drain_events::<HttpResponseError>().is_empty());
+    }
+
+    #[test]
+    fn held_request_times_out_on_the_manual_clock() {
+        let mut harness = HttpTestHarness::new();
+        harness.send(
+            HttpClient::new()
+                .post("https://example.com/login")
+                .timeout(Duration::from_secs(5))
+                .build(),
+        );
+        harness.advance(Duration::from_secs(4));
+        assert!(harness.drain_events::<HttpResponseError>().is_empty());
+
+        harness.advance(Duration::from_secs(2));
+        let errors = harness.drain_events::<HttpResponseError>();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].err, HttpError::Timeout(_)));
+    }
+
+    #[test]
+    fn mocked_request_is_answered_right_away() {
+        let mut harness = HttpTestHarness::new();
+        harness
+            .mock(Mock::new("GET", "https://example.com/scores/*").body("[1,2,3]"))
+            .send(
+                HttpClient::new()
+                    .get("https://example.com/scores/ada")
+                    .label("scores")
+                    .build(),
+            );
+        assert!(harness.held_requests().is_empty());
+        let responses = harness.drain_events::<HttpResponse>();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, 200);
+        assert_eq!(responses[0].text(), Some("[1,2,3]"));
+        assert_eq!(responses[0].label.as_deref(), Some("scores"));
+    }
+}
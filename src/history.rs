@@ -0,0 +1,170 @@
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::prelude::*;
+use bevy_utils::{Duration, SystemTime};
+use crossbeam_channel::Receiver;
+use ehttp::{Request, Response};
+
+use crate::middleware::HttpMiddleware;
+
+static NEXT_RECORD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Plugin that keeps the `HttpHistory` of recent requests.
+///
+/// Requests are recorded as sent after the request middleware, with their full bodies, so the
+/// history is opt-in.
+///
+/// # Examples
+///
+/// ```
+/// App::new()
+///     .add_plugins(HttpClientPlugin)
+///     .add_plugins(HttpHistoryPlugin { capacity: 50 });
+/// ```
+pub struct HttpHistoryPlugin {
+    /// How many requests are kept, the oldest are dropped first.
+    pub capacity: usize,
+}
+
+impl Default for HttpHistoryPlugin {
+    fn default() -> Self {
+        Self { capacity: 100 }
+    }
+}
+
+impl Plugin for HttpHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        app.world_mut()
+            .get_resource_or_init::<HttpMiddleware>()
+            .history = Some(tx);
+        app.insert_resource(HttpHistory {
+            records: VecDeque::new(),
+            capacity: self.capacity,
+            events: rx,
+        });
+        app.add_systems(Update, update_history);
+    }
+}
+
+/// The recent and in-flight requests, oldest first.
+#[derive(Resource, Debug)]
+pub struct HttpHistory {
+    records: VecDeque<HttpRecord>,
+    capacity: usize,
+    events: Receiver<HistoryEvent>,
+}
+
+impl HttpHistory {
+    /// Returns the recorded requests, oldest first.
+    pub fn records(&self) -> impl DoubleEndedIterator<Item = &HttpRecord> {
+        self.records.iter()
+    }
+
+    /// Returns the requests still waiting for their response.
+    pub fn in_flight(&self) -> impl Iterator<Item = &HttpRecord> {
+        self.records.iter().filter(|record| record.is_in_flight())
+    }
+
+    /// Returns the record with the given id, if it is still kept.
+    pub fn get(&self, id: u64) -> Option<&HttpRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    /// How many requests are kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Forgets all recorded requests.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    fn apply(&mut self, event: HistoryEvent) {
+        match event {
+            HistoryEvent::Sent(record) => {
+                self.records.push_back(record);
+                while self.records.len() > self.capacity {
+                    self.records.pop_front();
+                }
+            }
+            HistoryEvent::Completed {
+                id,
+                result,
+                duration,
+            } => {
+                if let Some(record) = self.records.iter_mut().find(|record| record.id == id) {
+                    record.result = Some(result);
+                    record.duration = Some(duration);
+                }
+            }
+        }
+    }
+}
+
+/// A request of the `HttpHistory`, with its result once completed.
+#[derive(Debug, Clone)]
+pub struct HttpRecord {
+    /// Unique id of the record.
+    pub id: u64,
+    /// The request as sent, after the request middleware.
+    pub request: Request,
+    /// The correlation ID of the request, see `HttpClientSetting::with_request_id_header`.
+    pub request_id: Option<String>,
+    /// When the request was sent.
+    pub started: SystemTime,
+    /// How long the request took, `None` while in flight.
+    pub duration: Option<Duration>,
+    /// The response or network error, `None` while in flight.
+    pub result: Option<Result<Response, String>>,
+}
+
+impl HttpRecord {
+    pub(crate) fn new(request: Request, request_id: Option<String>) -> Self {
+        Self {
+            id: NEXT_RECORD_ID.fetch_add(1, Ordering::Relaxed),
+            request,
+            request_id,
+            started: SystemTime::now(),
+            duration: None,
+            result: None,
+        }
+    }
+
+    /// Whether the request is still waiting for its response.
+    pub fn is_in_flight(&self) -> bool {
+        self.result.is_none()
+    }
+
+    /// Returns the response, if the request completed with one.
+    pub fn response(&self) -> Option<&Response> {
+        self.result.as_ref()?.as_ref().ok()
+    }
+
+    /// Returns the network error, if the request failed with one.
+    pub fn error(&self) -> Option<&str> {
+        self.result.as_ref()?.as_ref().err().map(String::as_str)
+    }
+}
+
+/// A history update, sent from the request task.
+#[derive(Debug)]
+pub(crate) enum HistoryEvent {
+    Sent(HttpRecord),
+    Completed {
+        id: u64,
+        result: Result<Response, String>,
+        duration: Duration,
+    },
+}
+
+fn update_history(mut history: ResMut<HttpHistory>) {
+    while let Ok(event) = history.events.try_recv() {
+        history.apply(event);
+    }
+}
@@ -0,0 +1,143 @@
+use std::hash::Hash;
+
+use bevy_app::{App, Update};
+use bevy_ecs::{entity::Entities, prelude::*, system::EntityCommands};
+use bevy_hierarchy::DespawnRecursiveExt;
+use bevy_utils::HashMap;
+use serde::Deserialize;
+
+use crate::typed::{HttpTypedRequestTrait, TypedResponse};
+
+/// A record of a remote collection, synced to one entity per record.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Deserialize)]
+/// struct Friend {
+///     id: u64,
+///     name: String,
+///     online: bool,
+/// }
+///
+/// impl HydratedRecord for Friend {
+///     type Key = u64;
+///
+///     fn key(&self) -> u64 {
+///         self.id
+///     }
+///
+///     fn hydrate(&self, entity: &mut EntityCommands) {
+///         entity.insert((Name::new(self.name.clone()), Online(self.online)));
+///     }
+/// }
+/// ```
+pub trait HydratedRecord: for<'a> Deserialize<'a> + Send + Sync + 'static {
+    /// The ID identifying a record across responses.
+    type Key: Eq + Hash + Clone + Send + Sync + 'static;
+
+    /// Returns the ID of this record.
+    fn key(&self) -> Self::Key;
+
+    /// Inserts the components of this record on its entity, when it is spawned or updated.
+    fn hydrate(&self, entity: &mut EntityCommands);
+}
+
+pub trait HttpHydrateAppExt {
+    /// Registers the request type `Vec<R>` and syncs every `TypedResponse<Vec<R>>` to entities.
+    ///
+    /// A record whose key was not seen before spawns an entity, a known one updates its entity,
+    /// and the entities of the keys missing from the response are despawned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_hydrated_collection::<Friend>();
+    ///
+    /// fn refresh_friends(mut ev_request: EventWriter<TypedRequest<Vec<Friend>>>) {
+    ///     ev_request.send(
+    ///         HttpClient::new()
+    ///             .get("https://example.com/friends")
+    ///             .with_type::<Vec<Friend>>(),
+    ///     );
+    /// }
+    /// ```
+    fn register_hydrated_collection<R: HydratedRecord>(&mut self) -> &mut Self;
+}
+
+impl HttpHydrateAppExt for App {
+    fn register_hydrated_collection<R: HydratedRecord>(&mut self) -> &mut Self {
+        if self.world().contains_resource::<HydratedEntities<R>>() {
+            return self;
+        }
+        self.register_request_type::<Vec<R>>()
+            .init_resource::<HydratedEntities<R>>()
+            .add_systems(Update, hydrate_collection::<R>);
+        self
+    }
+}
+
+/// The entities synced from the records of type `R`, by key.
+#[derive(Resource)]
+pub struct HydratedEntities<R: HydratedRecord> {
+    entities: HashMap<R::Key, Entity>,
+}
+
+impl<R: HydratedRecord> Default for HydratedEntities<R> {
+    fn default() -> Self {
+        Self {
+            entities: HashMap::default(),
+        }
+    }
+}
+
+impl<R: HydratedRecord> HydratedEntities<R> {
+    /// The entity of the record `key`.
+    pub fn get(&self, key: &R::Key) -> Option<Entity> {
+        self.entities.get(key).copied()
+    }
+
+    /// The keys and entities of all synced records.
+    pub fn iter(&self) -> impl Iterator<Item = (&R::Key, Entity)> {
+        self.entities.iter().map(|(key, entity)| (key, *entity))
+    }
+
+    /// The number of synced records.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Whether no record is synced.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// A system that syncs the received collections of `R` to entities.
+fn hydrate_collection<R: HydratedRecord>(
+    mut commands: Commands,
+    mut responses: EventReader<TypedResponse<Vec<R>>>,
+    mut hydrated: ResMut<HydratedEntities<R>>,
+    entities: &Entities,
+) {
+    for records in responses.read() {
+        let mut synced = HashMap::with_capacity(records.len());
+        for record in records.iter() {
+            let key = record.key();
+            let mut entity = match hydrated.entities.remove(&key) {
+                // respawn the records despawned by the app
+                Some(entity) if entities.contains(entity) => commands.entity(entity),
+                _ => commands.spawn_empty(),
+            };
+            record.hydrate(&mut entity);
+            synced.insert(key, entity.id());
+        }
+
+        for (_, removed) in hydrated.entities.drain() {
+            if entities.contains(removed) {
+                commands.entity(removed).despawn_recursive();
+            }
+        }
+        hydrated.entities = synced;
+    }
+}
@@ -0,0 +1,168 @@
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+use crate::{
+    curl::CurlOptions,
+    history::{HttpHistory, HttpHistoryPlugin, HttpRecord},
+};
+
+/// Plugin showing a network inspector window listing the recent and in-flight requests of the
+/// `HttpHistory`, like the "Network" tab of the browser dev tools.
+///
+/// Adds `HttpHistoryPlugin` and `EguiPlugin` when they are missing. The window is shown while
+/// `HttpInspector::open` is set.
+///
+/// # Examples
+///
+/// ```
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugins(HttpClientPlugin)
+///     .add_plugins(HttpInspectorPlugin);
+///
+/// fn toggle_inspector(keys: Res<ButtonInput<KeyCode>>, mut inspector: ResMut<HttpInspector>) {
+///     if keys.just_pressed(KeyCode::F12) {
+///         inspector.open = !inspector.open;
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct HttpInspectorPlugin;
+
+impl Plugin for HttpInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<HttpHistory>() {
+            app.add_plugins(HttpHistoryPlugin::default());
+        }
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+        app.init_resource::<HttpInspector>();
+        app.add_systems(Update, show_inspector);
+    }
+}
+
+/// State of the network inspector window.
+#[derive(Resource, Debug)]
+pub struct HttpInspector {
+    /// Whether the window is shown.
+    pub open: bool,
+}
+
+impl Default for HttpInspector {
+    fn default() -> Self {
+        Self { open: true }
+    }
+}
+
+fn show_inspector(
+    mut contexts: EguiContexts,
+    mut inspector: ResMut<HttpInspector>,
+    mut history: ResMut<HttpHistory>,
+) {
+    if !inspector.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Network")
+        .open(&mut inspector.open)
+        .default_width(600.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let in_flight = history.in_flight().count();
+                ui.label(format!(
+                    "{} requests, {in_flight} in flight",
+                    history.records().count()
+                ));
+                if ui.button("Clear").clicked() {
+                    history.clear();
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // newest first, like the browser dev tools
+                for record in history.records().rev() {
+                    egui::CollapsingHeader::new(summary(record))
+                        .id_salt(record.id)
+                        .show(ui, |ui| show_record(ui, record));
+                }
+            });
+        });
+}
+
+/// One line summary of a record: status, method, URL, duration and size.
+fn summary(record: &HttpRecord) -> String {
+    let status = match &record.result {
+        None => "…".to_string(),
+        Some(Ok(response)) => response.status.to_string(),
+        Some(Err(_)) => "ERR".to_string(),
+    };
+    let duration = record
+        .duration
+        .map(|duration| format!("{} ms", duration.as_millis()))
+        .unwrap_or_else(|| "pending".to_string());
+    let size = record
+        .response()
+        .map(|response| format!(", {} B", response.bytes.len()))
+        .unwrap_or_default();
+    format!(
+        "{status} {} {} ({duration}{size})",
+        record.request.method, record.request.url
+    )
+}
+
+fn show_record(ui: &mut egui::Ui, record: &HttpRecord) {
+    ui.horizontal(|ui| {
+        if let Some(request_id) = &record.request_id {
+            ui.label(format!("Request ID: {request_id}"));
+        }
+        if ui.button("Copy as curl").clicked() {
+            ui.ctx().copy_text(record.to_curl(&CurlOptions::default()));
+        }
+    });
+    if let Some(error) = record.error() {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+
+    egui::CollapsingHeader::new("Request")
+        .id_salt((record.id, "request"))
+        .show(ui, |ui| {
+            show_headers(ui, &record.request.headers);
+            show_body(ui, &record.request.body);
+        });
+    if let Some(response) = record.response() {
+        egui::CollapsingHeader::new(format!(
+            "Response {} {}",
+            response.status, response.status_text
+        ))
+        .id_salt((record.id, "response"))
+        .show(ui, |ui| {
+            show_headers(ui, &response.headers);
+            show_body(ui, &response.bytes);
+        });
+    }
+}
+
+fn show_headers(ui: &mut egui::Ui, headers: &ehttp::Headers) {
+    for (key, value) in headers {
+        ui.monospace(format!("{key}: {value}"));
+    }
+}
+
+fn show_body(ui: &mut egui::Ui, body: &[u8]) {
+    if body.is_empty() {
+        return;
+    }
+    ui.separator();
+    // pretty print JSON, show other bodies as text
+    let text = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| serde_json::to_string_pretty(&json).ok())
+        .unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+    ui.monospace(text);
+}
@@ -0,0 +1,102 @@
+use bevy_derive::Deref;
+use bevy_ecs::prelude::*;
+use bevy_utils::Duration;
+use crossbeam_channel::Receiver;
+
+use crate::error::HttpError;
+
+/// The request a lifecycle event is about.
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    /// "GET", "POST", …
+    pub method: String,
+    pub url: String,
+    /// The entity the request was made from.
+    pub from_entity: Option<Entity>,
+    /// The label of the request, see `HttpClient::label`.
+    pub label: Option<String>,
+}
+
+/// Sent when a request waits for a free slot of its client, or for its rate limit.
+///
+/// Lifecycle events are sent for the `HttpRequest` and `TypedRequest` events, in addition to
+/// their results.
+///
+/// # Examples
+///
+/// ```
+/// fn log_requests(mut completed: EventReader<RequestCompleted>) {
+///     for event in completed.read() {
+///         println!("{} took {:?}, {} bytes", event.url, event.duration, event.bytes);
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone, Deref)]
+pub struct RequestQueued {
+    #[deref]
+    pub request: RequestInfo,
+}
+
+/// Sent when a request is sent, right away or after it waited in the queue.
+#[derive(Event, Debug, Clone, Deref)]
+pub struct RequestStarted {
+    #[deref]
+    pub request: RequestInfo,
+}
+
+/// Sent as the file body of a request is uploaded, see `HttpClient::body_from_file`.
+///
+/// Sent when the upload starts, then every mebibyte and once the whole file is sent. An attempt
+/// retried after a network error starts over from zero.
+///
+/// # Examples
+///
+/// ```
+/// fn show_upload(mut progress: EventReader<UploadProgress>) {
+///     for event in progress.read() {
+///         println!("{}: {}/{} bytes", event.url, event.sent, event.total);
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone, Deref)]
+pub struct UploadProgress {
+    #[deref]
+    pub request: RequestInfo,
+    /// Bytes of the file sent so far.
+    pub sent: u64,
+    /// Size of the file.
+    pub total: u64,
+}
+
+/// Where the fetch tasks report the progress of uploads, drained by `send_upload_progress`.
+#[derive(Resource)]
+pub(crate) struct UploadProgressReceiver(pub(crate) Receiver<UploadProgress>);
+
+/// A system that sends the upload progress reported since the last update.
+pub(crate) fn send_upload_progress(
+    receiver: Res<UploadProgressReceiver>,
+    mut progress: EventWriter<UploadProgress>,
+) {
+    progress.send_batch(receiver.0.try_iter());
+}
+
+/// Sent when a response arrives, whatever its status, along with the response events.
+#[derive(Event, Debug, Clone, Deref)]
+pub struct RequestCompleted {
+    #[deref]
+    pub request: RequestInfo,
+    pub status: u16,
+    /// Time from starting the request to receiving the response, retries included.
+    pub duration: Duration,
+    /// Size of the response body.
+    pub bytes: usize,
+}
+
+/// Sent when a request fails without a response, e.g. with a network error or a timeout, along
+/// with the error events.
+#[derive(Event, Debug, Clone, Deref)]
+pub struct RequestFailed {
+    #[deref]
+    pub request: RequestInfo,
+    pub error: HttpError,
+}
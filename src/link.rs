@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use ehttp::{Headers, Response};
+use serde::Deserialize;
+
+use crate::HttpClient;
+
+/// A link to a related resource, from a `Link` header or a HAL `_links` object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The relation type, e.g. `next` or `author`.
+    pub rel: String,
+    /// The target URL, resolved against the response URL when possible.
+    pub href: String,
+    /// Other link parameters such as `type` or `title`.
+    pub params: Vec<(String, String)>,
+}
+
+/// Access to the links advertised by a response.
+pub trait ResponseLinks {
+    /// Returns all links of the `Link` headers followed by the links of a HAL `_links` body.
+    fn links(&self) -> Vec<Link>;
+
+    /// Returns the first link with the relation `rel`.
+    fn link(&self, rel: &str) -> Option<Link> {
+        self.links()
+            .into_iter()
+            .find(|link| link.rel.eq_ignore_ascii_case(rel))
+    }
+}
+
+impl ResponseLinks for Response {
+    fn links(&self) -> Vec<Link> {
+        let mut links = parse_link_headers(&self.headers, &self.url);
+        let is_json = self
+            .content_type()
+            .is_none_or(|content_type| content_type.contains("json"));
+        if is_json {
+            links.extend(parse_hal_links(&self.bytes, &self.url));
+        }
+        links
+    }
+}
+
+impl HttpClient {
+    /// Turns the builder into a `GET` request for the link `rel` of `response`.
+    ///
+    /// Headers, entity and mode of the builder are kept, so building it from the original request
+    /// inherits its authentication. The body and its "Content-Type" are dropped. Returns `None`
+    /// when the response has no such link.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let next = HttpClient::new()
+    ///     .request(original_request)
+    ///     .follow(&response, "next")
+    ///     .map(HttpClient::build);
+    /// ```
+    pub fn follow(mut self, response: &Response, rel: &str) -> Option<Self> {
+        let link = response.link(rel)?;
+        self.method = Some("GET".to_string());
+        self.url = Some(link.href);
+        self.body = vec![];
+        if let Some(headers) = self.headers.as_mut() {
+            headers
+                .headers
+                .retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+        }
+        Some(self)
+    }
+}
+
+/// Parses RFC 8288 `Link` headers, resolving targets against `base`.
+pub(crate) fn parse_link_headers(headers: &Headers, base: &str) -> Vec<Link> {
+    let mut links = vec![];
+    for value in headers.get_all("link") {
+        for link in split_links(value) {
+            let Some((target, params)) = link.split_once(';') else {
+                continue;
+            };
+            let Some(target) = target
+                .trim()
+                .strip_prefix('<')
+                .and_then(|t| t.strip_suffix('>'))
+            else {
+                continue;
+            };
+
+            let mut rels = String::new();
+            let mut others = vec![];
+            for param in params.split(';') {
+                let Some((key, value)) = param.trim().split_once('=') else {
+                    continue;
+                };
+                let key = key.trim().to_ascii_lowercase();
+                let value = value.trim().trim_matches('"').to_string();
+                if key == "rel" {
+                    rels = value;
+                } else {
+                    others.push((key, value));
+                }
+            }
+
+            let href = resolve(base, target);
+            // a single link may carry several space-separated relations
+            for rel in rels.split_whitespace() {
+                links.push(Link {
+                    rel: rel.to_string(),
+                    href: href.clone(),
+                    params: others.clone(),
+                });
+            }
+        }
+    }
+    links
+}
+
+/// Splits a `Link` header value on the commas that separate links, not those inside `<...>` or
+/// quoted parameters.
+fn split_links(value: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut start, mut in_target, mut in_quotes) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            '<' if !in_quotes => in_target = true,
+            '>' if !in_quotes => in_target = false,
+            '"' if !in_target => in_quotes = !in_quotes,
+            ',' if !in_target && !in_quotes => {
+                parts.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+#[derive(Deserialize)]
+struct HalBody {
+    #[serde(rename = "_links", default)]
+    links: BTreeMap<String, HalLinks>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HalLinks {
+    One(HalLink),
+    Many(Vec<HalLink>),
+}
+
+#[derive(Deserialize)]
+struct HalLink {
+    href: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    title: Option<String>,
+}
+
+/// Parses the HAL `_links` object of a JSON body, resolving targets against `base`.
+fn parse_hal_links(bytes: &[u8], base: &str) -> Vec<Link> {
+    let Ok(body) = serde_json::from_slice::<HalBody>(bytes) else {
+        return vec![];
+    };
+    let mut links = vec![];
+    for (rel, hal_links) in body.links {
+        let hal_links = match hal_links {
+            HalLinks::One(link) => vec![link],
+            HalLinks::Many(links) => links,
+        };
+        for link in hal_links {
+            let params = [("type", link.kind), ("title", link.title)]
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.to_string(), value?)))
+                .collect();
+            links.push(Link {
+                rel: rel.clone(),
+                href: resolve(base, &link.href),
+                params,
+            });
+        }
+    }
+    links
+}
+
+/// Resolves `target` against `base`, keeping it as is when either is not a valid URL.
+fn resolve(base: &str, target: &str) -> String {
+    url::Url::parse(base)
+        .and_then(|base| base.join(target))
+        .map(String::from)
+        .unwrap_or_else(|_| target.to_string())
+}
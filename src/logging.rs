@@ -0,0 +1,217 @@
+use std::ops::ControlFlow;
+
+use bevy_utils::{
+    tracing::{self, Level},
+    Duration,
+};
+use ehttp::{Headers, Request, Response};
+
+use crate::middleware::ResponseMiddleware;
+
+/// Logs an event at a level only known at runtime.
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        match $level {
+            Level::ERROR => tracing::error!($($arg)+),
+            Level::WARN => tracing::warn!($($arg)+),
+            Level::INFO => tracing::info!($($arg)+),
+            Level::DEBUG => tracing::debug!($($arg)+),
+            Level::TRACE => tracing::trace!($($arg)+),
+        }
+    };
+}
+
+const REDACTED: &str = "<redacted>";
+
+/// Response middleware logging the method, URL, status, duration and sizes of every request.
+///
+/// Headers and bodies are only logged when enabled, with the configured headers and JSON body
+/// fields redacted. "Authorization", "Proxy-Authorization", "Cookie" and "Set-Cookie" are
+/// redacted by default.
+///
+/// # Examples
+///
+/// ```
+/// app.add_response_middleware(
+///     HttpLogger::default()
+///         .level(Level::DEBUG)
+///         .log_bodies(true)
+///         .redact_field("password"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpLogger {
+    /// Level of successful requests.
+    pub level: Level,
+    /// Level of network errors and responses with an error status.
+    pub error_level: Level,
+    /// Whether request and response headers are logged.
+    pub log_headers: bool,
+    /// Whether request and response bodies are logged.
+    pub log_bodies: bool,
+    /// Names of the headers whose values are redacted, compared case-insensitively.
+    pub redacted_headers: Vec<String>,
+    /// Names of the JSON body fields whose values are redacted, at any depth.
+    pub redacted_fields: Vec<String>,
+}
+
+impl Default for HttpLogger {
+    fn default() -> Self {
+        Self {
+            level: Level::INFO,
+            error_level: Level::WARN,
+            log_headers: false,
+            log_bodies: false,
+            redacted_headers: [
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+            ]
+            .map(String::from)
+            .to_vec(),
+            redacted_fields: vec![],
+        }
+    }
+}
+
+impl HttpLogger {
+    /// Sets the level of successful requests.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the level of failed requests.
+    pub fn error_level(mut self, level: Level) -> Self {
+        self.error_level = level;
+        self
+    }
+
+    /// Enables logging of request and response headers.
+    pub fn log_headers(mut self, enabled: bool) -> Self {
+        self.log_headers = enabled;
+        self
+    }
+
+    /// Enables logging of request and response bodies.
+    pub fn log_bodies(mut self, enabled: bool) -> Self {
+        self.log_bodies = enabled;
+        self
+    }
+
+    /// Redacts the value of the header `name`.
+    pub fn redact_header(mut self, name: impl ToString) -> Self {
+        self.redacted_headers.push(name.to_string());
+        self
+    }
+
+    /// Redacts the value of the JSON body field `name`.
+    pub fn redact_field(mut self, name: impl ToString) -> Self {
+        self.redacted_fields.push(name.to_string());
+        self
+    }
+
+    fn format_headers(&self, headers: &Headers) -> String {
+        let headers: Vec<String> = headers
+            .headers
+            .iter()
+            .map(|(key, value)| {
+                let redacted = self
+                    .redacted_headers
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(key));
+                format!("{key}: {}", if redacted { REDACTED } else { value })
+            })
+            .collect();
+        format!("[{}]", headers.join(", "))
+    }
+
+    fn format_body(&self, bytes: &[u8]) -> String {
+        if let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(bytes) {
+            self.redact_json(&mut json);
+            return json.to_string();
+        }
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.redacted_fields.iter().any(|field| field == key) {
+                        *value = REDACTED.into();
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => {
+                values.iter_mut().for_each(|value| self.redact_json(value));
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the headers and bodies part of the log line, when enabled.
+    fn format_details(&self, request: &Request, response: Option<&Response>) -> String {
+        let mut details = String::new();
+        if self.log_headers {
+            details += &format!(" request_headers={}", self.format_headers(&request.headers));
+            if let Some(response) = response {
+                details += &format!(
+                    " response_headers={}",
+                    self.format_headers(&response.headers)
+                );
+            }
+        }
+        if self.log_bodies {
+            if !request.body.is_empty() {
+                details += &format!(" request_body={}", self.format_body(&request.body));
+            }
+            if let Some(response) = response.filter(|response| !response.bytes.is_empty()) {
+                details += &format!(" response_body={}", self.format_body(&response.bytes));
+            }
+        }
+        details
+    }
+}
+
+impl ResponseMiddleware for HttpLogger {
+    fn handle_response(
+        &self,
+        request: &Request,
+        result: &mut ehttp::Result<Response>,
+        elapsed: Duration,
+    ) -> ControlFlow<()> {
+        let details = self.format_details(request, result.as_ref().ok());
+        match result {
+            Ok(response) => {
+                let level = if response.ok {
+                    self.level
+                } else {
+                    self.error_level
+                };
+                log_at!(
+                    level,
+                    "{} {} -> {} {} in {elapsed:?} (sent {} B, received {} B){details}",
+                    request.method,
+                    request.url,
+                    response.status,
+                    response.status_text,
+                    request.body.len(),
+                    response.bytes.len(),
+                );
+            }
+            Err(err) => {
+                log_at!(
+                    self.error_level,
+                    "{} {} -> failed in {elapsed:?}: {err}{details}",
+                    request.method,
+                    request.url,
+                );
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
@@ -0,0 +1,88 @@
+use bevy_app::{App, Plugins};
+use bevy_ecs::{
+    component::Tick,
+    event::EventRegistry,
+    prelude::*,
+    reflect::AppTypeRegistry,
+    schedule::{ScheduleLabel, Schedules},
+};
+
+use crate::{HttpClientPlugin, HttpClientSetting};
+
+/// The schedule the request handling systems of an `HttpClientCore` run in.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpCoreUpdate;
+
+/// The `HttpClientPlugin` added to a `World` without an `App`, driven manually with `update`,
+/// e.g. by dedicated servers and tools running their own loop.
+///
+/// Requests and responses are the same events as with the plugin, sent and read on the world.
+///
+/// # Examples
+///
+/// ```
+/// let mut world = World::new();
+/// let mut core = HttpClientCore::new(&mut world);
+/// world.send_event(HttpClient::new().get("https://api.example.com/status").build());
+/// loop {
+///     core.update(&mut world);
+///     if let Some(response) = world.resource_mut::<Events<HttpResponse>>().drain().next() {
+///         println!("status {}", response.status);
+///         break;
+///     }
+///     std::thread::sleep(Duration::from_millis(10));
+/// }
+/// ```
+pub struct HttpClientCore {
+    /// When the events of the world were last updated.
+    last_event_update: Tick,
+}
+
+impl HttpClientCore {
+    /// Adds the request handling to `world`, with the default setting.
+    pub fn new(world: &mut World) -> Self {
+        Self::with_setting(world, HttpClientSetting::default())
+    }
+
+    /// Adds the request handling to `world`, with `setting`.
+    ///
+    /// The systems run in `HttpCoreUpdate`, whatever the schedule of `setting`.
+    pub fn with_setting(world: &mut World, setting: HttpClientSetting) -> Self {
+        world.insert_resource(setting.with_schedule(HttpCoreUpdate));
+        let core = Self {
+            last_event_update: world.change_tick(),
+        };
+        core.add_plugins(world, HttpClientPlugin);
+        core
+    }
+
+    /// Adds more `plugins` of the crate to `world`, e.g. the `HttpClientMockPlugin`, their
+    /// systems running in `HttpCoreUpdate` too.
+    pub fn add_plugins<M>(&self, world: &mut World, plugins: impl Plugins<M>) -> &Self {
+        world.init_resource::<Schedules>();
+        world.init_resource::<AppTypeRegistry>();
+        // the plugins are built on an app holding the world for the time being
+        let mut app = App::empty();
+        std::mem::swap(app.world_mut(), world);
+        app.add_plugins(plugins);
+        app.finish();
+        app.cleanup();
+        std::mem::swap(app.world_mut(), world);
+        self
+    }
+
+    /// Sends the due requests and applies the completed results, then updates the events of
+    /// `world`.
+    ///
+    /// Events are kept for two updates, as in an `App`. A world whose events are already
+    /// updated elsewhere runs `HttpCoreUpdate` itself instead.
+    pub fn update(&mut self, world: &mut World) {
+        world.run_schedule(HttpCoreUpdate);
+        if world.contains_resource::<EventRegistry>() {
+            world.resource_scope(|world, mut registry: Mut<EventRegistry>| {
+                registry.run_updates(world, self.last_event_update);
+            });
+        }
+        self.last_event_update = world.change_tick();
+    }
+}
@@ -0,0 +1,264 @@
+use bevy_ecs::prelude::*;
+use bevy_utils::{Duration, HashMap};
+use crossbeam_channel::Receiver;
+
+use crate::{endpoint::EndpointInfo, HttpClientSetting, HttpClients, HttpDispatcher};
+
+/// Upper bounds of the latency histogram buckets, the last bucket is unbounded.
+const LATENCY_BUCKETS: [Duration; 8] = [
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+/// Request counters and latencies, in total, per host, per endpoint and per labeled client.
+///
+/// Endpoints are labeled "METHOD /path/template" and only requests built from an `HttpEndpoint`
+/// are counted per endpoint. Only the requests sent with `HttpClient::on_client` are counted per
+/// client.
+///
+/// # Examples
+///
+/// ```
+/// fn net_status(metrics: Res<HttpMetrics>) {
+///     let stats = &metrics.total;
+///     println!(
+///         "{} in flight, {} ok, {} failed, mean {:?}",
+///         metrics.in_flight,
+///         stats.succeeded,
+///         stats.failed,
+///         stats.latency.mean()
+///     );
+/// }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct HttpMetrics {
+    pub total: RequestStats,
+    pub hosts: HashMap<String, RequestStats>,
+    pub endpoints: HashMap<String, RequestStats>,
+    pub clients: HashMap<String, RequestStats>,
+    /// Number of requests currently in flight, on all clients.
+    pub in_flight: usize,
+    /// Bytes of the response bodies waiting to be delivered, on all clients.
+    pub buffered_bytes: usize,
+    /// `buffered_bytes` over the memory budget, requests are deferred from 1.0, always 0.0
+    /// without a budget, see `HttpClientSetting::with_memory_budget`.
+    pub memory_pressure: f32,
+    samples: Receiver<MetricSample>,
+}
+
+impl HttpMetrics {
+    pub(crate) fn new(samples: Receiver<MetricSample>) -> Self {
+        Self {
+            total: RequestStats::default(),
+            hosts: HashMap::default(),
+            endpoints: HashMap::default(),
+            clients: HashMap::default(),
+            in_flight: 0,
+            buffered_bytes: 0,
+            memory_pressure: 0.0,
+            samples,
+        }
+    }
+
+    /// Returns the stats of requests to `host`.
+    pub fn host(&self, host: &str) -> Option<&RequestStats> {
+        self.hosts.get(host)
+    }
+
+    /// Returns the stats of requests to the endpoint `E`.
+    pub fn endpoint<E: crate::HttpEndpoint>(&self) -> Option<&RequestStats> {
+        self.endpoints.get(&endpoint_label(&E::info()))
+    }
+
+    /// Returns the stats of requests sent with the client labeled `label`.
+    pub fn client(&self, label: &str) -> Option<&RequestStats> {
+        self.clients.get(label)
+    }
+
+    /// Resets all counters and histograms.
+    pub fn reset(&mut self) {
+        self.total = RequestStats::default();
+        self.hosts.clear();
+        self.endpoints.clear();
+        self.clients.clear();
+    }
+
+    pub(crate) fn record(&mut self, sample: MetricSample) {
+        self.total.record(sample.event);
+        self.hosts
+            .entry(sample.host)
+            .or_default()
+            .record(sample.event);
+        if let Some(endpoint) = sample.endpoint {
+            self.endpoints
+                .entry(endpoint)
+                .or_default()
+                .record(sample.event);
+        }
+        if let Some(client) = sample.client {
+            self.clients.entry(client).or_default().record(sample.event);
+        }
+    }
+}
+
+/// Counters and latency histogram of a set of requests.
+#[derive(Debug, Default, Clone)]
+pub struct RequestStats {
+    pub sent: u64,
+    /// Requests that completed with a 2xx status.
+    pub succeeded: u64,
+    /// Requests that failed with a network error or completed with an error status.
+    pub failed: u64,
+    /// Retries after network errors.
+    pub retried: u64,
+    /// Requests that were abandoned, e.g. because they timed out.
+    pub cancelled: u64,
+    /// Latency of the succeeded and failed requests.
+    pub latency: LatencyHistogram,
+}
+
+impl RequestStats {
+    fn record(&mut self, event: MetricEvent) {
+        match event {
+            MetricEvent::Sent => self.sent += 1,
+            MetricEvent::Retried => self.retried += 1,
+            MetricEvent::Cancelled => self.cancelled += 1,
+            MetricEvent::Succeeded(latency) => {
+                self.succeeded += 1;
+                self.latency.record(latency);
+            }
+            MetricEvent::Failed(latency) => {
+                self.failed += 1;
+                self.latency.record(latency);
+            }
+        }
+    }
+}
+
+/// Latency histogram with fixed buckets from 50ms to 10s.
+#[derive(Debug, Default, Clone)]
+pub struct LatencyHistogram {
+    counts: [u64; LATENCY_BUCKETS.len() + 1],
+    count: u64,
+    sum: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += latency;
+        self.max = self.max.max(latency);
+    }
+
+    /// Number of recorded latencies.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean latency, zero when nothing was recorded.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.sum.div_f64(self.count as f64)
+        }
+    }
+
+    /// Highest recorded latency.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Upper bound of the bucket containing the `quantile` (between 0 and 1) of the latencies,
+    /// the highest latency for the unbounded bucket.
+    pub fn quantile(&self, quantile: f64) -> Duration {
+        let rank = (quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank.max(1) {
+                return LATENCY_BUCKETS.get(bucket).copied().unwrap_or(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Bucket upper bounds with their counts, the last bound being `None`.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BUCKETS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum MetricEvent {
+    Sent,
+    Retried,
+    Succeeded(Duration),
+    Failed(Duration),
+    Cancelled,
+}
+
+/// A metric event of a request, sent from the request task.
+#[derive(Debug, Clone)]
+pub(crate) struct MetricSample {
+    pub(crate) host: String,
+    pub(crate) endpoint: Option<String>,
+    pub(crate) client: Option<String>,
+    pub(crate) event: MetricEvent,
+}
+
+impl MetricSample {
+    pub(crate) fn new(
+        url: &str,
+        endpoint: Option<EndpointInfo>,
+        client: Option<String>,
+        event: MetricEvent,
+    ) -> Self {
+        Self {
+            host: url::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(ToString::to_string))
+                .unwrap_or_default(),
+            endpoint: endpoint.as_ref().map(endpoint_label),
+            client,
+            event,
+        }
+    }
+}
+
+fn endpoint_label(info: &EndpointInfo) -> String {
+    format!("{} {}", info.method, info.path)
+}
+
+pub(crate) fn update_metrics(
+    mut metrics: ResMut<HttpMetrics>,
+    setting: Res<HttpClientSetting>,
+    clients: Res<HttpClients>,
+    dispatcher: Res<HttpDispatcher>,
+) {
+    while let Ok(sample) = metrics.samples.try_recv() {
+        metrics.record(sample);
+    }
+    metrics.in_flight = setting.in_flight() + clients.in_flight();
+    metrics.buffered_bytes = dispatcher.buffered_bytes();
+    metrics.memory_pressure = setting.memory_budget.map_or(0.0, |budget| {
+        metrics.buffered_bytes as f32 / budget.max(1) as f32
+    });
+}
@@ -0,0 +1,528 @@
+use std::{
+    future::Future,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bevy_app::App;
+use bevy_ecs::system::Resource;
+use bevy_utils::{
+    tracing::{self, Instrument},
+    Duration, Instant,
+};
+use crossbeam_channel::Sender;
+use ehttp::{Request, Response};
+
+use crate::{
+    endpoint::EndpointInfo,
+    history::{HistoryEvent, HttpRecord},
+    lifecycle::UploadProgress,
+    metrics::{MetricEvent, MetricSample},
+    transport::{FileBody, HttpResolver, IpFamily},
+    verify::{ResponseVerifier, VERIFICATION_FAILED},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::NativeTransport;
+
+/// Mutates outgoing requests before they are sent.
+///
+/// Implemented for closures taking the request, so simple middleware needs no extra type.
+pub trait RequestMiddleware: Send + Sync + 'static {
+    /// Called before `request` is sent, in registration order.
+    ///
+    /// Returning `ControlFlow::Break` short-circuits the request: the given result is used as its
+    /// response without reaching the network or the following request middleware.
+    fn handle_request(&self, request: &mut Request) -> ControlFlow<ehttp::Result<Response>>;
+}
+
+impl<F> RequestMiddleware for F
+where
+    F: Fn(&mut Request) -> ControlFlow<ehttp::Result<Response>> + Send + Sync + 'static,
+{
+    fn handle_request(&self, request: &mut Request) -> ControlFlow<ehttp::Result<Response>> {
+        self(request)
+    }
+}
+
+/// Inspects incoming responses before they are delivered.
+///
+/// Implemented for closures taking the request, the result and the elapsed time.
+pub trait ResponseMiddleware: Send + Sync + 'static {
+    /// Called with the result of `request`, in registration order, `elapsed` after the request
+    /// middleware ran.
+    ///
+    /// The result can be replaced, e.g. to turn a response into an error. Returning
+    /// `ControlFlow::Break` skips the following response middleware.
+    fn handle_response(
+        &self,
+        request: &Request,
+        result: &mut ehttp::Result<Response>,
+        elapsed: Duration,
+    ) -> ControlFlow<()>;
+}
+
+impl<F> ResponseMiddleware for F
+where
+    F: Fn(&Request, &mut ehttp::Result<Response>, Duration) -> ControlFlow<()>
+        + Send
+        + Sync
+        + 'static,
+{
+    fn handle_response(
+        &self,
+        request: &Request,
+        result: &mut ehttp::Result<Response>,
+        elapsed: Duration,
+    ) -> ControlFlow<()> {
+        self(request, result, elapsed)
+    }
+}
+
+/// The ordered middleware run around every request of the plugin.
+///
+/// Registered with `add_request_middleware` and `add_response_middleware`.
+#[derive(Resource, Default, Clone)]
+pub struct HttpMiddleware {
+    request: Vec<Arc<dyn RequestMiddleware>>,
+    response: Vec<Arc<dyn ResponseMiddleware>>,
+    verifiers: Vec<Arc<dyn ResponseVerifier>>,
+    /// Where request metrics are sent, set by the plugin.
+    pub(crate) metrics: Option<Sender<MetricSample>>,
+    /// Where the progress of file uploads is sent, set by the plugin.
+    pub(crate) upload_progress: Option<Sender<UploadProgress>>,
+    /// Where sent and completed requests are recorded, set by `HttpHistoryPlugin`.
+    pub(crate) history: Option<Sender<HistoryEvent>>,
+    /// Number of fetches queued and not completed yet.
+    pub(crate) pending: Arc<AtomicUsize>,
+    /// The default `User-Agent` header, set from the `HttpUserAgent`.
+    pub(crate) user_agent: Option<String>,
+    /// Sends the requests going through a proxy or a custom resolver.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) transport: NativeTransport,
+}
+
+impl HttpMiddleware {
+    /// Appends a request middleware to the chain.
+    pub fn push_request(&mut self, middleware: impl RequestMiddleware) {
+        self.request.push(Arc::new(middleware));
+    }
+
+    /// Appends a response middleware to the chain.
+    pub fn push_response(&mut self, middleware: impl ResponseMiddleware) {
+        self.response.push(Arc::new(middleware));
+    }
+
+    /// Adds a verifier that every response must pass.
+    pub fn push_verifier(&mut self, verifier: impl ResponseVerifier) {
+        self.verifiers.push(Arc::new(verifier));
+    }
+
+    /// Adds the default headers the request does not set.
+    fn add_default_headers(&self, request: &mut Request) {
+        if request.headers.get("Accept").is_none() {
+            request.headers.insert("Accept", "*/*");
+        }
+        if let Some(user_agent) = &self.user_agent {
+            if request.headers.get("User-Agent").is_none() {
+                request.headers.insert("User-Agent", user_agent);
+            }
+        }
+    }
+
+    /// Sends `request` through the middleware chain, retrying up to `options.retries` times
+    /// after a network error, then failing over to `options.fallback_urls` in order.
+    ///
+    /// The default `Accept` and `User-Agent` are added before the request middleware run, unless
+    /// the request or the `HttpClientConfig` of its entity set them.
+    /// The response goes through the verifiers before the response middleware, a rejected one
+    /// becoming an error.
+    ///
+    /// The request is traced by an `http_request` span, tagged with its correlation ID if any,
+    /// from this call, when it is queued, until its result has been through the response
+    /// middleware.
+    pub(crate) fn fetch(
+        &self,
+        mut request: Request,
+        options: FetchOptions,
+    ) -> impl Future<Output = ehttp::Result<Response>> + Send + 'static {
+        let span = tracing::info_span!(
+            "http_request",
+            method = %request.method,
+            host = url::Url::parse(&request.url)
+                .ok()
+                .and_then(|url| url.host_str().map(ToString::to_string))
+                .unwrap_or_default(),
+            request_id = options.request_id.as_deref(),
+            status = tracing::field::Empty,
+            bytes = tracing::field::Empty,
+        );
+        span.in_scope(|| tracing::trace!("queued"));
+
+        let middleware = self.clone();
+        let url = request.url.clone();
+        let metric = move |event| {
+            if let Some(metrics) = &middleware.metrics {
+                metrics
+                    .send(MetricSample::new(
+                        &url,
+                        options.endpoint,
+                        options.client.clone(),
+                        event,
+                    ))
+                    .ok();
+            }
+        };
+        let middleware = self.clone();
+        middleware.pending.fetch_add(1, Ordering::SeqCst);
+        async move {
+            let start = Instant::now();
+            middleware.add_default_headers(&mut request);
+            // the request middleware run again on the original request for every fallback URL
+            let original = request.clone();
+            let mut fallback_urls = options.fallback_urls.clone().into_iter();
+            let mut record_id = None;
+            let mut first_attempt = true;
+            let mut result = loop {
+                let short_circuit = middleware
+                    .request
+                    .iter()
+                    .try_for_each(|middleware| middleware.handle_request(&mut request));
+
+                if std::mem::take(&mut first_attempt) {
+                    metric(MetricEvent::Sent);
+                    record_id = middleware.history.as_ref().map(|history| {
+                        let record = HttpRecord::new(request.clone(), options.request_id.clone());
+                        let id = record.id;
+                        history.send(HistoryEvent::Sent(record)).ok();
+                        id
+                    });
+                }
+                let result = match short_circuit {
+                    ControlFlow::Break(result) => result,
+                    ControlFlow::Continue(()) => {
+                        tracing::trace!("sent");
+                        let mut attempt = 0;
+                        loop {
+                            let body_file = options.body_file.as_ref();
+                            match middleware
+                                .send(&request, options.proxy.as_deref(), body_file)
+                                .await
+                            {
+                                Err(_) if attempt < options.retries => {
+                                    attempt += 1;
+                                    metric(MetricEvent::Retried);
+                                }
+                                result => break result,
+                            }
+                        }
+                    }
+                };
+                match (result, fallback_urls.next()) {
+                    (Err(e), Some(url)) => {
+                        tracing::debug!("{} failed: {e}, failing over to {url}", request.url);
+                        metric(MetricEvent::Retried);
+                        request = Request {
+                            url,
+                            ..original.clone()
+                        };
+                    }
+                    (result, _) => break result,
+                }
+            };
+
+            if let Ok(response) = &result {
+                let rejection = middleware
+                    .verifiers
+                    .iter()
+                    .find_map(|verifier| verifier.verify(&request, response).err());
+                if let Some(reason) = rejection {
+                    tracing::warn!("{VERIFICATION_FAILED} for {}: {reason}", request.url);
+                    result = Err(format!("{VERIFICATION_FAILED}: {reason}"));
+                }
+            }
+
+            let elapsed = start.elapsed();
+            let _ = middleware.response.iter().try_for_each(|middleware| {
+                middleware.handle_response(&request, &mut result, elapsed)
+            });
+
+            if let (Some(history), Some(id)) = (&middleware.history, record_id) {
+                history
+                    .send(HistoryEvent::Completed {
+                        id,
+                        result: result.clone(),
+                        duration: elapsed,
+                    })
+                    .ok();
+            }
+
+            let span = tracing::Span::current();
+            match &result {
+                Ok(response) => {
+                    span.record("status", response.status);
+                    span.record("bytes", response.bytes.len());
+                    metric(if response.ok {
+                        MetricEvent::Succeeded(elapsed)
+                    } else {
+                        MetricEvent::Failed(elapsed)
+                    });
+                }
+                Err(_) => metric(MetricEvent::Failed(elapsed)),
+            }
+            tracing::trace!("completed");
+            middleware.pending.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+        .instrument(span)
+    }
+
+    /// Streams the response of `request` to `on_data` part by part, see `ehttp::streaming`.
+    ///
+    /// The default headers and the request middleware apply as for `fetch`, a short-circuit
+    /// result being streamed as a single chunk. The response middleware, the verifiers, the
+    /// metrics and the history do not see the response, and the request goes out without the
+    /// proxy or the resolver of the client.
+    #[cfg(any(feature = "asset", feature = "sse"))]
+    pub(crate) fn stream(
+        &self,
+        mut request: Request,
+        on_data: impl Fn(ehttp::Result<ehttp::streaming::Part>) -> ControlFlow<()> + Send + 'static,
+    ) {
+        use ehttp::streaming::Part;
+
+        self.add_default_headers(&mut request);
+        let short_circuit = self
+            .request
+            .iter()
+            .try_for_each(|middleware| middleware.handle_request(&mut request));
+        match short_circuit {
+            ControlFlow::Continue(()) => ehttp::streaming::fetch(request, on_data),
+            ControlFlow::Break(Ok(response)) => {
+                let partial = ehttp::PartialResponse {
+                    url: response.url,
+                    ok: response.ok,
+                    status: response.status,
+                    status_text: response.status_text,
+                    headers: response.headers,
+                };
+                // an empty chunk ends the stream
+                if on_data(Ok(Part::Response(partial))).is_continue()
+                    && (response.bytes.is_empty()
+                        || on_data(Ok(Part::Chunk(response.bytes))).is_continue())
+                {
+                    let _ = on_data(Ok(Part::Chunk(Vec::new())));
+                }
+            }
+            ControlFlow::Break(Err(e)) => {
+                let _ = on_data(Err(e));
+            }
+        }
+    }
+
+    /// Resolves host names with `resolver` on native, see `HttpResolver`.
+    ///
+    /// Browsers resolve host names themselves, so this does nothing on wasm.
+    pub fn set_resolver(&mut self, resolver: impl HttpResolver) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.transport.set_resolver(resolver);
+        #[cfg(target_arch = "wasm32")]
+        let _ = resolver;
+    }
+
+    /// Connects over `ip_family` to dual-stack hosts on native, see `IpFamily`.
+    ///
+    /// Browsers pick the IP family themselves, so this does nothing on wasm.
+    pub fn set_ip_family(&mut self, ip_family: IpFamily) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.transport.set_ip_family(ip_family);
+        #[cfg(target_arch = "wasm32")]
+        let _ = ip_family;
+    }
+
+    /// Sends `request` over the network, through `proxy` if any, streaming `body_file` as its
+    /// body if any.
+    async fn send(
+        &self,
+        request: &Request,
+        proxy: Option<&str>,
+        body_file: Option<&FileBody>,
+    ) -> ehttp::Result<Response> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if proxy.is_some() || body_file.is_some() || self.transport.is_customized() {
+            return self
+                .transport
+                .fetch(request.clone(), proxy, body_file.cloned())
+                .await;
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = proxy;
+            if let Some(body_file) = body_file {
+                return Err(format!(
+                    "cannot upload {}, file bodies are only supported on native",
+                    body_file.path.display()
+                ));
+            }
+        }
+        ehttp::fetch_async(request.clone()).await
+    }
+
+    /// Records that a request to `url` was abandoned.
+    pub(crate) fn record_cancelled(
+        &self,
+        url: &str,
+        endpoint: Option<EndpointInfo>,
+        client: Option<String>,
+    ) {
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .send(MetricSample::new(
+                    url,
+                    endpoint,
+                    client,
+                    MetricEvent::Cancelled,
+                ))
+                .ok();
+        }
+    }
+}
+
+/// Per-request settings of `HttpMiddleware::fetch`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FetchOptions {
+    /// How many times the request is retried after a network error.
+    pub(crate) retries: u32,
+    /// The correlation ID of the request.
+    pub(crate) request_id: Option<String>,
+    /// The endpoint the request was built from.
+    pub(crate) endpoint: Option<EndpointInfo>,
+    /// The label of the client sending the request.
+    pub(crate) client: Option<String>,
+    /// The URLs tried in order after a network error.
+    pub(crate) fallback_urls: Vec<String>,
+    /// The URL of the proxy the request goes through.
+    pub(crate) proxy: Option<String>,
+    /// The file streamed as the body of the request.
+    pub(crate) body_file: Option<FileBody>,
+}
+
+impl std::fmt::Debug for HttpMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpMiddleware")
+            .field("request", &self.request.len())
+            .field("response", &self.response.len())
+            .field("verifiers", &self.verifiers.len())
+            .finish()
+    }
+}
+
+pub trait HttpMiddlewareAppExt {
+    /// Appends a middleware mutating every outgoing request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.add_request_middleware(|request: &mut Request| {
+    ///     request.headers.insert("Authorization", "Bearer token");
+    ///     ControlFlow::Continue(())
+    /// });
+    /// ```
+    fn add_request_middleware(&mut self, middleware: impl RequestMiddleware) -> &mut Self;
+
+    /// Appends a middleware inspecting every incoming response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.add_response_middleware(
+    ///     |request: &Request, result: &mut ehttp::Result<Response>, elapsed: Duration| {
+    ///         if elapsed > Duration::from_secs(1) {
+    ///             warn!("{} took {elapsed:?}", request.url);
+    ///         }
+    ///         ControlFlow::Continue(())
+    ///     },
+    /// );
+    /// ```
+    fn add_response_middleware(&mut self, middleware: impl ResponseMiddleware) -> &mut Self;
+
+    /// Adds a verifier that every response must pass, see `ResponseVerifier`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.add_response_verifier(|request: &Request, response: &Response| {
+    ///     match response.headers.get("X-Build") {
+    ///         Some(build) if build == EXPECTED_BUILD => Ok(()),
+    ///         _ => Err(format!("{} is not from the expected build", request.url)),
+    ///     }
+    /// });
+    /// ```
+    fn add_response_verifier(&mut self, verifier: impl ResponseVerifier) -> &mut Self;
+
+    /// Resolves host names with `resolver` on native, replacing the previous one, see
+    /// `HttpResolver`.
+    ///
+    /// With a proxy, only the host of the proxy is resolved, the proxy resolving the others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.set_http_resolver(|host: &str| match host {
+    ///     "api.example.com" => Some(vec![IpAddr::from([203, 0, 113, 7])]),
+    ///     host if host.ends_with(".local") => lan_hosts.lookup(host),
+    ///     _ => None,
+    /// });
+    /// ```
+    fn set_http_resolver(&mut self, resolver: impl HttpResolver) -> &mut Self;
+
+    /// Connects over `ip_family` to hosts with both IPv4 and IPv6 addresses on native, see
+    /// `IpFamily`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.set_http_ip_family(IpFamily::PreferIpv4);
+    /// ```
+    fn set_http_ip_family(&mut self, ip_family: IpFamily) -> &mut Self;
+}
+
+impl HttpMiddlewareAppExt for App {
+    fn add_request_middleware(&mut self, middleware: impl RequestMiddleware) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<HttpMiddleware>()
+            .push_request(middleware);
+        self
+    }
+
+    fn add_response_middleware(&mut self, middleware: impl ResponseMiddleware) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<HttpMiddleware>()
+            .push_response(middleware);
+        self
+    }
+
+    fn add_response_verifier(&mut self, verifier: impl ResponseVerifier) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<HttpMiddleware>()
+            .push_verifier(verifier);
+        self
+    }
+
+    fn set_http_resolver(&mut self, resolver: impl HttpResolver) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<HttpMiddleware>()
+            .set_resolver(resolver);
+        self
+    }
+
+    fn set_http_ip_family(&mut self, ip_family: IpFamily) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<HttpMiddleware>()
+            .set_ip_family(ip_family);
+        self
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    ops::ControlFlow,
+    sync::{Arc, RwLock},
+};
+
+use bevy_app::{App, Plugin};
+use bevy_ecs::system::Resource;
+use bevy_utils::Duration;
+use ehttp::{Headers, Request, Response};
+
+use crate::middleware::{HttpMiddlewareAppExt, RequestMiddleware};
+
+/// Plugin answering requests with the canned responses of `HttpMocks` instead of the network.
+///
+/// Mocked requests go through the normal pipeline, so the same events are sent as for a real
+/// fetch. Requests without a matching mock fail with a network error unless
+/// `HttpMocks::passthrough` is set. Other request middleware registered before the plugin runs
+/// first.
+///
+/// # Examples
+///
+/// ```
+/// let mut app = App::new();
+/// app.add_plugins((MinimalPlugins, HttpClientPlugin, HttpClientMockPlugin));
+/// app.world()
+///     .resource::<HttpMocks>()
+///     .add(Mock::get("https://example.com/users/*").json(&User { name: "Ada".into() }));
+/// ```
+#[derive(Default)]
+pub struct HttpClientMockPlugin;
+
+impl Plugin for HttpClientMockPlugin {
+    fn build(&self, app: &mut App) {
+        let mocks = HttpMocks::default();
+        app.add_request_middleware(MockTransport(mocks.clone()));
+        app.insert_resource(mocks);
+    }
+}
+
+/// The mocks of `HttpClientMockPlugin`, the first matching mock answers a request.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HttpMocks {
+    inner: Arc<RwLock<MocksInner>>,
+}
+
+#[derive(Debug, Default)]
+struct MocksInner {
+    mocks: Vec<Mock>,
+    passthrough: bool,
+}
+
+impl HttpMocks {
+    /// Adds a mock, after the existing ones.
+    pub fn add(&self, mock: Mock) -> &Self {
+        self.inner.write().unwrap().mocks.push(mock);
+        self
+    }
+
+    /// Removes all mocks.
+    pub fn clear(&self) {
+        self.inner.write().unwrap().mocks.clear();
+    }
+
+    /// Sets whether requests without a matching mock are sent to the network.
+    pub fn passthrough(&self, enabled: bool) {
+        self.inner.write().unwrap().passthrough = enabled;
+    }
+}
+
+/// A canned response for the requests matching a method and a URL pattern.
+#[derive(Debug, Clone)]
+pub struct Mock {
+    method: Option<String>,
+    pattern: String,
+    result: Result<Response, String>,
+    latency: Duration,
+}
+
+impl Mock {
+    /// Mocks the requests of any method whose URL matches `pattern`, where `*` matches any
+    /// characters. The response is an empty `200 OK` until set.
+    pub fn any(pattern: impl ToString) -> Self {
+        let pattern = pattern.to_string();
+        Self {
+            method: None,
+            result: Ok(Response {
+                url: pattern.clone(),
+                ok: true,
+                status: 200,
+                status_text: "OK".to_string(),
+                headers: Headers::default(),
+                bytes: vec![],
+            }),
+            pattern,
+            latency: Duration::ZERO,
+        }
+    }
+
+    /// Mocks the requests of `method` whose URL matches `pattern`.
+    pub fn new(method: impl ToString, pattern: impl ToString) -> Self {
+        Self {
+            method: Some(method.to_string()),
+            ..Self::any(pattern)
+        }
+    }
+
+    /// Mocks the `GET` requests whose URL matches `pattern`.
+    pub fn get(pattern: impl ToString) -> Self {
+        Self::new("GET", pattern)
+    }
+
+    /// Mocks the `POST` requests whose URL matches `pattern`.
+    pub fn post(pattern: impl ToString) -> Self {
+        Self::new("POST", pattern)
+    }
+
+    /// Sets the status of the response.
+    pub fn status(mut self, status: u16) -> Self {
+        if let Ok(response) = &mut self.result {
+            response.status = status;
+            response.ok = (200..300).contains(&status);
+            response.status_text = status_text(status).to_string();
+        }
+        self
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, key: impl ToString, value: impl ToString) -> Self {
+        if let Ok(response) = &mut self.result {
+            response.headers.insert(key, value);
+        }
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        if let Ok(response) = &mut self.result {
+            response.bytes = body.into();
+        }
+        self
+    }
+
+    /// Sets the body of the response to `body` as JSON, with its "Content-Type".
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `body` cannot be serialized to JSON.
+    pub fn json(self, body: &impl serde::Serialize) -> Self {
+        self.header("Content-Type", "application/json")
+            .body(serde_json::to_vec(body).expect("mock body must be serializable to JSON"))
+    }
+
+    /// Fails the request with the network error `err` instead of responding.
+    pub fn error(mut self, err: impl ToString) -> Self {
+        self.result = Err(err.to_string());
+        self
+    }
+
+    /// Delays the response by `latency`, blocking the task thread.
+    ///
+    /// Latency is ignored on wasm, where tasks cannot block.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Returns the mocked result, as the response of `request`.
+    pub(crate) fn result_for(&self, request: &Request) -> ehttp::Result<Response> {
+        self.result.clone().map(|mut response| {
+            response.url = request.url.clone();
+            response
+        })
+    }
+
+    pub(crate) fn matches(&self, request: &Request) -> bool {
+        self.method
+            .as_ref()
+            .is_none_or(|method| method.eq_ignore_ascii_case(&request.method))
+            && glob_match(&self.pattern, &request.url)
+    }
+}
+
+/// Request middleware answering requests from `HttpMocks`.
+struct MockTransport(HttpMocks);
+
+impl RequestMiddleware for MockTransport {
+    fn handle_request(&self, request: &mut Request) -> ControlFlow<ehttp::Result<Response>> {
+        let inner = self.0.inner.read().unwrap();
+        let Some(mock) = inner.mocks.iter().find(|mock| mock.matches(request)) else {
+            if inner.passthrough {
+                return ControlFlow::Continue(());
+            }
+            return ControlFlow::Break(Err(format!(
+                "no mock for {} {}",
+                request.method, request.url
+            )));
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !mock.latency.is_zero() {
+            std::thread::sleep(mock.latency);
+        }
+        ControlFlow::Break(mock.result_for(request))
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // no wildcard, the whole text must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
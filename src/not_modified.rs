@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use bevy_derive::Deref;
+use bevy_ecs::prelude::*;
+use bevy_utils::Duration;
+use ehttp::{Request, Response};
+
+use crate::observe::{deliver, Recipient};
+
+/// Sent instead of the response when a conditional request is answered with 304 Not Modified.
+///
+/// A request is conditional when it sets an `If-None-Match` or `If-Modified-Since` header. Its
+/// 304 response has no body, so it is neither an `HttpResponse` nor a `TypedResponse`, nor a
+/// deserialization error: the copy cached with these validators is still valid.
+///
+/// # Examples
+///
+/// ```
+/// fn refresh_news(mut not_modified: EventReader<HttpNotModified>) {
+///     for event in not_modified.read() {
+///         println!("{:?} is still fresh", event.validators.if_none_match);
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone, Deref)]
+pub struct HttpNotModified {
+    #[deref]
+    pub response: Arc<Response>,
+    /// The validators the request was sent with.
+    pub validators: ConditionalValidators,
+    /// label of the request, see `HttpClient::label`
+    pub label: Option<String>,
+    /// time from starting the request to receiving the response, retries included
+    pub duration: Duration,
+}
+
+/// The validators of a conditional request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConditionalValidators {
+    /// The `If-None-Match` header, the ETag of the cached copy.
+    pub if_none_match: Option<String>,
+    /// The `If-Modified-Since` header, the date of the cached copy.
+    pub if_modified_since: Option<String>,
+}
+
+impl ConditionalValidators {
+    /// The validators of `request`, `None` if it is not conditional.
+    pub(crate) fn of(request: &Request) -> Option<Self> {
+        let validators = Self {
+            if_none_match: request
+                .headers
+                .get("If-None-Match")
+                .map(ToString::to_string),
+            if_modified_since: request
+                .headers
+                .get("If-Modified-Since")
+                .map(ToString::to_string),
+        };
+        (validators != Self::default()).then_some(validators)
+    }
+}
+
+/// Delivers `response` as `HttpNotModified` if it is a 304 to a request sent with `validators`,
+/// giving it back otherwise.
+pub(crate) fn deliver_not_modified(
+    world: &mut World,
+    recipient: Recipient,
+    validators: Option<&ConditionalValidators>,
+    response: Response,
+    label: &Option<String>,
+    duration: Duration,
+) -> Result<(), Response> {
+    match validators {
+        Some(validators) if response.status == 304 => {
+            deliver(
+                world,
+                recipient,
+                HttpNotModified {
+                    response: Arc::new(response),
+                    validators: validators.clone(),
+                    label: label.clone(),
+                    duration,
+                },
+            );
+            Ok(())
+        }
+        _ => Err(response),
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::{
+    prelude::{Commands, Entity, Event, Trigger, World},
+    system::EntityCommands,
+};
+use serde::Deserialize;
+
+use crate::{
+    not_modified::HttpNotModified,
+    typed::{TypedResponse, TypedResponseError},
+    HttpResponse, HttpResponseError,
+};
+
+/// A result of a request made `from_entity`, triggered on that entity before it is sent as an
+/// event.
+///
+/// `E` is `HttpResponse`, `HttpResponseError`, `TypedResponse<T>` or `TypedResponseError<T>`.
+///
+/// # Examples
+///
+/// ```
+/// commands
+///     .spawn(PlayerCard)
+///     .observe(|trigger: Trigger<HttpObserved<HttpResponse>>| {
+///         println!("{} got {}", trigger.entity(), trigger.status);
+///     });
+/// ```
+#[derive(Event, Debug, Deref, DerefMut)]
+pub struct HttpObserved<E> {
+    #[deref]
+    inner: E,
+    entity: Entity,
+}
+
+impl<E> HttpObserved<E> {
+    /// The entity the request was made from.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+/// Where the results of a request go.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Recipient {
+    /// The entity the request was made from.
+    pub(crate) entity: Option<Entity>,
+    /// Skip the global event, the results only go to the observers of `entity`.
+    pub(crate) observers_only: bool,
+}
+
+impl Recipient {
+    /// Whether the results are sent as global events.
+    pub(crate) fn broadcast(&self) -> bool {
+        !(self.observers_only && self.entity.is_some())
+    }
+}
+
+/// Triggers `event` on the entity of `recipient` if any, then sends it unless the recipient only
+/// wants observer triggers.
+pub(crate) fn deliver<E: Event>(world: &mut World, recipient: Recipient, event: E) {
+    let event = match recipient.entity {
+        Some(entity) if world.get_entity(entity).is_ok() => {
+            let mut observed = HttpObserved {
+                inner: event,
+                entity,
+            };
+            world.trigger_targets_ref(&mut observed, entity);
+            observed.inner
+        }
+        _ => event,
+    };
+    if recipient.broadcast() {
+        world.send_event(event);
+    }
+}
+
+/// A result of an `HttpRequest` observed by the crate, e.g. by a `RequestGroup`.
+///
+/// A 304 to a conditional request counts as a response.
+pub(crate) trait RawResult: Event + Clone {
+    fn into_result(self) -> Result<HttpResponse, HttpResponseError>;
+}
+
+impl RawResult for HttpResponse {
+    fn into_result(self) -> Result<HttpResponse, HttpResponseError> {
+        Ok(self)
+    }
+}
+
+impl RawResult for HttpResponseError {
+    fn into_result(self) -> Result<HttpResponse, HttpResponseError> {
+        Err(self)
+    }
+}
+
+impl RawResult for HttpNotModified {
+    fn into_result(self) -> Result<HttpResponse, HttpResponseError> {
+        Ok(HttpResponse::new(self.response)
+            .label(self.label)
+            .duration(self.duration))
+    }
+}
+
+/// Observers of the responses to requests made from an entity.
+pub trait HttpObserveExt {
+    /// Observes the `TypedResponse<T>` and `TypedResponseError<T>` of the requests made from
+    /// this entity with a single handler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// commands
+    ///     .spawn(PlayerCard)
+    ///     .observe_http::<Profile>(|entity, result, commands| match result {
+    ///         Ok(profile) => {
+    ///             commands.entity(entity).insert(Name::new(profile.name.clone()));
+    ///         }
+    ///         Err(err) => println!("failed to load profile: {}", err.err),
+    ///     });
+    /// ```
+    fn observe_http<T>(
+        &mut self,
+        handler: impl Fn(Entity, Result<&TypedResponse<T>, &TypedResponseError<T>>, &mut Commands)
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + 'static;
+
+    /// Observes the `HttpResponse` and `HttpResponseError` of the requests made from this entity
+    /// with a single handler.
+    fn observe_http_raw(
+        &mut self,
+        handler: impl Fn(Entity, Result<&HttpResponse, &HttpResponseError>, &mut Commands)
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self;
+}
+
+impl HttpObserveExt for EntityCommands<'_> {
+    fn observe_http<T>(
+        &mut self,
+        handler: impl Fn(Entity, Result<&TypedResponse<T>, &TypedResponseError<T>>, &mut Commands)
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+    {
+        let on_response = Arc::new(handler);
+        let on_error = on_response.clone();
+        self.observe(
+            move |trigger: Trigger<HttpObserved<TypedResponse<T>>>, mut commands: Commands| {
+                on_response(trigger.entity(), Ok(&trigger.event().inner), &mut commands);
+            },
+        )
+        .observe(
+            move |trigger: Trigger<HttpObserved<TypedResponseError<T>>>, mut commands: Commands| {
+                on_error(trigger.entity(), Err(&trigger.event().inner), &mut commands);
+            },
+        )
+    }
+
+    fn observe_http_raw(
+        &mut self,
+        handler: impl Fn(Entity, Result<&HttpResponse, &HttpResponseError>, &mut Commands)
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        let on_response = Arc::new(handler);
+        let on_error = on_response.clone();
+        self.observe(
+            move |trigger: Trigger<HttpObserved<HttpResponse>>, mut commands: Commands| {
+                on_response(trigger.entity(), Ok(&trigger.event().inner), &mut commands);
+            },
+        )
+        .observe(
+            move |trigger: Trigger<HttpObserved<HttpResponseError>>, mut commands: Commands| {
+                on_error(trigger.entity(), Err(&trigger.event().inner), &mut commands);
+            },
+        )
+    }
+}
@@ -0,0 +1,394 @@
+use bevy_app::App;
+use bevy_ecs::{prelude::*, world::CommandQueue};
+use bevy_utils::Instant;
+use ehttp::Request;
+use serde::Deserialize;
+use std::{fmt, marker::PhantomData};
+
+use crate::{
+    api_url::ApiUrl,
+    attach_request_id,
+    error::HttpError,
+    http_schedule,
+    link::parse_link_headers,
+    middleware::{FetchOptions, HttpMiddleware},
+    redact::{DebugRedaction, RedactDebug},
+    typed::{from_json_slice, TypedErrorKind, TypedResponse, TypedResponseError},
+    HttpClient, HttpClientSet, HttpClientSetting, HttpClock, HttpDispatcher, TaskTags,
+};
+
+pub trait HttpPaginatedRequestTrait {
+    /// Registers a paginated request type `T` to the application.
+    ///
+    /// `T` is the type of a single page body. Every page is sent as a `TypedResponse<Page<T>>`
+    /// event and a `PaginationComplete` event is sent once the last page was fetched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_paginated_type::<FriendsPage>();
+    /// ```
+    fn register_paginated_type<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+    ) -> &mut Self;
+}
+
+impl HttpPaginatedRequestTrait for App {
+    fn register_paginated_type<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+    ) -> &mut Self {
+        self.add_event::<PaginatedRequest<T>>();
+        self.add_event::<TypedResponse<Page<T>>>();
+        self.add_event::<TypedResponseError<Page<T>>>();
+        self.add_event::<PaginationComplete>();
+        let schedule = http_schedule(self);
+        self.add_systems(
+            schedule,
+            handle_paginated_request::<T>.in_set(HttpClientSet::Dispatch),
+        );
+        self
+    }
+}
+
+/// How the next page of a paginated request is located.
+#[derive(Debug, Clone)]
+pub enum Pagination {
+    /// Follow the `rel="next"` entry of the `Link` response header.
+    Link,
+    /// Read a cursor from the response body and send it as a query parameter.
+    Cursor {
+        /// JSON pointer to the cursor in the body, e.g. `/meta/next_cursor`.
+        pointer: String,
+        /// Query parameter carrying the cursor in the next request, e.g. `cursor`.
+        param: String,
+    },
+}
+
+impl Pagination {
+    /// Pagination reading the cursor at `pointer` and sending it as the `param` query parameter.
+    pub fn cursor(pointer: impl ToString, param: impl ToString) -> Self {
+        Self::Cursor {
+            pointer: pointer.to_string(),
+            param: param.to_string(),
+        }
+    }
+}
+
+/// A request that follows pages until the last one, or until `max_pages` pages were fetched.
+///
+/// # Examples
+///
+/// ```
+/// fn fetch_friends(mut ev_request: EventWriter<PaginatedRequest<FriendsPage>>) {
+///     ev_request.send(
+///         HttpClient::new()
+///             .get("https://example.com/friends")
+///             .paginate::<FriendsPage>(Pagination::cursor("/next_cursor", "cursor"))
+///             .max_pages(20),
+///     );
+/// }
+/// ```
+#[derive(Event)]
+pub struct PaginatedRequest<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    pub from_entity: Option<Entity>,
+    pub request: Request,
+    pub pagination: Pagination,
+    /// Safety cap on the number of pages fetched.
+    pub max_pages: usize,
+    /// The label delivered with every page, see `HttpClient::label`.
+    pub label: Option<String>,
+    inner: PhantomData<T>,
+}
+
+impl<T: for<'a> Deserialize<'a>> PaginatedRequest<T> {
+    pub fn new(request: Request, from_entity: Option<Entity>, pagination: Pagination) -> Self {
+        Self {
+            from_entity,
+            request,
+            pagination,
+            max_pages: 100,
+            label: None,
+            inner: PhantomData,
+        }
+    }
+
+    /// Sets the maximum number of pages to fetch, 100 by default.
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> RedactDebug for PaginatedRequest<T> {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>, redaction: &DebugRedaction) -> fmt::Result {
+        f.debug_struct("PaginatedRequest")
+            .field("from_entity", &self.from_entity)
+            .field("request", &redaction.request(&self.request))
+            .field("pagination", &self.pagination)
+            .field("max_pages", &self.max_pages)
+            .field("label", &self.label)
+            .finish()
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> fmt::Debug for PaginatedRequest<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_redacted(f, &DebugRedaction::default())
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> Clone for PaginatedRequest<T> {
+    fn clone(&self) -> Self {
+        Self {
+            from_entity: self.from_entity,
+            request: self.request.clone(),
+            pagination: self.pagination.clone(),
+            max_pages: self.max_pages,
+            label: self.label.clone(),
+            inner: PhantomData,
+        }
+    }
+}
+
+impl HttpClient {
+    /// Builds a `PaginatedRequest` whose pages are deserialized into `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("https://example.com/friends")
+    ///     .paginate::<FriendsPage>(Pagination::Link);
+    /// ```
+    pub fn paginate<T: for<'a> Deserialize<'a>>(
+        self,
+        pagination: Pagination,
+    ) -> PaginatedRequest<T> {
+        let from_entity = self.from_entity;
+        let label = self.label.clone();
+        let mut request = PaginatedRequest::new(self.into_request(), from_entity, pagination);
+        request.label = label;
+        request
+    }
+}
+
+/// A single page of a paginated request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Page<T> {
+    /// Zero-based index of the page.
+    pub index: usize,
+    /// The deserialized page body.
+    pub data: T,
+    /// URL of the next page, if any.
+    pub next: Option<String>,
+}
+
+/// Sent once a paginated request stopped fetching pages.
+#[derive(Event, Debug, Clone)]
+pub struct PaginationComplete {
+    pub from_entity: Option<Entity>,
+    /// URL of the first page.
+    pub url: String,
+    /// Number of pages delivered.
+    pub pages: usize,
+    /// The `max_pages` cap was reached while more pages were available.
+    pub truncated: bool,
+    /// A page failed, its error was sent as a `TypedResponseError<Page<T>>`.
+    pub failed: bool,
+}
+
+fn handle_paginated_request<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    mut req_res: ResMut<HttpClientSetting>,
+    mut requests: EventReader<PaginatedRequest<T>>,
+    mut waiting: Local<Vec<PaginatedRequest<T>>>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+    middleware: Res<HttpMiddleware>,
+    clock: Res<HttpClock>,
+) {
+    let thread_pool = req_res.fetch_pool.get();
+    // the requests waiting for a free slot go first
+    req_res.queued -= waiting.len();
+    let waiting_requests = std::mem::take(&mut *waiting);
+    for request in waiting_requests.into_iter().chain(requests.read().cloned()) {
+        if req_res.try_start(clock.now()) {
+            let mut req = request.request.clone();
+            let pagination = request.pagination.clone();
+            let max_pages = request.max_pages;
+            let from_entity = request.from_entity;
+            let label = request.label.clone();
+            let middleware = middleware.clone();
+            let request_id_header = req_res.request_id_header.clone();
+            // the pages are sent as parts of the result, the request ending with the last one
+            let url = req.url.clone();
+            let sender = dispatcher.start(
+                clock.now(),
+                None,
+                TaskTags {
+                    label: label.clone(),
+                    from_entity,
+                    ..Default::default()
+                },
+                move |world, _| {
+                    world.send_event(PaginationComplete {
+                        from_entity,
+                        url,
+                        pages: 0,
+                        truncated: false,
+                        failed: true,
+                    });
+                },
+            );
+
+            thread_pool
+                .spawn(async move {
+                    let mut complete = PaginationComplete {
+                        from_entity,
+                        url: req.url.clone(),
+                        pages: 0,
+                        truncated: false,
+                        failed: false,
+                    };
+
+                    loop {
+                        let mut command_queue = CommandQueue::default();
+                        // every page gets its own correlation ID
+                        let mut page_req = req.clone();
+                        let request_id = request_id_header
+                            .as_deref()
+                            .map(|header| attach_request_id(&mut page_req, header));
+                        let started = Instant::now();
+                        let page = fetch_page::<T>(
+                            &middleware,
+                            &page_req,
+                            request_id.as_deref(),
+                            &pagination,
+                            complete.pages,
+                        )
+                        .await;
+                        let duration = started.elapsed();
+                        let next = match page {
+                            Ok(page) => {
+                                let next = page.next.clone();
+                                complete.pages += 1;
+                                let label = label.clone();
+                                command_queue.push(move |world: &mut World| {
+                                    world
+                                        .get_resource_mut::<Events<TypedResponse<Page<T>>>>()
+                                        .unwrap()
+                                        .send(
+                                            TypedResponse::new(page)
+                                                .with_request_id(request_id)
+                                                .with_label(label)
+                                                .with_duration(duration),
+                                        );
+                                });
+                                next
+                            }
+                            Err(e) => {
+                                complete.failed = true;
+                                let label = label.clone();
+                                command_queue.push(move |world: &mut World| {
+                                    world
+                                        .get_resource_mut::<Events<TypedResponseError<Page<T>>>>()
+                                        .unwrap()
+                                        .send(
+                                            e.request_id(request_id)
+                                                .label(label)
+                                                .duration(duration),
+                                        );
+                                });
+                                None
+                            }
+                        };
+
+                        match next {
+                            Some(_) if complete.pages >= max_pages => {
+                                complete.truncated = true;
+                            }
+                            Some(url) => {
+                                req.url = url;
+                                // stop fetching once the request was cancelled
+                                if sender.send_part(command_queue) {
+                                    continue;
+                                }
+                                break;
+                            }
+                            None => {}
+                        }
+
+                        command_queue.push(move |world: &mut World| {
+                            world
+                                .get_resource_mut::<Events<PaginationComplete>>()
+                                .unwrap()
+                                .send(complete);
+                        });
+                        sender.send(command_queue);
+                        break;
+                    }
+                })
+                .detach();
+        } else {
+            waiting.push(request);
+        }
+    }
+    req_res.queued += waiting.len();
+}
+
+/// Fetches and deserializes one page, locating the URL of the next one.
+async fn fetch_page<T: for<'a> Deserialize<'a>>(
+    middleware: &HttpMiddleware,
+    request: &Request,
+    request_id: Option<&str>,
+    pagination: &Pagination,
+    index: usize,
+) -> Result<Page<T>, TypedResponseError<Page<T>>> {
+    let response = middleware
+        .fetch(
+            request.clone(),
+            FetchOptions {
+                request_id: request_id.map(ToString::to_string),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| TypedResponseError::network(HttpError::transport(e)))?;
+    if !response.ok {
+        return Err(TypedResponseError::new(format!(
+            "HTTP status {} {}",
+            response.status, response.status_text
+        ))
+        .kind(TypedErrorKind::Status)
+        .response(response));
+    }
+
+    let next = match pagination {
+        Pagination::Link => parse_link_headers(&response.headers, &request.url)
+            .into_iter()
+            .find(|link| link.rel.eq_ignore_ascii_case("next"))
+            .map(|link| link.href),
+        Pagination::Cursor { pointer, param } => {
+            serde_json::from_slice::<serde_json::Value>(&response.bytes)
+                .ok()
+                .and_then(|body| match body.pointer(pointer) {
+                    Some(serde_json::Value::String(cursor)) => Some(cursor.clone()),
+                    Some(serde_json::Value::Number(cursor)) => Some(cursor.to_string()),
+                    _ => None,
+                })
+                .and_then(|cursor| with_query_param(&request.url, param, &cursor))
+        }
+    };
+
+    match from_json_slice::<T>(&response.bytes) {
+        Ok(data) => Ok(Page { index, data, next }),
+        Err(e) => Err(e.into_error().response(response)),
+    }
+}
+
+/// Returns `url` with the query parameter `param` set to `value`.
+fn with_query_param(url: &str, param: &str, value: &str) -> Option<String> {
+    let url = ApiUrl::parse(url).ok()?.set_query(param, value);
+    Some(url.into())
+}
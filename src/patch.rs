@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::HttpClient;
+
+/// An operation of a JSON Patch document, see RFC 6902 and `HttpClient::json_patch`.
+///
+/// Paths are JSON Pointers, e.g. `/inventory/3/count`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Adds `value` at `path`, inserting it into an array or replacing a member of an object.
+    Add { path: String, value: Value },
+    /// Removes the value at `path`.
+    Remove { path: String },
+    /// Replaces the value at `path` with `value`.
+    Replace { path: String, value: Value },
+    /// Moves the value at `from` to `path`.
+    Move { from: String, path: String },
+    /// Copies the value at `from` to `path`.
+    Copy { from: String, path: String },
+    /// Fails the whole patch unless the value at `path` is `value`.
+    Test { path: String, value: Value },
+}
+
+impl HttpClient {
+    /// Sets a JSON Patch body, e.g. a list of `PatchOperation`, with the
+    /// "application/json-patch+json" content type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `operations` fail to serialize, like `json`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .patch("https://example.com/players/7")
+    ///     .json_patch(&[
+    ///         PatchOperation::Test {
+    ///             path: "/gold".to_string(),
+    ///             value: json!(120),
+    ///         },
+    ///         PatchOperation::Replace {
+    ///             path: "/gold".to_string(),
+    ///             value: json!(70),
+    ///         },
+    ///     ])
+    ///     .build();
+    /// ```
+    pub fn json_patch(self, operations: &impl Serialize) -> Self {
+        let body = serde_json::to_vec(operations).unwrap();
+        self.typed_body(body, "application/json-patch+json")
+    }
+
+    /// Sets a JSON Merge Patch body with the "application/merge-patch+json" content type, see
+    /// RFC 7386: the members of `partial` replace those of the resource, `null` removing them.
+    ///
+    /// Use `merge_patch_diff` to compute it from two versions of the resource.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partial` fails to serialize, like `json`.
+    pub fn merge_patch(self, partial: &impl Serialize) -> Self {
+        let body = serde_json::to_vec(partial).unwrap();
+        self.typed_body(body, "application/merge-patch+json")
+    }
+}
+
+/// Computes the JSON Merge Patch turning `before` into `after`, see `HttpClient::merge_patch`.
+///
+/// Only the changed members are kept, and removed ones are set to `null`. A merge patch cannot
+/// set a member to `null`, so a member changed to `null` is removed, nor change part of an
+/// array, so a changed array is replaced as a whole.
+///
+/// # Examples
+///
+/// ```
+/// let patch = merge_patch_diff(&saved_settings, &settings)?;
+/// let request = HttpClient::new()
+///     .patch("https://example.com/settings")
+///     .merge_patch(&patch)
+///     .build();
+/// ```
+pub fn merge_patch_diff(
+    before: &impl Serialize,
+    after: &impl Serialize,
+) -> Result<Value, serde_json::Error> {
+    Ok(diff(
+        serde_json::to_value(before)?,
+        serde_json::to_value(after)?,
+    ))
+}
+
+fn diff(before: Value, after: Value) -> Value {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => Value::Object(diff_objects(before, after)),
+        (_, after) => after,
+    }
+}
+
+fn diff_objects(mut before: Map<String, Value>, after: Map<String, Value>) -> Map<String, Value> {
+    let mut patch = Map::new();
+    for (key, value) in after {
+        match before.remove(&key) {
+            Some(previous) if previous == value => {}
+            Some(previous @ Value::Object(_)) if value.is_object() => {
+                patch.insert(key, diff(previous, value));
+            }
+            // a member cannot be set to null, only removed
+            None if value.is_null() => {}
+            _ => {
+                patch.insert(key, value);
+            }
+        }
+    }
+    for (key, _) in before {
+        patch.insert(key, Value::Null);
+    }
+    patch
+}
@@ -0,0 +1,184 @@
+use std::path::PathBuf;
+
+use base64::Engine;
+use bevy_utils::Duration;
+use ehttp::{Headers, Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backoff::RetryPolicy, transport::RequestProxy, HttpRequest, RequestCoalescing, RequestDelay,
+};
+
+/// The serialized form of an `HttpRequest`.
+///
+/// Only `method` and `url` are required, so requests can be written by hand in data files. The
+/// entity the request was made from and the instant of `send_at` are not serialized, they do not
+/// outlive the app. A `send_after` delay is kept.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "HttpRequest")]
+pub(crate) struct SerializedRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "RecordedBody::is_empty")]
+    body: RecordedBody,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout: Option<Duration>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    observers_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delay: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    coalescing: Option<RequestCoalescing>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fallback_urls: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry: Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proxy: Option<RequestProxy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_file: Option<PathBuf>,
+}
+
+impl From<HttpRequest> for SerializedRequest {
+    fn from(request: HttpRequest) -> Self {
+        Self {
+            method: request.request.method,
+            url: request.request.url,
+            headers: request.request.headers.headers,
+            body: RecordedBody::new(&request.request.body),
+            timeout: request.timeout,
+            observers_only: request.observers_only,
+            client: request.client,
+            label: request.label,
+            delay: match request.delay {
+                Some(RequestDelay::After(delay)) => Some(delay),
+                _ => None,
+            },
+            coalescing: request.coalescing,
+            fallback_urls: request.fallback_urls,
+            retry: request.retry,
+            proxy: request.proxy,
+            body_file: request.body_file,
+        }
+    }
+}
+
+impl From<SerializedRequest> for HttpRequest {
+    fn from(request: SerializedRequest) -> Self {
+        Self {
+            from_entity: None,
+            request: Request {
+                method: request.method,
+                body: request.body.to_bytes(),
+                headers: Headers {
+                    headers: request.headers,
+                },
+                ..Request::get(request.url)
+            },
+            timeout: request.timeout,
+            observers_only: request.observers_only,
+            client: request.client,
+            label: request.label,
+            delay: request.delay.map(RequestDelay::After),
+            coalescing: request.coalescing,
+            fallback_urls: request.fallback_urls,
+            retry: request.retry,
+            proxy: request.proxy,
+            body_file: request.body_file,
+        }
+    }
+}
+
+/// The serializable part of a response, e.g. to persist it or to replay it later.
+///
+/// # Examples
+///
+/// ```
+/// fn save_responses(mut ev_response: EventReader<HttpResponse>) {
+///     for response in ev_response.read() {
+///         let recorded = RecordedResponse::from(&**response);
+///         let json = serde_json::to_string(&recorded).unwrap();
+///         // …
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub url: String,
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    /// The body, as text when it is valid UTF-8, as base64 otherwise.
+    pub body: RecordedBody,
+}
+
+impl From<&Response> for RecordedResponse {
+    fn from(response: &Response) -> Self {
+        Self {
+            url: response.url.clone(),
+            status: response.status,
+            status_text: response.status_text.clone(),
+            headers: response.headers.headers.clone(),
+            body: RecordedBody::new(&response.bytes),
+        }
+    }
+}
+
+impl RecordedResponse {
+    /// Rebuilds the response.
+    pub fn to_response(&self) -> Response {
+        Response {
+            url: self.url.clone(),
+            ok: (200..300).contains(&self.status),
+            status: self.status,
+            status_text: self.status_text.clone(),
+            headers: Headers {
+                headers: self.headers.clone(),
+            },
+            bytes: self.body.to_bytes(),
+        }
+    }
+}
+
+/// A body kept as text when possible to keep the serialized data readable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedBody {
+    Text(String),
+    Base64(String),
+}
+
+impl Default for RecordedBody {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl RecordedBody {
+    pub fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_string()),
+            Err(_) => Self::Base64(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        }
+    }
+
+    /// The bytes of the body, empty if the base64 is invalid.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Text(text) => text.as_bytes().to_vec(),
+            Self::Base64(encoded) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Self::Text(text) if text.is_empty())
+    }
+}
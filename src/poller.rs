@@ -0,0 +1,162 @@
+use bevy_ecs::{prelude::*, system::EntityCommands};
+use bevy_utils::{Duration, Instant};
+use serde::Deserialize;
+
+use crate::{
+    observe::HttpObserved,
+    typed::{TypedResponse, TypedResponseError},
+    HttpClient, HttpClock, HttpResponse, HttpResponseError,
+};
+
+/// Sends a request every interval, from the entity it is added to.
+///
+/// The responses go through the usual pipelines, as `HttpResponse` events, or as
+/// `TypedResponse<T>` events for a poller made `typed::<T>()`. The first request is sent when the
+/// poller is added. After a failure, the interval doubles up to the backoff limit, if any, and it
+/// goes back to normal after a success.
+///
+/// # Examples
+///
+/// ```
+/// commands.spawn(
+///     HttpPoller::new(HttpClient::new().get("https://example.com/status"), Duration::from_secs(10))
+///         .jitter(Duration::from_secs(2))
+///         .backoff_on_error(Duration::from_secs(120))
+///         .typed::<ServerStatus>(),
+/// );
+/// ```
+#[derive(Component, Clone)]
+pub struct HttpPoller {
+    client: HttpClient,
+    interval: Duration,
+    jitter: Duration,
+    max_backoff: Option<Duration>,
+    /// Consecutive failed polls.
+    failures: u32,
+    next_poll: Option<Instant>,
+    send: fn(HttpClient, &mut Commands),
+    observe: fn(&mut EntityCommands),
+}
+
+impl HttpPoller {
+    /// Polls with the request built by `client` every `interval`.
+    pub fn new(client: HttpClient, interval: Duration) -> Self {
+        Self {
+            client,
+            interval,
+            jitter: Duration::ZERO,
+            max_backoff: None,
+            failures: 0,
+            next_poll: None,
+            send: |client, commands| {
+                commands.send_event(client.build());
+            },
+            observe: |entity| {
+                entity
+                    .observe(
+                        |trigger: Trigger<HttpObserved<HttpResponse>>,
+                         mut pollers: Query<&mut HttpPoller>,
+                         clock: Res<HttpClock>| {
+                            if let Ok(mut poller) = pollers.get_mut(trigger.entity()) {
+                                poller.record(trigger.event().ok, clock.now());
+                            }
+                        },
+                    )
+                    .observe(failed::<HttpResponseError>);
+            },
+        }
+    }
+
+    /// Sends the requests as `TypedRequest<T>`, whose results arrive as `TypedResponse<T>`.
+    pub fn typed<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(mut self) -> Self {
+        self.send = |client, commands| {
+            commands.send_event(client.with_type::<T>());
+        };
+        self.observe = |entity| {
+            entity
+                .observe(
+                    |trigger: Trigger<HttpObserved<TypedResponse<T>>>,
+                     mut pollers: Query<&mut HttpPoller>,
+                     clock: Res<HttpClock>| {
+                        if let Ok(mut poller) = pollers.get_mut(trigger.entity()) {
+                            poller.record(true, clock.now());
+                        }
+                    },
+                )
+                .observe(failed::<TypedResponseError<T>>);
+        };
+        self
+    }
+
+    /// Delays every poll by a random duration up to `jitter`, so clients do not poll in sync.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Doubles the interval after each consecutive failure, up to `max`.
+    pub fn backoff_on_error(mut self, max: Duration) -> Self {
+        self.max_backoff = Some(max);
+        self
+    }
+
+    /// The number of consecutive failed polls.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Polls at the next update.
+    pub fn poll_now(&mut self) {
+        self.next_poll = None;
+    }
+
+    /// The delay until the next poll.
+    fn delay(&self) -> Duration {
+        let interval = match self.max_backoff {
+            Some(max) if self.failures > 0 => self
+                .interval
+                .saturating_mul(2u32.saturating_pow(self.failures.min(16)))
+                .min(max.max(self.interval)),
+            _ => self.interval,
+        };
+        interval + self.jitter.mul_f64(fastrand::f64())
+    }
+
+    /// Schedules the next poll after a result.
+    fn record(&mut self, ok: bool, now: Instant) {
+        self.failures = if ok { 0 } else { self.failures + 1 };
+        self.next_poll = Some(now + self.delay());
+    }
+}
+
+/// Records a failed poll.
+fn failed<E: Event>(
+    trigger: Trigger<HttpObserved<E>>,
+    mut pollers: Query<&mut HttpPoller>,
+    clock: Res<HttpClock>,
+) {
+    if let Ok(mut poller) = pollers.get_mut(trigger.entity()) {
+        poller.record(false, clock.now());
+    }
+}
+
+/// A system that sends the requests of the pollers that are due.
+pub(crate) fn handle_pollers(
+    mut commands: Commands,
+    mut pollers: Query<(Entity, &mut HttpPoller)>,
+    clock: Res<HttpClock>,
+) {
+    let now = clock.now();
+    for (entity, mut poller) in pollers.iter_mut() {
+        if poller.is_added() {
+            (poller.observe)(&mut commands.entity(entity));
+        }
+        if poller.next_poll.is_some_and(|next| next > now) {
+            continue;
+        }
+        // polls again after the delay if the request never completes, e.g. when dropped
+        poller.next_poll = Some(now + poller.delay());
+        let client = poller.client.clone().entity(entity);
+        (poller.send)(client, &mut commands);
+    }
+}
@@ -0,0 +1,82 @@
+use bevy_utils::tracing;
+use ehttp::Response;
+use serde::Deserialize;
+
+use crate::{not_modified::HttpNotModified, typed::TypedResponse, HttpClient, HttpResponse};
+
+/// Access to the `ETag` of a response, the version of the resource it carries.
+pub trait ResponseETag {
+    /// Returns the `ETag` header, `None` if the response has none.
+    fn etag(&self) -> Option<&str>;
+}
+
+impl ResponseETag for Response {
+    fn etag(&self) -> Option<&str> {
+        self.headers.get("ETag")
+    }
+}
+
+impl ResponseETag for HttpResponse {
+    fn etag(&self) -> Option<&str> {
+        self.response.etag()
+    }
+}
+
+impl ResponseETag for HttpNotModified {
+    fn etag(&self) -> Option<&str> {
+        self.response.etag()
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> ResponseETag for TypedResponse<T> {
+    fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+}
+
+impl HttpClient {
+    /// Sends the request only if the resource still has the ETag `etag`, setting `If-Match`.
+    ///
+    /// Otherwise the server answers 412 Precondition Failed, a typed request failing with
+    /// `TypedErrorKind::PreconditionFailed`.
+    pub fn if_match(self, etag: impl ToString) -> Self {
+        self.header("If-Match", etag)
+    }
+
+    /// Sends the request only if the resource was not changed since `response` was read,
+    /// setting `If-Match` to its ETag, e.g. to update it without overwriting a concurrent update.
+    ///
+    /// A response without an ETag leaves the request unconditional, with a warning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// fn rename_guild(
+    ///     mut ev_guild: EventReader<TypedResponse<Guild>>,
+    ///     mut ev_request: EventWriter<TypedRequest<Guild>>,
+    /// ) {
+    ///     for guild in ev_guild.read() {
+    ///         let renamed = Guild {
+    ///             name: "Night Owls".to_string(),
+    ///             ..(**guild).clone()
+    ///         };
+    ///         ev_request.send(
+    ///             HttpClient::new()
+    ///                 .put(format!("https://example.com/guilds/{}", guild.id))
+    ///                 .if_match_from(guild)
+    ///                 .json(&renamed)
+    ///                 .with_type::<Guild>(),
+    ///         );
+    ///     }
+    /// }
+    /// ```
+    pub fn if_match_from(self, response: &impl ResponseETag) -> Self {
+        match response.etag() {
+            Some(etag) => self.if_match(etag),
+            None => {
+                tracing::warn!("the response has no ETag, the request is sent without If-Match");
+                self
+            }
+        }
+    }
+}
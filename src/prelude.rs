@@ -1,5 +1,110 @@
+#[cfg(feature = "derive")]
+pub use bevy_http_client_derive::HttpEndpoint;
+
+#[cfg(feature = "checksum")]
+pub use super::checksum::BodyChecksum;
+
+#[cfg(feature = "ed25519")]
+pub use super::verify::Ed25519Verifier;
+
+#[cfg(feature = "inspector")]
+pub use super::inspector::{HttpInspector, HttpInspectorPlugin};
+
+#[cfg(feature = "asset")]
 pub use super::{
-    typed::{HttpTypedRequestTrait, TypedRequest, TypedResponse, TypedResponseError},
-    HttpClient, HttpClientPlugin, HttpClientSetting, HttpRequest, HttpResponse, HttpResponseError,
-    RequestTask,
+    asset_source::HttpAssetSourcePlugin,
+    asset_upload::{AssetBody, HttpAssetUpload, HttpAssetUploadPlugin},
+    progressive::{
+        ProgressiveAsset, ProgressiveAssetError, ProgressiveAssetLoaded, ProgressiveAssetPlugin,
+        ProgressiveAssetProgress, ProgressiveAssets, ProgressiveLoad,
+    },
+};
+
+#[cfg(feature = "remote_audio")]
+pub use super::remote_audio::{RemoteAudio, RemoteAudioError, RemoteAudioPlugin, RemoteAudioReady};
+
+#[cfg(feature = "remote_image")]
+pub use super::remote_image::{RemoteImage, RemoteImagePlugin, RemoteImageState};
+
+#[cfg(feature = "request_assets")]
+pub use super::request_asset::{
+    HttpRequestAssetPlugin, HttpRequestDefinitions, RequestDefinition, RequestDefinitionsError,
+};
+
+#[cfg(feature = "scene")]
+pub use super::scene::{
+    LoadRemoteScene, RemoteSceneError, RemoteSceneLoaded, RemoteScenePlugin, RemoteSceneProgress,
+    RemoteSceneStage,
+};
+
+#[cfg(feature = "sse")]
+pub use super::sse::{
+    HttpSseAppExt, HttpSsePlugin, OpenSseStream, SseEvent, SseEventError, SseMessage,
+    SseStreamClosed,
+};
+
+pub use super::{
+    api_url::ApiUrl,
+    backoff::{Backoff, HttpBackoffs, Jitter, RetryPolicy},
+    cancel::{CancelHttpRequests, HttpClientCommands},
+    chain::{ChainComplete, RequestChain},
+    chaos::HttpChaos,
+    condition::{
+        any_request_in_flight, on_http_error, on_http_response, on_typed_error, on_typed_response,
+    },
+    config::HttpClientConfig,
+    cookie::{Cookie, ResponseCookies},
+    cors::{CorsPolicy, ResponseCors},
+    curl::CurlOptions,
+    endpoint::{EndpointInfo, EndpointRegistry, HttpEndpoint, HttpEndpointAppExt},
+    error::{HttpClientBuildError, HttpError},
+    fetch::HttpFetcher,
+    group::{GroupComplete, RequestGroup},
+    harness::HttpTestHarness,
+    history::{HttpHistory, HttpHistoryPlugin, HttpRecord},
+    hydrate::{HttpHydrateAppExt, HydratedEntities, HydratedRecord},
+    lifecycle::{
+        RequestCompleted, RequestFailed, RequestInfo, RequestQueued, RequestStarted, UploadProgress,
+    },
+    link::{Link, ResponseLinks},
+    logging::HttpLogger,
+    manual::{HttpClientCore, HttpCoreUpdate},
+    metrics::{HttpMetrics, LatencyHistogram, RequestStats},
+    middleware::{HttpMiddleware, HttpMiddlewareAppExt, RequestMiddleware, ResponseMiddleware},
+    mock::{HttpClientMockPlugin, HttpMocks, Mock},
+    not_modified::{ConditionalValidators, HttpNotModified},
+    observe::{HttpObserveExt, HttpObserved},
+    pagination::{
+        HttpPaginatedRequestTrait, Page, PaginatedRequest, Pagination, PaginationComplete,
+    },
+    patch::{merge_patch_diff, PatchOperation},
+    persist::{RecordedBody, RecordedResponse},
+    poller::HttpPoller,
+    precondition::ResponseETag,
+    race::{race, RaceComplete, RequestRace},
+    rate_limit::{HostRateLimit, RateLimit, RateLimitState, ResponseRateLimit},
+    redact::{DebugRedaction, RedactDebug, Redacted},
+    reflect::{ReflectRequest, ReflectResponse, ReflectResponseError},
+    remote_config::{ConfigUpdated, RefreshRemoteConfig, RemoteConfigPlugin},
+    remote_resource::{HttpRemoteResourceAppExt, RemoteResource},
+    resumable::{
+        ResumableUpload, ResumableUploadComplete, ResumableUploadError, ResumableUploadProgress,
+        ResumeToken, UploadSource,
+    },
+    sender::HttpRequestSender,
+    task_pool::HttpTaskPool,
+    telemetry::{DropPolicy, TelemetryPlugin, TelemetryQueue},
+    template::HttpRequestTemplate,
+    transport::{HttpResolver, IpFamily, RequestProxy},
+    typed::{
+        ApiErrorTypes, DynamicJsonRequest, DynamicJsonResponse, DynamicJsonResponseError, HttpData,
+        HttpTypedRequestTrait, ResponseEnvelope, TypedApiError, TypedCall, TypedErrorKind,
+        TypedRequest, TypedResponse, TypedResponseError,
+    },
+    user_agent::HttpUserAgent,
+    vcr::Vcr,
+    verify::ResponseVerifier,
+    HttpClient, HttpClientError, HttpClientPlugin, HttpClientSet, HttpClientSetting, HttpClients,
+    HttpClock, HttpRedirect, HttpRequest, HttpResponse, HttpResponseError, HttpServerError,
+    ReconfigureHttpClient, RequestCoalescing, RequestDelay,
 };
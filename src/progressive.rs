@@ -0,0 +1,348 @@
+use std::{marker::PhantomData, ops::ControlFlow, sync::Mutex};
+
+use bevy_app::{App, Plugin};
+use bevy_asset::{Asset, Assets, Handle, UntypedHandle};
+use bevy_ecs::{prelude::*, system::SystemParam, world::CommandQueue};
+use ehttp::streaming::Part;
+
+use crate::{
+    error::HttpError, http_schedule, middleware::HttpMiddleware, HttpClient, HttpClientSet,
+    HttpClientSetting, HttpClients, HttpClock, HttpDispatcher, TaskSender, TaskTags,
+};
+
+/// Streams downloads into `A` assets, refined as their bytes arrive, with the
+/// `ProgressiveAssets<A>` system param.
+///
+/// Requires the `HttpClientPlugin` and the asset plugin of Bevy. Every refinement replaces the
+/// asset, sending an `AssetEvent::Modified`. Assets shown by the renderer, e.g. an `Image`, must
+/// keep `RenderAssetUsages::MAIN_WORLD` for their refinements to be uploaded again.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(ProgressiveAssetPlugin::<Heightmap>::default());
+/// ```
+pub struct ProgressiveAssetPlugin<A>(PhantomData<fn() -> A>);
+
+impl<A> Default for ProgressiveAssetPlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: ProgressiveAsset> Plugin for ProgressiveAssetPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ProgressiveLoad<A>>()
+            .add_event::<ProgressiveAssetProgress>()
+            .add_event::<ProgressiveAssetLoaded>()
+            .add_event::<ProgressiveAssetError>();
+        let schedule = http_schedule(app);
+        app.add_systems(
+            schedule,
+            handle_progressive_loads::<A>.in_set(HttpClientSet::Dispatch),
+        );
+    }
+}
+
+/// An asset built from a download before it completes, e.g. a low resolution image refined as
+/// more bytes arrive, or a sound that starts playing early.
+///
+/// # Examples
+///
+/// ```
+/// impl ProgressiveAsset for Heightmap {
+///     fn from_partial(bytes: &[u8], complete: bool) -> Option<Self> {
+///         // rows arrive in order, the missing ones stay flat
+///         let rows = bytes.chunks_exact(HEIGHTMAP_WIDTH).count();
+///         (rows > 0 || complete).then(|| Heightmap::from_rows(bytes, rows))
+///     }
+/// }
+/// ```
+pub trait ProgressiveAsset: Asset + Sized {
+    /// Builds the asset from the `bytes` received so far, all of them when `complete`, or
+    /// returns `None` if there are not enough of them yet.
+    ///
+    /// Called off the main thread. Returning `None` once `complete` fails the download.
+    fn from_partial(bytes: &[u8], complete: bool) -> Option<Self>;
+
+    /// The number of bytes received between two refinements, 64 KiB by default.
+    fn refine_every() -> usize {
+        64 << 10
+    }
+}
+
+/// A request to stream a download into the asset of `handle`, sent by `ProgressiveAssets`.
+#[derive(Event)]
+pub struct ProgressiveLoad<A: Asset> {
+    pub client: HttpClient,
+    pub handle: Handle<A>,
+}
+
+/// Streams downloads into `A` assets without blocking, see `ProgressiveAssetPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// fn load_terrain(mut commands: Commands, mut heightmaps: ProgressiveAssets<Heightmap>) {
+///     // refined as it downloads
+///     let heightmap = heightmaps.load("https://cdn.example.com/terrain/island.r16");
+///     commands.spawn(Terrain(heightmap));
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct ProgressiveAssets<'w, A: ProgressiveAsset> {
+    assets: Res<'w, Assets<A>>,
+    loads: EventWriter<'w, ProgressiveLoad<A>>,
+}
+
+impl<A: ProgressiveAsset> ProgressiveAssets<'_, A> {
+    /// Streams the download of `url` with a GET request.
+    ///
+    /// The returned handle gets its first asset once enough bytes arrived, then a refined one
+    /// every `refine_every` bytes and a last one when the download completes.
+    pub fn load(&mut self, url: impl ToString) -> Handle<A> {
+        self.load_with(HttpClient::new().get(url))
+    }
+
+    /// Streams the download of the request built by `client`, e.g. to set headers.
+    pub fn load_with(&mut self, client: HttpClient) -> Handle<A> {
+        let handle = self.assets.reserve_handle();
+        self.loads.send(ProgressiveLoad {
+            client,
+            handle: handle.clone(),
+        });
+        handle
+    }
+}
+
+/// Sent when the asset of a progressive download was refined.
+#[derive(Event, Debug, Clone)]
+pub struct ProgressiveAssetProgress {
+    pub url: String,
+    pub handle: UntypedHandle,
+    /// Bytes received so far.
+    pub received: u64,
+    /// Size of the download, from its "Content-Length" header.
+    pub total: Option<u64>,
+}
+
+/// Sent when a progressive download completed, once its asset holds all of its bytes.
+#[derive(Event, Debug, Clone)]
+pub struct ProgressiveAssetLoaded {
+    pub url: String,
+    pub handle: UntypedHandle,
+}
+
+/// Sent when a progressive download failed, its asset keeping its last refinement if any.
+#[derive(Event, Debug, Clone)]
+pub struct ProgressiveAssetError {
+    pub url: String,
+    pub handle: UntypedHandle,
+    pub err: HttpError,
+}
+
+/// A system that starts the progressive downloads, or queues them while their client has no
+/// free slot.
+///
+/// A download holds its slot until it completed or failed. Its refinements go through the
+/// `HttpDispatcher` as parts of its result.
+fn handle_progressive_loads<A: ProgressiveAsset>(
+    mut req_res: ResMut<HttpClientSetting>,
+    mut clients: ResMut<HttpClients>,
+    mut loads: EventReader<ProgressiveLoad<A>>,
+    mut waiting: Local<Vec<(HttpClient, Handle<A>)>>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+    middleware: Res<HttpMiddleware>,
+    clock: Res<HttpClock>,
+) {
+    // the downloads waiting for a free slot go first
+    for (client, _) in waiting.iter() {
+        clients
+            .setting_mut(&mut req_res, client.client.as_deref())
+            .queued -= 1;
+    }
+    let waiting_loads = std::mem::take(&mut *waiting);
+    let loads = loads
+        .read()
+        .map(|load| (load.client.clone(), load.handle.clone()));
+    for (client, handle) in waiting_loads.into_iter().chain(loads) {
+        let setting = clients.setting_mut(&mut req_res, client.client.as_deref());
+        if !setting.try_start(clock.now()) {
+            setting.queued += 1;
+            waiting.push((client, handle));
+            continue;
+        }
+        let tags = TaskTags {
+            client: client.client.clone(),
+            label: client.label.clone(),
+            from_entity: None,
+        };
+        let request = client.into_request();
+        let url = request.url.clone();
+        let failure_url = url.clone();
+        let failure_handle = handle.clone().untyped();
+        let sender = dispatcher.start(clock.now(), None, tags, move |world, failure| {
+            world.send_event(ProgressiveAssetError {
+                url: failure_url,
+                handle: failure_handle,
+                err: failure.into(),
+            });
+        });
+        let stream = ProgressiveStream {
+            url,
+            handle,
+            sender: Mutex::new(Some(sender)),
+            state: Mutex::default(),
+        };
+        middleware.stream(request, move |part| stream.on_data(part));
+    }
+}
+
+/// Refines the asset of a progressive download as its parts arrive.
+struct ProgressiveStream<A: Asset> {
+    url: String,
+    handle: Handle<A>,
+    /// Where the refinements are sent, taken once the download stopped.
+    sender: Mutex<Option<TaskSender>>,
+    state: Mutex<StreamState>,
+}
+
+#[derive(Default)]
+struct StreamState {
+    bytes: Vec<u8>,
+    total: Option<u64>,
+    /// The number of bytes of the last refinement.
+    refined: usize,
+}
+
+impl<A: ProgressiveAsset> ProgressiveStream<A> {
+    fn on_data(&self, part: ehttp::Result<Part>) -> ControlFlow<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let chunk = match part {
+            Ok(Part::Response(response)) if response.ok => {
+                state.total = response
+                    .headers
+                    .get("Content-Length")
+                    .and_then(|length| length.trim().parse().ok());
+                return ControlFlow::Continue(());
+            }
+            Ok(Part::Response(response)) => return self.fail(HttpError::Status(response.status)),
+            Ok(Part::Chunk(chunk)) => chunk,
+            Err(e) => return self.fail(HttpError::transport(e)),
+        };
+
+        let complete = chunk.is_empty();
+        state.bytes.extend(chunk);
+        if !complete && state.bytes.len() - state.refined < A::refine_every() {
+            return ControlFlow::Continue(());
+        }
+        let asset = A::from_partial(&state.bytes, complete);
+        // without an asset yet, the next chunk tries again
+        if asset.is_some() {
+            state.refined = state.bytes.len();
+        }
+        if complete && asset.is_none() {
+            let err = format!("{} bytes could not be decoded", state.bytes.len());
+            return self.fail(HttpError::Decode(err));
+        }
+
+        let mut command_queue = CommandQueue::default();
+        if let Some(asset) = asset {
+            let handle = self.handle.clone();
+            let progress = ProgressiveAssetProgress {
+                url: self.url.clone(),
+                handle: self.handle.clone().untyped(),
+                received: state.bytes.len() as u64,
+                total: state.total,
+            };
+            command_queue.push(move |world: &mut World| {
+                world.resource_mut::<Assets<A>>().insert(&handle, asset);
+                world.send_event(progress);
+            });
+        }
+        if !complete {
+            return self.send_part(command_queue);
+        }
+        let loaded = ProgressiveAssetLoaded {
+            url: self.url.clone(),
+            handle: self.handle.clone().untyped(),
+        };
+        command_queue.push(move |world: &mut World| {
+            world.send_event(loaded);
+        });
+        self.finish(command_queue)
+    }
+
+    /// Stops the download with `err`.
+    fn fail(&self, err: HttpError) -> ControlFlow<()> {
+        let error = ProgressiveAssetError {
+            url: self.url.clone(),
+            handle: self.handle.clone().untyped(),
+            err,
+        };
+        let mut command_queue = CommandQueue::default();
+        command_queue.push(move |world: &mut World| {
+            world.send_event(error);
+        });
+        self.finish(command_queue)
+    }
+
+    /// Sends a refinement, stopping the download once it was cancelled.
+    fn send_part(&self, command_queue: CommandQueue) -> ControlFlow<()> {
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        match sender.as_ref() {
+            Some(sender) if sender.send_part(command_queue) => ControlFlow::Continue(()),
+            _ => ControlFlow::Break(()),
+        }
+    }
+
+    /// Sends the last `command_queue` of the download and frees its slot.
+    fn finish(&self, command_queue: CommandQueue) -> ControlFlow<()> {
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(sender) = sender {
+            sender.send(command_queue);
+        }
+        ControlFlow::Break(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_reflect::TypePath;
+    use bevy_utils::Instant;
+
+    use super::*;
+
+    /// A download decoded once it has 3 bytes.
+    #[derive(Asset, TypePath)]
+    struct Triple;
+
+    impl ProgressiveAsset for Triple {
+        fn from_partial(bytes: &[u8], _complete: bool) -> Option<Self> {
+            (bytes.len() >= 3).then_some(Triple)
+        }
+
+        fn refine_every() -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn refinement_is_retried_until_an_asset_is_built() {
+        let mut dispatcher = HttpDispatcher::new(8);
+        let sender = dispatcher.start(Instant::now(), None, TaskTags::default(), |_, _| {});
+        let stream = ProgressiveStream::<Triple> {
+            url: "https://example.com/triple".to_string(),
+            handle: Handle::default(),
+            sender: Mutex::new(Some(sender)),
+            state: Mutex::default(),
+        };
+        for _ in 0..2 {
+            assert!(stream.on_data(Ok(Part::Chunk(vec![0]))).is_continue());
+        }
+        // 2 bytes, too few for an asset
+        assert_eq!(stream.state.lock().unwrap().refined, 0);
+        assert!(stream.on_data(Ok(Part::Chunk(vec![0]))).is_continue());
+        assert_eq!(stream.state.lock().unwrap().refined, 3);
+    }
+}
@@ -0,0 +1,192 @@
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::{BuildChildren, Children, DespawnRecursiveExt, Parent};
+
+use crate::{
+    cancel::CancelHttpRequests,
+    error::HttpError,
+    lifecycle::RequestFailed,
+    not_modified::HttpNotModified,
+    observe::{HttpObserved, RawResult},
+    HttpRequest, HttpResponse, HttpResponseError,
+};
+
+/// Requests racing each other, completing with a single `RaceComplete` with the first 2xx
+/// response, e.g. to query several region endpoints or mirrors at once.
+///
+/// The requests are sent together, through the limits of their clients as usual. Once one of
+/// them gets a 2xx response, the others are cancelled. Their results only go to the race, they
+/// are not sent as `HttpResponse` or `HttpResponseError` events. Each request is sent from an
+/// entity of the race, replacing its own `from_entity`. With a label, every request of the race
+/// is labeled with it, so `HttpClientCommands::cancel_label` cancels the whole race.
+///
+/// # Examples
+///
+/// ```
+/// fn find_region(mut races: EventWriter<RequestRace>) {
+///     races.send(
+///         race([
+///             HttpClient::new().get("https://eu.example.com/ping").build(),
+///             HttpClient::new().get("https://us.example.com/ping").build(),
+///         ])
+///         .label("region"),
+///     );
+/// }
+///
+/// fn pick_region(mut complete: EventReader<RaceComplete>) {
+///     for race in complete.read() {
+///         if let (Some(winner), Ok(response)) = (race.winner, &race.result) {
+///             println!("request {winner} won with {}", response.url);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone, Default)]
+pub struct RequestRace {
+    requests: Vec<HttpRequest>,
+    label: Option<String>,
+}
+
+/// Races `requests`, see `RequestRace`.
+pub fn race(requests: impl IntoIterator<Item = HttpRequest>) -> RequestRace {
+    RequestRace {
+        requests: requests.into_iter().collect(),
+        label: None,
+    }
+}
+
+impl RequestRace {
+    /// Labels every request of the race, and the `RaceComplete`, with `label`.
+    pub fn label(mut self, label: impl ToString) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+}
+
+/// Sent once a `RequestRace` has a winner, or all of its requests failed.
+#[derive(Event, Debug, Clone)]
+pub struct RaceComplete {
+    /// label of the race, see `RequestRace::label`
+    pub label: Option<String>,
+    /// Index of the request with the first 2xx response, `None` if all failed.
+    pub winner: Option<usize>,
+    /// The winning response, or the result of the request that failed last.
+    pub result: Result<HttpResponse, HttpResponseError>,
+}
+
+/// A race waiting for a winner.
+#[derive(Component)]
+pub(crate) struct PendingRace {
+    label: Option<String>,
+    /// Whether each request is still running.
+    running: Vec<bool>,
+}
+
+/// The entity a request of a race is sent from, a child of the race.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct Racer {
+    index: usize,
+}
+
+/// A system that sends the requests of the `RequestRace` events.
+pub(crate) fn handle_request_races(
+    mut commands: Commands,
+    mut races: EventReader<RequestRace>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    for race in races.read().cloned() {
+        if race.requests.is_empty() {
+            commands.send_event(RaceComplete {
+                label: race.label,
+                winner: None,
+                result: Err(HttpResponseError::new("no request to race")),
+            });
+            continue;
+        }
+
+        let entity = commands
+            .spawn(PendingRace {
+                label: race.label.clone(),
+                running: vec![true; race.requests.len()],
+            })
+            .id();
+        for (index, mut request) in race.requests.into_iter().enumerate() {
+            let racer = commands
+                .spawn(Racer { index })
+                .set_parent(entity)
+                .observe(finish::<HttpResponse>)
+                .observe(finish::<HttpResponseError>)
+                .observe(finish::<HttpNotModified>)
+                .id();
+            request.from_entity = Some(racer);
+            request.observers_only = true;
+            if race.label.is_some() {
+                request.label.clone_from(&race.label);
+            }
+            requests.send(request);
+        }
+    }
+}
+
+/// Records the result of the request sent from a racer.
+fn finish<E: RawResult>(
+    trigger: Trigger<HttpObserved<E>>,
+    mut commands: Commands,
+    racers: Query<(&Racer, &Parent)>,
+    mut races: Query<(&mut PendingRace, &Children)>,
+) {
+    let result = (**trigger.event()).clone().into_result();
+    finish_racer(&mut commands, trigger.entity(), result, &racers, &mut races);
+}
+
+/// A system that records the cancelled requests of races, which deliver no result.
+pub(crate) fn finish_cancelled_racers(
+    mut commands: Commands,
+    mut failed: EventReader<RequestFailed>,
+    racers: Query<(&Racer, &Parent)>,
+    mut races: Query<(&mut PendingRace, &Children)>,
+) {
+    for event in failed.read() {
+        let (HttpError::Cancelled(_), Some(racer)) = (&event.error, event.from_entity) else {
+            continue;
+        };
+        let error = HttpResponseError::new(event.error.clone()).label(event.label.clone());
+        finish_racer(&mut commands, racer, Err(error), &racers, &mut races);
+    }
+}
+
+/// Completes the race of `racer` with its result if it won or was the last one running,
+/// cancelling the requests of the other racers.
+fn finish_racer(
+    commands: &mut Commands,
+    racer: Entity,
+    result: Result<HttpResponse, HttpResponseError>,
+    racers: &Query<(&Racer, &Parent)>,
+    races: &mut Query<(&mut PendingRace, &Children)>,
+) {
+    let Ok((&Racer { index }, parent)) = racers.get(racer) else {
+        return;
+    };
+    let Ok((mut race, children)) = races.get_mut(parent.get()) else {
+        return;
+    };
+    // a request failing for several reasons, e.g. overflowing the result channel, finishes once,
+    // and none is running once the race is complete
+    if !std::mem::take(&mut race.running[index]) {
+        return;
+    }
+
+    let won = result.as_ref().is_ok_and(|response| response.ok);
+    if !won && race.running.contains(&true) {
+        return;
+    }
+    race.running.fill(false);
+    for &other in children.iter().filter(|&&other| other != racer) {
+        commands.send_event(CancelHttpRequests::Entity(other));
+    }
+    commands.send_event(RaceComplete {
+        label: race.label.take(),
+        winner: won.then_some(index),
+        result,
+    });
+    commands.entity(parent.get()).despawn_recursive();
+}
@@ -0,0 +1,230 @@
+use bevy_ecs::prelude::*;
+use bevy_utils::{Duration, HashMap, Instant, SystemTime};
+use ehttp::{Headers, Response};
+
+/// Resets above this many seconds are Unix timestamps rather than delays, as some servers send
+/// in `X-RateLimit-Reset`.
+const UNIX_TIMESTAMP_RESET: u64 = 1_000_000_000;
+
+/// The quota a server advertised in the `RateLimit-*` or `X-RateLimit-*` headers of a response.
+///
+/// Browsers hide these headers from pages unless the server lists them in
+/// `Access-Control-Expose-Headers`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// How many requests the quota allows.
+    pub limit: Option<u64>,
+    /// How many requests are left in the quota.
+    pub remaining: Option<u64>,
+    /// How long until the quota is reset, from when the response was received.
+    pub reset: Option<Duration>,
+}
+
+/// Access to the rate limit advertised by a response.
+///
+/// # Examples
+///
+/// ```
+/// fn handle_scores(mut ev_response: EventReader<HttpResponse>) {
+///     for response in ev_response.read() {
+///         if let Some(RateLimit { remaining: Some(remaining), .. }) = response.rate_limit() {
+///             println!("{remaining} requests left");
+///         }
+///     }
+/// }
+/// ```
+pub trait ResponseRateLimit {
+    /// Returns the rate limit of the headers, `None` if the response has none.
+    fn rate_limit(&self) -> Option<RateLimit>;
+}
+
+impl ResponseRateLimit for Response {
+    fn rate_limit(&self) -> Option<RateLimit> {
+        parse_rate_limit_headers(&self.headers)
+    }
+}
+
+/// Parses the `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers, falling
+/// back to their `X-RateLimit-*` counterparts.
+pub(crate) fn parse_rate_limit_headers(headers: &Headers) -> Option<RateLimit> {
+    let field = |name: &str| {
+        [format!("ratelimit-{name}"), format!("x-ratelimit-{name}")]
+            .iter()
+            .find_map(|header| headers.get(header))
+            .and_then(parse_number)
+    };
+    let rate_limit = RateLimit {
+        limit: field("limit"),
+        remaining: field("remaining"),
+        reset: field("reset").map(reset_delay),
+    };
+    (rate_limit != RateLimit::default()).then_some(rate_limit)
+}
+
+/// Parses the leading number of a header, e.g. `100` of `100, 100;w=60`.
+fn parse_number(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+fn reset_delay(reset: u64) -> Duration {
+    if reset < UNIX_TIMESTAMP_RESET {
+        return Duration::from_secs(reset);
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    Duration::from_secs(reset).saturating_sub(now)
+}
+
+/// The last rate limit advertised by each host, updated from every response.
+///
+/// Unless `HttpClientSetting::adaptive_throttling` is off, requests to a host are paced by its
+/// rate limit: they are queued until the reset once its quota is used up, and spread evenly
+/// until the reset once what is left falls to the `pacing_threshold`. The requests sent since
+/// the last response are counted against the quota.
+///
+/// # Examples
+///
+/// ```
+/// fn send_telemetry(rate_limits: Res<RateLimitState>, clock: Res<HttpClock>) {
+///     if rate_limits.is_exhausted("api.example.com", clock.now()) {
+///         return;
+///     }
+///     // …
+/// }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct RateLimitState {
+    /// The share of the quota left below which requests are spread until the reset, 10% by
+    /// default.
+    pub pacing_threshold: f32,
+    hosts: HashMap<String, HostRateLimit>,
+}
+
+impl Default for RateLimitState {
+    fn default() -> Self {
+        Self {
+            pacing_threshold: 0.1,
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+/// The rate limit of a host, see `RateLimitState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostRateLimit {
+    pub rate_limit: RateLimit,
+    /// When the response advertising it was received, on the `HttpClock`.
+    pub received: Instant,
+    /// The requests sent since.
+    sent: u64,
+    /// When the last request was sent.
+    last_sent: Option<Instant>,
+}
+
+impl HostRateLimit {
+    /// When the quota is reset, on the `HttpClock`.
+    pub fn reset_at(&self) -> Option<Instant> {
+        self.rate_limit.reset.map(|reset| self.received + reset)
+    }
+
+    /// How many requests are left in the quota at `now`, less the requests sent since it was
+    /// advertised, the whole quota once it was reset.
+    pub fn remaining(&self, now: Instant) -> Option<u64> {
+        match self.reset_at() {
+            Some(reset_at) if reset_at <= now => self.rate_limit.limit,
+            _ => self
+                .rate_limit
+                .remaining
+                .map(|remaining| remaining.saturating_sub(self.sent)),
+        }
+    }
+
+    /// How long to wait at `now` before sending a request, spreading the quota left below
+    /// `threshold` until the reset.
+    fn wait(&self, now: Instant, threshold: f32) -> Option<Duration> {
+        let reset_at = self.reset_at().filter(|reset_at| now < *reset_at)?;
+        let remaining = self.remaining(now)?;
+        if remaining == 0 {
+            return Some(reset_at - now);
+        }
+        let limit = self.rate_limit.limit?;
+        if remaining as f32 > limit as f32 * threshold {
+            return None;
+        }
+        let interval = (reset_at - now) / remaining.min(u32::MAX.into()) as u32;
+        let next = self.last_sent? + interval;
+        (now < next).then(|| next - now)
+    }
+}
+
+impl RateLimitState {
+    /// Returns the rate limit last advertised by `host`.
+    pub fn get(&self, host: &str) -> Option<&HostRateLimit> {
+        self.hosts.get(host)
+    }
+
+    /// Returns the hosts and their rate limits.
+    pub fn hosts(&self) -> impl Iterator<Item = (&str, &HostRateLimit)> {
+        self.hosts
+            .iter()
+            .map(|(host, limit)| (host.as_str(), limit))
+    }
+
+    /// How long requests to `host` wait at `now` to stay within its rate limit, `None` if they
+    /// can be sent right away.
+    pub fn pacing_delay(&self, host: &str, now: Instant) -> Option<Duration> {
+        self.get(host)?.wait(now, self.pacing_threshold)
+    }
+
+    /// Whether no request is left in the quota of `host` at `now`.
+    pub fn is_exhausted(&self, host: &str, now: Instant) -> bool {
+        self.get(host)
+            .and_then(|limit| limit.remaining(now))
+            .is_some_and(|remaining| remaining == 0)
+    }
+
+    /// Whether a request to `url` can be sent at `now`, see `pacing_delay`.
+    pub(crate) fn can_send(&self, url: &str, now: Instant) -> bool {
+        host(url).is_none_or(|host| self.pacing_delay(&host, now).is_none())
+    }
+
+    /// Counts a request to `url` sent at `now` against the quota of its host.
+    pub(crate) fn record_sent(&mut self, url: &str, now: Instant) {
+        let Some(limit) = host(url).and_then(|host| self.hosts.get_mut(&host)) else {
+            return;
+        };
+        limit.sent += 1;
+        limit.last_sent = Some(now);
+    }
+
+    /// Records the rate limit advertised by `response`, if any.
+    pub(crate) fn update(&mut self, response: &Response, now: Instant) {
+        let Some(rate_limit) = response.rate_limit() else {
+            return;
+        };
+        let Some(host) = host(&response.url) else {
+            return;
+        };
+        let last_sent = self.hosts.get(&host).and_then(|limit| limit.last_sent);
+        self.hosts.insert(
+            host,
+            HostRateLimit {
+                rate_limit,
+                received: now,
+                sent: 0,
+                last_sent,
+            },
+        );
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(ToString::to_string))
+}
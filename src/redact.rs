@@ -0,0 +1,167 @@
+use std::fmt;
+
+use ehttp::{Headers, Request};
+
+const REDACTED: &str = "<redacted>";
+
+/// What the `Debug` output of requests shows.
+///
+/// The `Debug` implementations of `HttpClient`, `HttpRequest`, `TypedRequest`, `ReflectRequest`
+/// and `PaginatedRequest` use the default redaction: only the allowed headers show their values
+/// and bodies only show their size, so logging a request leaks no credentials. Use
+/// `RedactDebug::debug_with` for other settings, or `RedactDebug::debug_full` to show everything
+/// while debugging locally.
+///
+/// # Examples
+///
+/// ```
+/// let request = HttpClient::new()
+///     .post("https://example.com/login")
+///     .header("Authorization", "Bearer token")
+///     .json(&credentials)
+///     .build();
+///
+/// // Authorization: <redacted>, body: <42 bytes>
+/// println!("{request:?}");
+///
+/// let redaction = DebugRedaction::default().allow_header("x-trace-id");
+/// println!("{:?}", request.debug_with(&redaction));
+///
+/// // everything, for local debugging only
+/// println!("{:?}", request.debug_full());
+/// ```
+#[derive(Debug, Clone)]
+pub struct DebugRedaction {
+    /// Names of the headers whose values are shown, compared case-insensitively.
+    pub allowed_headers: Vec<String>,
+    /// Whether the values of all headers are shown.
+    pub show_all_headers: bool,
+    /// Whether bodies are shown instead of their size.
+    pub show_bodies: bool,
+}
+
+impl Default for DebugRedaction {
+    fn default() -> Self {
+        Self {
+            allowed_headers: [
+                "accept",
+                "accept-encoding",
+                "accept-language",
+                "cache-control",
+                "content-length",
+                "content-type",
+                "if-match",
+                "if-none-match",
+                "user-agent",
+            ]
+            .map(String::from)
+            .to_vec(),
+            show_all_headers: false,
+            show_bodies: false,
+        }
+    }
+}
+
+/// Shows all headers and bodies.
+static FULL: DebugRedaction = DebugRedaction {
+    allowed_headers: Vec::new(),
+    show_all_headers: true,
+    show_bodies: true,
+};
+
+impl DebugRedaction {
+    /// Shows the value of the header `name`.
+    pub fn allow_header(mut self, name: impl ToString) -> Self {
+        self.allowed_headers.push(name.to_string());
+        self
+    }
+
+    /// Sets whether bodies are shown instead of their size.
+    pub fn show_bodies(mut self, enabled: bool) -> Self {
+        self.show_bodies = enabled;
+        self
+    }
+
+    fn allows(&self, header: &str) -> bool {
+        self.show_all_headers
+            || self
+                .allowed_headers
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(header))
+    }
+
+    /// Formats `headers` with the values of the headers not allowed redacted.
+    pub(crate) fn headers<'a>(&'a self, headers: &'a Headers) -> impl fmt::Debug + 'a {
+        DebugWith(move |f: &mut fmt::Formatter<'_>| {
+            f.debug_map()
+                .entries(headers.headers.iter().map(|(key, value)| {
+                    let value = if self.allows(key) { value } else { REDACTED };
+                    (key, value)
+                }))
+                .finish()
+        })
+    }
+
+    /// Formats `body` as text if bodies are shown, as its size otherwise.
+    pub(crate) fn body<'a>(&'a self, body: &'a [u8]) -> impl fmt::Debug + 'a {
+        DebugWith(move |f: &mut fmt::Formatter<'_>| {
+            if self.show_bodies {
+                fmt::Debug::fmt(&String::from_utf8_lossy(body), f)
+            } else {
+                write!(f, "<{} bytes>", body.len())
+            }
+        })
+    }
+
+    /// Formats `request` with its headers and body redacted.
+    pub(crate) fn request<'a>(&'a self, request: &'a Request) -> impl fmt::Debug + 'a {
+        DebugWith(move |f: &mut fmt::Formatter<'_>| {
+            f.debug_struct("Request")
+                .field("method", &request.method)
+                .field("url", &request.url)
+                .field("body", &self.body(&request.body))
+                .field("headers", &self.headers(&request.headers))
+                .finish()
+        })
+    }
+}
+
+/// Formats with a closure.
+struct DebugWith<F>(F);
+
+impl<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result> fmt::Debug for DebugWith<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+/// Request types whose `Debug` output redacts credentials and bodies.
+pub trait RedactDebug {
+    /// Formats the value as its `Debug` implementation does, with `redaction`.
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>, redaction: &DebugRedaction) -> fmt::Result;
+
+    /// Debug-formats the value with `redaction` instead of the default one.
+    fn debug_with<'a>(&'a self, redaction: &'a DebugRedaction) -> Redacted<'a, Self> {
+        Redacted {
+            value: self,
+            redaction,
+        }
+    }
+
+    /// Debug-formats the value with all headers and bodies, for local debugging only.
+    fn debug_full(&self) -> Redacted<'_, Self> {
+        self.debug_with(&FULL)
+    }
+}
+
+/// A value debug-formatted with a given `DebugRedaction`, see `RedactDebug::debug_with`.
+pub struct Redacted<'a, T: ?Sized> {
+    value: &'a T,
+    redaction: &'a DebugRedaction,
+}
+
+impl<T: RedactDebug + ?Sized> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_redacted(f, self.redaction)
+    }
+}
@@ -0,0 +1,199 @@
+use bevy_ecs::{prelude::*, reflect::AppTypeRegistry, world::CommandQueue};
+use bevy_reflect::{serde::TypedReflectDeserializer, PartialReflect};
+use ehttp::{Request, Response};
+use serde::de::DeserializeSeed;
+use std::fmt;
+
+use crate::{
+    attach_request_id,
+    middleware::{FetchOptions, HttpMiddleware},
+    redact::{DebugRedaction, RedactDebug},
+    HttpClient, HttpClientSetting, HttpClock, HttpDispatcher, TaskFailure, TaskTags,
+};
+
+/// A request whose response is deserialized into the registered type with the given type path.
+///
+/// This enables tools and mods to consume API data for types that are only known at runtime. The
+/// type must be registered in the `AppTypeRegistry`.
+///
+/// # Examples
+///
+/// ```
+/// fn send_request(mut ev_request: EventWriter<ReflectRequest>) {
+///     ev_request.send(
+///         HttpClient::new()
+///             .get("https://example.com/player")
+///             .with_type_path("my_game::Player"),
+///     );
+/// }
+/// ```
+#[derive(Event, Clone)]
+pub struct ReflectRequest {
+    pub from_entity: Option<Entity>,
+    pub request: Request,
+    /// Type path of the registered type to deserialize into.
+    pub type_path: String,
+}
+
+impl RedactDebug for ReflectRequest {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>, redaction: &DebugRedaction) -> fmt::Result {
+        f.debug_struct("ReflectRequest")
+            .field("from_entity", &self.from_entity)
+            .field("request", &redaction.request(&self.request))
+            .field("type_path", &self.type_path)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ReflectRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_redacted(f, &DebugRedaction::default())
+    }
+}
+
+impl HttpClient {
+    /// Builds a `ReflectRequest` deserializing the response into the type registered with
+    /// `type_path`.
+    pub fn with_type_path(self, type_path: impl ToString) -> ReflectRequest {
+        let from_entity = self.from_entity;
+        ReflectRequest {
+            from_entity,
+            request: self.into_request(),
+            type_path: type_path.to_string(),
+        }
+    }
+}
+
+/// The reflected value of a `ReflectRequest` response.
+#[derive(Event, Debug)]
+pub struct ReflectResponse {
+    pub type_path: String,
+    pub value: Box<dyn PartialReflect>,
+    /// The correlation ID of the request, see `HttpClientSetting::with_request_id_header`.
+    pub request_id: Option<String>,
+}
+
+/// The error of a `ReflectRequest`.
+#[derive(Event, Debug, Clone)]
+pub struct ReflectResponseError {
+    pub type_path: String,
+    pub err: String,
+    pub response: Option<Response>,
+    /// The correlation ID of the request, see `HttpClientSetting::with_request_id_header`.
+    pub request_id: Option<String>,
+}
+
+pub(crate) fn handle_reflect_request(
+    mut req_res: ResMut<HttpClientSetting>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+    mut requests: EventReader<ReflectRequest>,
+    mut waiting: Local<Vec<ReflectRequest>>,
+    registry: Res<AppTypeRegistry>,
+    middleware: Res<HttpMiddleware>,
+    clock: Res<HttpClock>,
+) {
+    let thread_pool = req_res.fetch_pool.get();
+    // the requests waiting for a free slot go first
+    req_res.queued -= waiting.len();
+    let waiting_requests = std::mem::take(&mut *waiting);
+    for request in waiting_requests.into_iter().chain(requests.read().cloned()) {
+        if req_res.try_start(clock.now()) {
+            let mut req = request.request.clone();
+            let request_id = req_res
+                .request_id_header
+                .as_deref()
+                .map(|header| attach_request_id(&mut req, header));
+            let fetch = middleware.fetch(
+                req,
+                FetchOptions {
+                    request_id: request_id.clone(),
+                    ..Default::default()
+                },
+            );
+            let type_path = request.type_path.clone();
+            let registry = registry.clone();
+            let failed_type_path = type_path.clone();
+            let failed_request_id = request_id.clone();
+            let sender = dispatcher.start(
+                clock.now(),
+                None,
+                TaskTags::default(),
+                move |world, failure| {
+                    if let TaskFailure::Cancelled = failure {
+                        return;
+                    }
+                    world.send_event(ReflectResponseError {
+                        type_path: failed_type_path,
+                        err: failure.to_string(),
+                        response: None,
+                        request_id: failed_request_id,
+                    });
+                },
+            );
+
+            thread_pool
+                .spawn(async move {
+                    let mut command_queue = CommandQueue::default();
+
+                    let result = match fetch.await {
+                        Ok(response) if !response.ok => Err((
+                            format!("HTTP status {} {}", response.status, response.status_text),
+                            Some(response),
+                        )),
+                        Ok(response) => deserialize_reflect(&registry, &type_path, &response.bytes)
+                            .map_err(|e| (e, Some(response))),
+                        Err(e) => Err((e, None)),
+                    };
+
+                    command_queue.push(move |world: &mut World| match result {
+                        Ok(value) => {
+                            world
+                                .get_resource_mut::<Events<ReflectResponse>>()
+                                .unwrap()
+                                .send(ReflectResponse {
+                                    type_path,
+                                    value,
+                                    request_id,
+                                });
+                        }
+                        Err((err, response)) => {
+                            world
+                                .get_resource_mut::<Events<ReflectResponseError>>()
+                                .unwrap()
+                                .send(ReflectResponseError {
+                                    type_path,
+                                    err,
+                                    response,
+                                    request_id,
+                                });
+                        }
+                    });
+
+                    sender.send(command_queue);
+                })
+                .detach();
+        } else {
+            waiting.push(request);
+        }
+    }
+    req_res.queued += waiting.len();
+}
+
+/// Deserializes a JSON body into the registered type with the given type path.
+fn deserialize_reflect(
+    registry: &AppTypeRegistry,
+    type_path: &str,
+    bytes: &[u8],
+) -> Result<Box<dyn PartialReflect>, String> {
+    let registry = registry.read();
+    let registration = registry
+        .get_with_type_path(type_path)
+        .ok_or_else(|| format!("type `{type_path}` is not registered"))?;
+
+    let mut de = serde_json::Deserializer::from_slice(bytes);
+    let value = TypedReflectDeserializer::new(registration, &registry)
+        .deserialize(&mut de)
+        .map_err(|e| e.to_string())?;
+    de.end().map_err(|e| e.to_string())?;
+    Ok(value)
+}
@@ -0,0 +1,139 @@
+use bevy_app::{App, Plugin};
+use bevy_asset::{Assets, Handle};
+use bevy_audio::AudioSource;
+use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_hierarchy::DespawnRecursiveExt;
+
+use crate::{observe::HttpObserved, HttpClient, HttpRequest, HttpResponse, HttpResponseError};
+
+/// Downloads `AudioSource`s from URLs with the `RemoteAudio` system param.
+///
+/// Requires the `HttpClientPlugin` and Bevy's audio plugin. The file is downloaded entirely
+/// before it can play, and decoded with the formats enabled on Bevy, e.g. its `vorbis` feature.
+pub struct RemoteAudioPlugin;
+
+impl Plugin for RemoteAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RemoteAudioReady>()
+            .add_event::<RemoteAudioError>()
+            .add_observer(insert_remote_audio)
+            .add_observer(fail_remote_audio);
+    }
+}
+
+/// Loads audio files from URLs without blocking.
+///
+/// # Examples
+///
+/// ```
+/// fn play_voice_line(mut commands: Commands, mut remote_audio: RemoteAudio) {
+///     // plays once downloaded
+///     let voice = remote_audio.load("https://cdn.example.com/voice/intro.ogg");
+///     commands.spawn(AudioPlayer(voice));
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct RemoteAudio<'w, 's> {
+    commands: Commands<'w, 's>,
+    sources: Res<'w, Assets<AudioSource>>,
+    requests: EventWriter<'w, HttpRequest>,
+}
+
+impl RemoteAudio<'_, '_> {
+    /// Downloads the audio file at `url` with a GET request.
+    ///
+    /// The returned handle gets its asset once downloaded, when `RemoteAudioReady` is sent.
+    pub fn load(&mut self, url: impl ToString) -> Handle<AudioSource> {
+        self.load_with(HttpClient::new().get(url))
+    }
+
+    /// Downloads the audio file with the request built by `client`, e.g. to set headers.
+    pub fn load_with(&mut self, client: HttpClient) -> Handle<AudioSource> {
+        let handle = self.sources.reserve_handle();
+        let mut request = client.build();
+        let pending = self
+            .commands
+            .spawn(PendingRemoteAudio {
+                url: request.request.url.clone(),
+                handle: handle.clone(),
+            })
+            .id();
+        request.from_entity = Some(pending);
+        self.requests.send(request);
+        handle
+    }
+}
+
+/// Sent when a remote audio file was downloaded into its asset.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteAudioReady {
+    pub url: String,
+    pub handle: Handle<AudioSource>,
+}
+
+/// Sent when a remote audio file could not be downloaded.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteAudioError {
+    pub url: String,
+    pub handle: Handle<AudioSource>,
+    pub err: String,
+}
+
+/// Tracks the download of a remote audio file.
+#[derive(Component, Debug)]
+struct PendingRemoteAudio {
+    url: String,
+    handle: Handle<AudioSource>,
+}
+
+/// Inserts a downloaded audio file into its asset.
+fn insert_remote_audio(
+    trigger: Trigger<HttpObserved<HttpResponse>>,
+    mut commands: Commands,
+    pending: Query<&PendingRemoteAudio>,
+    mut sources: ResMut<Assets<AudioSource>>,
+    mut ready: EventWriter<RemoteAudioReady>,
+    mut errors: EventWriter<RemoteAudioError>,
+) {
+    let Ok(PendingRemoteAudio { url, handle }) = pending.get(trigger.entity()) else {
+        return;
+    };
+    commands.entity(trigger.entity()).despawn_recursive();
+
+    let response = trigger.event();
+    if !response.ok {
+        errors.send(RemoteAudioError {
+            url: url.clone(),
+            handle: handle.clone(),
+            err: format!("HTTP status {} {}", response.status, response.status_text),
+        });
+        return;
+    }
+    sources.insert(
+        handle,
+        AudioSource {
+            bytes: response.bytes.as_slice().into(),
+        },
+    );
+    ready.send(RemoteAudioReady {
+        url: url.clone(),
+        handle: handle.clone(),
+    });
+}
+
+/// Reports a failed audio download.
+fn fail_remote_audio(
+    trigger: Trigger<HttpObserved<HttpResponseError>>,
+    mut commands: Commands,
+    pending: Query<&PendingRemoteAudio>,
+    mut errors: EventWriter<RemoteAudioError>,
+) {
+    if let Ok(PendingRemoteAudio { url, handle }) = pending.get(trigger.entity()) {
+        commands.entity(trigger.entity()).despawn_recursive();
+        errors.send(RemoteAudioError {
+            url: url.clone(),
+            handle: handle.clone(),
+            err: trigger.event().err.to_string(),
+        });
+    }
+}
@@ -0,0 +1,188 @@
+use std::marker::PhantomData;
+
+use bevy_app::{App, Plugin, Startup, Update};
+use bevy_ecs::prelude::*;
+use bevy_utils::{Duration, Instant};
+use serde::Deserialize;
+
+use crate::{
+    observe::HttpObserved,
+    typed::{HttpTypedRequestTrait, TypedRequest, TypedResponse},
+    HttpClient, HttpClock,
+};
+
+/// Fetches a config of type `T` at startup and keeps it up to date as the resource `T`.
+///
+/// The config is fetched again every refresh interval, if any, and when a
+/// `RefreshRemoteConfig<T>` event is sent. A `ConfigUpdated<T>` event is sent whenever the fetched
+/// config differs from the current one. Until the first fetch succeeded, the resource is absent
+/// unless a default was given. Failed fetches keep the current config and are reported as
+/// `TypedResponseError<T>` events.
+///
+/// Requires the `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Resource, Deserialize, Clone, PartialEq)]
+/// struct LiveOps {
+///     double_xp: bool,
+/// }
+///
+/// app.add_plugins(
+///     RemoteConfigPlugin::<LiveOps>::new("https://example.com/liveops.json")
+///         .refresh_every(Duration::from_secs(300)),
+/// );
+///
+/// fn show_banner(live_ops: Option<Res<LiveOps>>) {
+///     if live_ops.is_some_and(|live_ops| live_ops.double_xp) {
+///         println!("double XP weekend!");
+///     }
+/// }
+/// ```
+pub struct RemoteConfigPlugin<T> {
+    client: HttpClient,
+    interval: Option<Duration>,
+    default: Option<T>,
+}
+
+impl<T> RemoteConfigPlugin<T> {
+    /// Fetches the config at `url` with a GET request.
+    pub fn new(url: impl ToString) -> Self {
+        Self::from_client(HttpClient::new().get(url))
+    }
+
+    /// Fetches the config with the request built by `client`, e.g. to set headers.
+    pub fn from_client(client: HttpClient) -> Self {
+        Self {
+            client,
+            interval: None,
+            default: None,
+        }
+    }
+
+    /// Fetches the config again every `interval`.
+    pub fn refresh_every(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Inserts `default` as the config until the first fetch succeeded.
+    pub fn with_default(mut self, default: T) -> Self {
+        self.default = Some(default);
+        self
+    }
+}
+
+impl<T> Plugin for RemoteConfigPlugin<T>
+where
+    T: for<'a> Deserialize<'a> + Resource + Clone + PartialEq,
+{
+    fn build(&self, app: &mut App) {
+        if let Some(default) = &self.default {
+            app.insert_resource(default.clone());
+        }
+        app.register_request_type::<T>()
+            .add_event::<RefreshRemoteConfig<T>>()
+            .add_event::<ConfigUpdated<T>>()
+            .insert_resource(RemoteConfigSource::<T> {
+                client: self.client.clone(),
+                interval: self.interval,
+                entity: Entity::PLACEHOLDER,
+                next_refresh: None,
+                phantom: PhantomData,
+            })
+            .add_systems(Startup, spawn_remote_config_source::<T>)
+            .add_systems(Update, refresh_remote_config::<T>)
+            .add_observer(update_remote_config::<T>);
+    }
+}
+
+/// Fetches a remote config again, without waiting for the refresh interval.
+#[derive(Event, Debug)]
+pub struct RefreshRemoteConfig<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for RefreshRemoteConfig<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Sent when a fetched remote config differs from the current one.
+#[derive(Event, Debug, Clone)]
+pub struct ConfigUpdated<T> {
+    /// The previous config, `None` on the first fetch without a default.
+    pub old: Option<T>,
+    pub new: T,
+}
+
+/// Where the config `T` is fetched from.
+#[derive(Resource)]
+struct RemoteConfigSource<T> {
+    client: HttpClient,
+    interval: Option<Duration>,
+    /// The entity the requests are made from, to recognize their responses.
+    entity: Entity,
+    next_refresh: Option<Instant>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> RemoteConfigSource<T>
+where
+    T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    fn request(&mut self, now: Instant) -> TypedRequest<T> {
+        self.next_refresh = self.interval.map(|interval| now + interval);
+        self.client.clone().entity(self.entity).with_type::<T>()
+    }
+}
+
+/// A system that spawns the entity the config requests are made from and fetches the config.
+fn spawn_remote_config_source<T>(
+    mut commands: Commands,
+    mut source: ResMut<RemoteConfigSource<T>>,
+    mut requests: EventWriter<TypedRequest<T>>,
+    clock: Res<HttpClock>,
+) where
+    T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    source.entity = commands.spawn_empty().id();
+    requests.send(source.request(clock.now()));
+}
+
+/// A system that fetches the config again when due or asked to.
+fn refresh_remote_config<T>(
+    mut source: ResMut<RemoteConfigSource<T>>,
+    mut refreshes: EventReader<RefreshRemoteConfig<T>>,
+    mut requests: EventWriter<TypedRequest<T>>,
+    clock: Res<HttpClock>,
+) where
+    T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    let now = clock.now();
+    let due = source.next_refresh.is_some_and(|next| next <= now);
+    if refreshes.read().count() > 0 || due {
+        requests.send(source.request(now));
+    }
+}
+
+/// Replaces the config with a fetched one.
+fn update_remote_config<T>(
+    trigger: Trigger<HttpObserved<TypedResponse<T>>>,
+    mut commands: Commands,
+    source: Res<RemoteConfigSource<T>>,
+    current: Option<Res<T>>,
+    mut updated: EventWriter<ConfigUpdated<T>>,
+) where
+    T: for<'a> Deserialize<'a> + Resource + Clone + PartialEq,
+{
+    if trigger.entity() != source.entity {
+        return;
+    }
+    let new = T::clone(trigger.event());
+    let old = current.map(|current| current.clone());
+    if old.as_ref() != Some(&new) {
+        commands.insert_resource(new.clone());
+        updated.send(ConfigUpdated { old, new });
+    }
+}
@@ -0,0 +1,194 @@
+use bevy_app::{App, Plugin, Update};
+use bevy_asset::{Assets, Handle, RenderAssetUsages};
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::DespawnRecursiveExt;
+use bevy_image::Image;
+use bevy_sprite::Sprite;
+use bevy_ui::widget::ImageNode;
+
+use crate::{observe::HttpObserved, HttpClient, HttpRequest, HttpResponse, HttpResponseError};
+
+/// Downloads the images of `RemoteImage` components into the `Sprite` or `ImageNode` of their
+/// entity.
+///
+/// Requires the `HttpClientPlugin`. The image is decoded with the formats enabled on Bevy, e.g.
+/// its `png` and `jpeg` features.
+pub struct RemoteImagePlugin;
+
+impl Plugin for RemoteImagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, request_remote_images)
+            .add_observer(apply_remote_image)
+            .add_observer(fail_remote_image);
+    }
+}
+
+/// An image downloaded from `url` and shown by the `Sprite` or `ImageNode` of the entity.
+///
+/// The placeholder is shown while downloading and the error image, if any, when the download or
+/// decoding failed. Changing the URL downloads the new image. The progress is tracked by the
+/// `RemoteImageState` component.
+///
+/// # Examples
+///
+/// ```
+/// commands.spawn((
+///     ImageNode::default(),
+///     RemoteImage::new(&player.avatar_url).with_placeholder(asset_server.load("avatar.png")),
+/// ));
+/// ```
+#[derive(Component, Debug, Clone)]
+pub struct RemoteImage {
+    pub url: String,
+    pub placeholder: Option<Handle<Image>>,
+    pub error_image: Option<Handle<Image>>,
+}
+
+impl RemoteImage {
+    /// Downloads the image at `url`.
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            placeholder: None,
+            error_image: None,
+        }
+    }
+
+    /// Shows `placeholder` while downloading.
+    pub fn with_placeholder(mut self, placeholder: Handle<Image>) -> Self {
+        self.placeholder = Some(placeholder);
+        self
+    }
+
+    /// Shows `error_image` if the image could not be downloaded or decoded.
+    pub fn with_error_image(mut self, error_image: Handle<Image>) -> Self {
+        self.error_image = Some(error_image);
+        self
+    }
+}
+
+/// The download state of a `RemoteImage`.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub enum RemoteImageState {
+    Loading,
+    Loaded(Handle<Image>),
+    Failed(String),
+}
+
+/// Tracks the download of the image of `target`.
+#[derive(Component, Debug)]
+struct PendingRemoteImage {
+    target: Entity,
+    url: String,
+}
+
+/// A remote image and where it is shown.
+type RemoteImageTarget = (
+    &'static RemoteImage,
+    Option<&'static mut Sprite>,
+    Option<&'static mut ImageNode>,
+);
+
+/// A system that downloads the images of new and changed `RemoteImage`s.
+fn request_remote_images(
+    mut commands: Commands,
+    mut images: Query<(Entity, RemoteImageTarget), Changed<RemoteImage>>,
+    mut requests: EventWriter<HttpRequest>,
+) {
+    for (entity, (image, sprite, node)) in images.iter_mut() {
+        if let Some(placeholder) = &image.placeholder {
+            show(sprite, node, placeholder.clone());
+        }
+        let pending = commands
+            .spawn(PendingRemoteImage {
+                target: entity,
+                url: image.url.clone(),
+            })
+            .id();
+        commands.entity(entity).insert(RemoteImageState::Loading);
+        requests.send(HttpClient::new().entity(pending).get(&image.url).build());
+    }
+}
+
+/// Decodes a downloaded image and shows it.
+fn apply_remote_image(
+    trigger: Trigger<HttpObserved<HttpResponse>>,
+    mut commands: Commands,
+    pending: Query<&PendingRemoteImage>,
+    mut images: Query<RemoteImageTarget>,
+    mut assets: ResMut<Assets<Image>>,
+) {
+    let Ok(PendingRemoteImage { target, url }) = pending.get(trigger.entity()) else {
+        return;
+    };
+    commands.entity(trigger.entity()).despawn_recursive();
+    // skip the images replaced while downloading
+    let Ok((image, sprite, node)) = images.get_mut(*target) else {
+        return;
+    };
+    if image.url != *url {
+        return;
+    }
+
+    let response = trigger.event();
+    let decoded = if response.ok {
+        image::load_from_memory(&response.bytes)
+            .map(|decoded| Image::from_dynamic(decoded, true, RenderAssetUsages::default()))
+            .map_err(|e| e.to_string())
+    } else {
+        Err(format!(
+            "HTTP status {} {}",
+            response.status, response.status_text
+        ))
+    };
+    let state = match decoded {
+        Ok(decoded) => {
+            let handle = assets.add(decoded);
+            show(sprite, node, handle.clone());
+            RemoteImageState::Loaded(handle)
+        }
+        Err(err) => {
+            if let Some(error_image) = &image.error_image {
+                show(sprite, node, error_image.clone());
+            }
+            RemoteImageState::Failed(err)
+        }
+    };
+    commands.entity(*target).insert(state);
+}
+
+/// Shows the error image of a failed download.
+fn fail_remote_image(
+    trigger: Trigger<HttpObserved<HttpResponseError>>,
+    mut commands: Commands,
+    pending: Query<&PendingRemoteImage>,
+    mut images: Query<RemoteImageTarget>,
+) {
+    let Ok(PendingRemoteImage { target, url }) = pending.get(trigger.entity()) else {
+        return;
+    };
+    commands.entity(trigger.entity()).despawn_recursive();
+    let Ok((image, sprite, node)) = images.get_mut(*target) else {
+        return;
+    };
+    if image.url != *url {
+        return;
+    }
+
+    if let Some(error_image) = &image.error_image {
+        show(sprite, node, error_image.clone());
+    }
+    commands
+        .entity(*target)
+        .insert(RemoteImageState::Failed(trigger.event().err.to_string()));
+}
+
+/// Sets the image of the sprite or UI node.
+fn show(sprite: Option<Mut<Sprite>>, node: Option<Mut<ImageNode>>, handle: Handle<Image>) {
+    if let Some(mut sprite) = sprite {
+        sprite.image = handle.clone();
+    }
+    if let Some(mut node) = node {
+        node.image = handle;
+    }
+}
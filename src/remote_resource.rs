@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+
+use bevy_app::{App, Update};
+use bevy_ecs::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    observe::HttpObserved, template::HttpRequestTemplate, HttpRequest, HttpResponse,
+    HttpResponseError,
+};
+
+pub trait HttpRemoteResourceAppExt {
+    /// Registers the systems syncing `RemoteResource<T>` components.
+    fn register_remote_resource<T>(&mut self) -> &mut Self
+    where
+        T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static;
+}
+
+impl HttpRemoteResourceAppExt for App {
+    fn register_remote_resource<T>(&mut self) -> &mut Self
+    where
+        T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        self.add_systems(Update, send_remote_resource_requests::<T>)
+            .add_observer(apply_remote_resource_response::<T>)
+            .add_observer(fail_remote_resource_request::<T>)
+    }
+}
+
+/// A local copy of the REST resource at a URL.
+///
+/// The resource is fetched with a GET request when the component is added, and again on
+/// `refresh`. `patch` applies a JSON merge patch to the local copy right away and sends it with a
+/// PATCH request. If the request fails, the local copy is rolled back, otherwise it is replaced
+/// by the resource in the response body, if any. Requests are sent one at a time, in order,
+/// from the entity of the component.
+///
+/// Register the resource type with `register_remote_resource::<T>()`.
+///
+/// # Examples
+///
+/// ```
+/// app.register_remote_resource::<Profile>();
+///
+/// commands.spawn(RemoteResource::<Profile>::new("https://example.com/me"));
+///
+/// fn rename(mut profiles: Query<&mut RemoteResource<Profile>>) {
+///     for mut profile in profiles.iter_mut() {
+///         profile.patch(&serde_json::json!({ "name": "fox" }));
+///     }
+/// }
+/// ```
+#[derive(Component, Debug, Clone)]
+pub struct RemoteResource<T: Send + Sync + 'static> {
+    url: String,
+    template: HttpRequestTemplate,
+    value: Option<T>,
+    error: Option<String>,
+    queue: VecDeque<Operation<T>>,
+    in_flight: Option<Operation<T>>,
+}
+
+#[derive(Debug, Clone)]
+enum Operation<T> {
+    Get,
+    Patch { body: Value, rollback: Option<T> },
+}
+
+impl<T> RemoteResource<T>
+where
+    T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    /// Syncs with the resource at `url`.
+    pub fn new(url: impl ToString) -> Self {
+        Self {
+            url: url.to_string(),
+            template: HttpRequestTemplate::default(),
+            value: None,
+            error: None,
+            queue: VecDeque::from([Operation::Get]),
+            in_flight: None,
+        }
+    }
+
+    /// Builds the requests from `template`, e.g. for authentication headers. The URL is resolved
+    /// against the base URL of the template.
+    pub fn with_template(mut self, template: HttpRequestTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// The local copy, `None` until fetched.
+    pub fn value(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// The error of the last failed request, cleared by the next successful one.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Whether requests are queued or in flight.
+    pub fn is_syncing(&self) -> bool {
+        self.in_flight.is_some() || !self.queue.is_empty()
+    }
+
+    /// Fetches the resource again.
+    pub fn refresh(&mut self) {
+        self.queue.push_back(Operation::Get);
+    }
+
+    /// Applies the JSON merge patch `changes` to the local copy and sends it to the server.
+    pub fn patch(&mut self, changes: &impl Serialize) {
+        let body = match serde_json::to_value(changes) {
+            Ok(body) => body,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        let rollback = self.value.clone();
+        if let Some(value) = &self.value {
+            let patched = serde_json::to_value(value).and_then(|mut patched| {
+                merge_patch(&mut patched, &body);
+                serde_json::from_value(patched)
+            });
+            match patched {
+                Ok(patched) => self.value = Some(patched),
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    return;
+                }
+            }
+        }
+        self.queue.push_back(Operation::Patch { body, rollback });
+    }
+
+    fn request(&self, operation: &Operation<T>) -> HttpRequest {
+        let client = self.template.instantiate();
+        match operation {
+            Operation::Get => client.get(&self.url),
+            Operation::Patch { body, .. } => client.patch(&self.url).json(body),
+        }
+        .build()
+    }
+
+    /// Completes the request in flight.
+    fn complete(&mut self, result: Result<&[u8], String>) {
+        let Some(operation) = self.in_flight.take() else {
+            return;
+        };
+        let parsed = result.and_then(|body| {
+            if body.is_empty() {
+                return Ok(None);
+            }
+            serde_json::from_slice::<T>(body)
+                .map(Some)
+                .map_err(|e| e.to_string())
+        });
+        match (operation, parsed) {
+            (_, Ok(Some(value))) => {
+                self.value = Some(value);
+                self.error = None;
+            }
+            (Operation::Get, Ok(None)) => {
+                self.error = Some("empty response body".to_string());
+            }
+            // keep the optimistic copy
+            (Operation::Patch { .. }, Ok(None)) => self.error = None,
+            (Operation::Get, Err(e)) => self.error = Some(e),
+            (Operation::Patch { rollback, .. }, Err(e)) => {
+                self.value = rollback;
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Applies a JSON merge patch (RFC 7386) to `target`.
+pub(crate) fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target) = target else {
+        unreachable!();
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// A system that sends the next request of the idle remote resources.
+fn send_remote_resource_requests<T>(
+    mut resources: Query<(Entity, &mut RemoteResource<T>)>,
+    mut requests: EventWriter<HttpRequest>,
+) where
+    T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    for (entity, mut resource) in resources.iter_mut() {
+        if resource.in_flight.is_some() || resource.queue.is_empty() {
+            continue;
+        }
+        // request bookkeeping is not a change of the resource
+        let resource = resource.bypass_change_detection();
+        let Some(operation) = resource.queue.pop_front() else {
+            continue;
+        };
+        let mut request = resource.request(&operation);
+        request.from_entity = Some(entity);
+        requests.send(request);
+        resource.in_flight = Some(operation);
+    }
+}
+
+/// Updates a remote resource with the response to its request.
+fn apply_remote_resource_response<T>(
+    trigger: Trigger<HttpObserved<HttpResponse>>,
+    mut resources: Query<&mut RemoteResource<T>>,
+) where
+    T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    if let Ok(mut resource) = resources.get_mut(trigger.entity()) {
+        let response = trigger.event();
+        if response.ok {
+            resource.complete(Ok(&response.bytes));
+        } else {
+            resource.complete(Err(format!(
+                "HTTP status {} {}",
+                response.status, response.status_text
+            )));
+        }
+    }
+}
+
+/// Rolls back a remote resource whose request failed.
+fn fail_remote_resource_request<T>(
+    trigger: Trigger<HttpObserved<HttpResponseError>>,
+    mut resources: Query<&mut RemoteResource<T>>,
+) where
+    T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    if let Ok(mut resource) = resources.get_mut(trigger.entity()) {
+        resource.complete(Err(trigger.event().err.to_string()));
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use bevy_app::{App, Plugin};
+use bevy_asset::{io::Reader, Asset, AssetApp, AssetLoader, LoadContext};
+use bevy_reflect::TypePath;
+use bevy_utils::{Duration, HashMap};
+use serde::Deserialize;
+
+use crate::{endpoint::encode_path_param, join_url, poller::HttpPoller, HttpClient};
+
+/// Loads `.requests.ron` files as `HttpRequestDefinitions`.
+///
+/// # Examples
+///
+/// ```
+/// App::new()
+///     .add_plugins((DefaultPlugins, HttpClientPlugin, HttpRequestAssetPlugin))
+///     .add_systems(Startup, |mut commands: Commands, asset_server: Res<AssetServer>| {
+///         commands.insert_resource(Api(asset_server.load("api.requests.ron")));
+///     });
+/// ```
+pub struct HttpRequestAssetPlugin;
+
+impl Plugin for HttpRequestAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<HttpRequestDefinitions>()
+            .register_asset_loader(HttpRequestDefinitionsLoader);
+    }
+}
+
+/// Named requests defined in a data file, so endpoints and polling can change without a
+/// recompile.
+///
+/// Parameters written `{name}` in the URLs are filled in, escaped, when a request is
+/// instantiated. Durations are in seconds.
+///
+/// ```ron
+/// (
+///     base_url: Some("https://api.example.com"),
+///     requests: {
+///         "news": (
+///             url: "/news/{lang}",
+///             headers: [("Accept", "application/json")],
+///             poll_interval: Some(30.0),
+///         ),
+///         "save": (method: "POST", url: "/saves/{slot}", timeout: Some(10.0)),
+///     },
+/// )
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// fn fetch_news(api: Res<Api>, definitions: Res<Assets<HttpRequestDefinitions>>, mut commands: Commands) {
+///     let Some(definitions) = definitions.get(&api.0) else {
+///         return;
+///     };
+///     if let Some(poller) = definitions.poller("news", &[("lang", "en")]) {
+///         commands.spawn(poller.typed::<News>());
+///     }
+/// }
+/// ```
+#[derive(Asset, TypePath, Debug, Clone, Default, Deserialize)]
+pub struct HttpRequestDefinitions {
+    /// Base URL that relative request URLs are resolved against.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// The requests, by name.
+    pub requests: HashMap<String, RequestDefinition>,
+}
+
+/// A request of `HttpRequestDefinitions`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestDefinition {
+    /// "GET" by default.
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// The URL, absolute or relative to the base URL, with `{name}` parameters.
+    pub url: String,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Timeout, in seconds.
+    #[serde(default)]
+    pub timeout: Option<f32>,
+    /// Interval of the poller of the request, in seconds.
+    #[serde(default)]
+    pub poll_interval: Option<f32>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+impl HttpRequestDefinitions {
+    /// Creates a request builder from the request `name`, with the URL parameters filled in
+    /// from `params`.
+    ///
+    /// Returns `None` if there is no request `name`. Parameters missing from `params` are kept as
+    /// they are written.
+    pub fn instantiate(&self, name: &str, params: &[(&str, &str)]) -> Option<HttpClient> {
+        let definition = self.requests.get(name)?;
+        let mut url = definition.url.clone();
+        for (key, value) in params {
+            url = url.replace(&format!("{{{key}}}"), &encode_path_param(value));
+        }
+        if let Some(base_url) = &self.base_url {
+            url = join_url(base_url, url);
+        }
+
+        let mut client = HttpClient::new();
+        client.method = Some(definition.method.to_uppercase());
+        client.url = Some(url);
+        for (key, value) in &definition.headers {
+            client = client.header(key, value);
+        }
+        if let Some(body) = &definition.body {
+            client.body = body.as_bytes().to_vec();
+        }
+        if let Some(timeout) = definition.timeout {
+            client = client.timeout(Duration::from_secs_f32(timeout));
+        }
+        Some(client)
+    }
+
+    /// Creates a poller sending the request `name` at its `poll_interval`, see `instantiate`.
+    ///
+    /// Returns `None` if there is no request `name` or if it has no poll interval.
+    pub fn poller(&self, name: &str, params: &[(&str, &str)]) -> Option<HttpPoller> {
+        let interval = self.requests.get(name)?.poll_interval?;
+        let client = self.instantiate(name, params)?;
+        Some(HttpPoller::new(client, Duration::from_secs_f32(interval)))
+    }
+}
+
+/// Loads `HttpRequestDefinitions` from RON.
+struct HttpRequestDefinitionsLoader;
+
+impl AssetLoader for HttpRequestDefinitionsLoader {
+    type Asset = HttpRequestDefinitions;
+    type Settings = ();
+    type Error = RequestDefinitionsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(RequestDefinitionsError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(RequestDefinitionsError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["requests.ron"]
+    }
+}
+
+/// Why `HttpRequestDefinitions` failed to load.
+#[derive(Debug)]
+pub enum RequestDefinitionsError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for RequestDefinitionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read the request definitions: {e}"),
+            Self::Ron(e) => write!(f, "invalid request definitions: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RequestDefinitionsError {}
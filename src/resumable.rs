@@ -0,0 +1,648 @@
+use std::{fmt, sync::Arc};
+
+use base64::Engine;
+use bevy_ecs::{prelude::*, world::CommandQueue};
+use ehttp::{Headers, Request, Response};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use crate::{
+    attach_request_id,
+    error::HttpError,
+    middleware::{FetchOptions, HttpMiddleware},
+    redact::{DebugRedaction, RedactDebug},
+    HttpClient, HttpClientSetting, HttpClients, HttpClock, HttpDispatcher, TaskSender, TaskTags,
+};
+
+/// The version of the tus protocol spoken by `ResumableUpload`.
+const TUS_VERSION: &str = "1.0.0";
+
+/// An upload split into parts with the tus resumable upload protocol, see https://tus.io.
+///
+/// The upload is created at the URL of the request, then its parts are sent in order, each one
+/// retried after a network error or a server error. The headers of the request, e.g. its
+/// authorization, are sent with every part. Its progress is sent as `ResumableUploadProgress`
+/// events, whose `ResumeToken` continues the upload with `resume`, e.g. after a restart.
+///
+/// # Examples
+///
+/// ```
+/// fn upload_replay(mut ev_upload: EventWriter<ResumableUpload>, saved: Res<Saved>) {
+///     let mut upload = HttpClient::new()
+///         .post("https://uploads.example.com/files/")
+///         .header("Authorization", format!("Bearer {}", saved.api_token))
+///         .resumable_upload(UploadSource::file("replays/42.replay"))
+///         .metadata("filename", "42.replay");
+///     if let Some(token) = &saved.upload_token {
+///         upload = upload.resume(token.clone());
+///     }
+///     ev_upload.send(upload);
+/// }
+/// ```
+#[derive(Event, Clone)]
+pub struct ResumableUpload {
+    pub from_entity: Option<Entity>,
+    /// The request creating the upload, whose headers are sent with every part.
+    pub request: Request,
+    pub source: UploadSource,
+    /// The size of the parts, 4 MiB by default.
+    pub chunk_size: usize,
+    /// How many times a failed part is retried in a row, 3 by default.
+    pub retries: u32,
+    /// The upload to continue instead of creating one.
+    pub resume: Option<ResumeToken>,
+    /// The `Upload-Metadata` of the upload, e.g. its file name.
+    pub metadata: Vec<(String, String)>,
+    /// The label delivered with the events of the upload, see `HttpClient::label`.
+    pub label: Option<String>,
+    /// The label of the client sending the parts, see `HttpClient::on_client`.
+    pub client: Option<String>,
+}
+
+impl ResumableUpload {
+    pub fn new(request: Request, from_entity: Option<Entity>, source: UploadSource) -> Self {
+        Self {
+            from_entity,
+            request,
+            source,
+            chunk_size: 4 << 20,
+            retries: 3,
+            resume: None,
+            metadata: Vec::new(),
+            label: None,
+            client: None,
+        }
+    }
+
+    /// Sends parts of `chunk_size` bytes.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Retries a failed part up to `retries` times in a row.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Continues the upload of `token` from where the server stopped receiving it.
+    pub fn resume(mut self, token: ResumeToken) -> Self {
+        self.resume = Some(token);
+        self
+    }
+
+    /// Adds the metadata `key` with `value` to the upload.
+    pub fn metadata(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl RedactDebug for ResumableUpload {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>, redaction: &DebugRedaction) -> fmt::Result {
+        f.debug_struct("ResumableUpload")
+            .field("from_entity", &self.from_entity)
+            .field("request", &redaction.request(&self.request))
+            .field("source", &self.source)
+            .field("chunk_size", &self.chunk_size)
+            .field("retries", &self.retries)
+            .field("resume", &self.resume)
+            .field("metadata", &self.metadata)
+            .field("label", &self.label)
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ResumableUpload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_redacted(f, &DebugRedaction::default())
+    }
+}
+
+impl HttpClient {
+    /// Builds a `ResumableUpload` of `source` to the tus server whose creation URL is the URL of
+    /// the request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let upload = HttpClient::new()
+    ///     .post("https://uploads.example.com/files/")
+    ///     .resumable_upload(UploadSource::bytes(save))
+    ///     .chunk_size(1 << 20);
+    /// ```
+    pub fn resumable_upload(self, source: UploadSource) -> ResumableUpload {
+        let from_entity = self.from_entity;
+        let label = self.label.clone();
+        let client = self.client.clone();
+        let mut upload = ResumableUpload::new(self.into_request(), from_entity, source);
+        upload.label = label;
+        upload.client = client;
+        upload
+    }
+}
+
+/// Where the parts of a `ResumableUpload` are read from.
+#[derive(Clone)]
+pub enum UploadSource {
+    Bytes(Arc<Vec<u8>>),
+    /// A file read part by part, native only.
+    #[cfg(not(target_arch = "wasm32"))]
+    File(PathBuf),
+}
+
+impl UploadSource {
+    /// Uploads `bytes`.
+    pub fn bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self::Bytes(Arc::new(bytes.into()))
+    }
+
+    /// Uploads the file at `path`, without loading it in memory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File(path.into())
+    }
+
+    /// The size of the upload.
+    fn len(&self) -> Result<u64, HttpError> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes.len() as u64),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => std::fs::metadata(path)
+                .map(|metadata| metadata.len())
+                .map_err(|e| HttpError::Other(format!("failed to read {}: {e}", path.display()))),
+        }
+    }
+
+    /// Reads the part of up to `len` bytes starting at `offset`.
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, HttpError> {
+        match self {
+            Self::Bytes(bytes) => {
+                let start = (offset as usize).min(bytes.len());
+                let end = start.saturating_add(len).min(bytes.len());
+                Ok(bytes[start..end].to_vec())
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => {
+                use std::io::{Read, Seek, SeekFrom};
+
+                let read = || {
+                    let mut file = std::fs::File::open(path)?;
+                    file.seek(SeekFrom::Start(offset))?;
+                    let mut part = Vec::with_capacity(len);
+                    file.take(len as u64).read_to_end(&mut part)?;
+                    Ok(part)
+                };
+                read().map_err(|e: std::io::Error| {
+                    HttpError::Other(format!("failed to read {}: {e}", path.display()))
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Debug for UploadSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+        }
+    }
+}
+
+/// Identifies an upload on the server, to continue it with `ResumableUpload::resume`.
+///
+/// Serializable, so it can be saved until the game restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeToken {
+    /// The URL of the upload, returned by the server when it was created.
+    pub upload_url: String,
+    /// The size of the upload.
+    pub total: u64,
+}
+
+/// Sent when a resumable upload was created or resumed, then after each part.
+#[derive(Event, Debug, Clone)]
+pub struct ResumableUploadProgress {
+    pub from_entity: Option<Entity>,
+    pub label: Option<String>,
+    pub token: ResumeToken,
+    /// Bytes received by the server so far.
+    pub sent: u64,
+}
+
+/// Sent once the server received the whole upload.
+#[derive(Event, Debug, Clone)]
+pub struct ResumableUploadComplete {
+    pub from_entity: Option<Entity>,
+    pub label: Option<String>,
+    pub token: ResumeToken,
+}
+
+/// Sent when a resumable upload stopped before it completed.
+#[derive(Event, Debug, Clone)]
+pub struct ResumableUploadError {
+    pub from_entity: Option<Entity>,
+    pub label: Option<String>,
+    /// The token to continue the upload with, unless it failed before it was created.
+    pub token: Option<ResumeToken>,
+    pub err: HttpError,
+}
+
+/// A system that starts the resumable uploads, or queues them while their client has no free
+/// slot.
+///
+/// An upload holds its slot until it completed or failed. Its progress goes through the
+/// `HttpDispatcher` as parts of its result.
+pub(crate) fn handle_resumable_uploads(
+    mut req_res: ResMut<HttpClientSetting>,
+    mut clients: ResMut<HttpClients>,
+    mut uploads: EventReader<ResumableUpload>,
+    mut waiting: Local<Vec<ResumableUpload>>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+    middleware: Res<HttpMiddleware>,
+    clock: Res<HttpClock>,
+) {
+    // the uploads waiting for a free slot go first
+    for upload in waiting.iter() {
+        clients
+            .setting_mut(&mut req_res, upload.client.as_deref())
+            .queued -= 1;
+    }
+    let waiting_uploads = std::mem::take(&mut *waiting);
+    for upload in waiting_uploads.into_iter().chain(uploads.read().cloned()) {
+        let setting = clients.setting_mut(&mut req_res, upload.client.as_deref());
+        if !setting.try_start(clock.now()) {
+            setting.queued += 1;
+            waiting.push(upload);
+            continue;
+        }
+        let thread_pool = setting.fetch_pool.get();
+        let request_id_header = setting.request_id_header.clone();
+        let from_entity = upload.from_entity;
+        let label = upload.label.clone();
+        let failure_label = label.clone();
+        let failure_token = upload.resume.clone();
+        let sender = dispatcher.start(
+            clock.now(),
+            None,
+            TaskTags {
+                client: upload.client.clone(),
+                label: label.clone(),
+                from_entity,
+            },
+            move |world, failure| {
+                world.send_event(ResumableUploadError {
+                    from_entity,
+                    label: failure_label,
+                    token: failure_token,
+                    err: failure.into(),
+                });
+            },
+        );
+        let task = UploadTask {
+            middleware: middleware.clone(),
+            request_id_header,
+            sender,
+        };
+
+        thread_pool
+            .spawn(async move {
+                let result = task.run(&upload).await;
+                let mut command_queue = CommandQueue::default();
+                command_queue.push(move |world: &mut World| match result {
+                    Ok(token) => {
+                        world.send_event(ResumableUploadComplete {
+                            from_entity,
+                            label,
+                            token,
+                        });
+                    }
+                    Err((token, err)) => {
+                        world.send_event(ResumableUploadError {
+                            from_entity,
+                            label,
+                            token,
+                            err,
+                        });
+                    }
+                });
+                // dropped by the dispatcher if the upload was cancelled
+                task.sender.send(command_queue);
+            })
+            .detach();
+    }
+}
+
+/// Sends the requests of a resumable upload.
+struct UploadTask {
+    middleware: HttpMiddleware,
+    request_id_header: Option<String>,
+    /// Where the progress events and the result are sent.
+    sender: TaskSender,
+}
+
+impl UploadTask {
+    /// Creates or resumes the upload then sends its remaining parts, returning its token, or
+    /// the error it stopped with and its token if it was created.
+    async fn run(
+        &self,
+        upload: &ResumableUpload,
+    ) -> Result<ResumeToken, (Option<ResumeToken>, HttpError)> {
+        let total = upload.source.len().map_err(|e| (None, e))?;
+        let (token, mut sent) = match &upload.resume {
+            Some(token) if token.total != total => {
+                let err = HttpError::Other(format!(
+                    "cannot resume an upload of {} bytes with {total} bytes",
+                    token.total
+                ));
+                return Err((Some(token.clone()), err));
+            }
+            Some(token) => {
+                let sent = self
+                    .offset(upload, token)
+                    .await
+                    .map_err(|e| (Some(token.clone()), e))?;
+                (token.clone(), sent)
+            }
+            None => (self.create(upload, total).await.map_err(|e| (None, e))?, 0),
+        };
+        self.progress(upload, &token, sent);
+
+        let mut failures = 0;
+        while sent < total {
+            if self.sender.is_stopped() {
+                return Err((Some(token), HttpError::Cancelled("cancelled".to_string())));
+            }
+            let result = match upload.source.read(sent, upload.chunk_size) {
+                Ok(part) if part.is_empty() => {
+                    let err = HttpError::Other(format!("the source ended at {sent} bytes"));
+                    return Err((Some(token), err));
+                }
+                Ok(part) => self.send_part(upload, &token, sent, part).await,
+                Err(e) => return Err((Some(token), e)),
+            };
+            match result {
+                Ok(offset) if offset <= sent => {
+                    let err = HttpError::Other(format!("the server stopped at {offset} bytes"));
+                    return Err((Some(token), err));
+                }
+                Ok(offset) => {
+                    failures = 0;
+                    sent = offset;
+                    self.progress(upload, &token, sent);
+                }
+                Err(e) if failures < upload.retries && is_retryable(&e) => {
+                    failures += 1;
+                    // the server may have received part of the part
+                    if let Ok(offset) = self.offset(upload, &token).await {
+                        sent = offset;
+                    }
+                }
+                Err(e) => return Err((Some(token), e)),
+            }
+        }
+        Ok(token)
+    }
+
+    /// Creates an upload of `total` bytes.
+    async fn create(&self, upload: &ResumableUpload, total: u64) -> Result<ResumeToken, HttpError> {
+        let mut request = self.request(upload, "POST", upload.request.url.clone());
+        request.headers.insert("Upload-Length", total);
+        if !upload.metadata.is_empty() {
+            let metadata = upload
+                .metadata
+                .iter()
+                .map(|(key, value)| {
+                    let value = base64::engine::general_purpose::STANDARD.encode(value);
+                    format!("{key} {value}")
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            request.headers.insert("Upload-Metadata", metadata);
+        }
+        let response = self.fetch(request).await?;
+        let location = response
+            .headers
+            .get("Location")
+            .ok_or_else(|| HttpError::Other("the upload has no Location header".to_string()))?;
+        let upload_url = url::Url::parse(&upload.request.url)
+            .and_then(|url| url.join(location))
+            .map_err(|e| HttpError::Other(format!("invalid upload URL {location:?}: {e}")))?;
+        Ok(ResumeToken {
+            upload_url: upload_url.into(),
+            total,
+        })
+    }
+
+    /// Asks the server how many bytes of the upload it received.
+    async fn offset(
+        &self,
+        upload: &ResumableUpload,
+        token: &ResumeToken,
+    ) -> Result<u64, HttpError> {
+        let request = self.request(upload, "HEAD", token.upload_url.clone());
+        upload_offset(&self.fetch(request).await?)
+    }
+
+    /// Sends `part` at `offset`, returning the new offset of the upload.
+    async fn send_part(
+        &self,
+        upload: &ResumableUpload,
+        token: &ResumeToken,
+        offset: u64,
+        part: Vec<u8>,
+    ) -> Result<u64, HttpError> {
+        let mut request = self.request(upload, "PATCH", token.upload_url.clone());
+        request.headers.insert("Upload-Offset", offset);
+        request
+            .headers
+            .insert("Content-Type", "application/offset+octet-stream");
+        request.body = part;
+        upload_offset(&self.fetch(request).await?)
+    }
+
+    /// A tus request to `url`, with the headers of the upload request.
+    fn request(&self, upload: &ResumableUpload, method: &str, url: String) -> Request {
+        let mut headers = Headers::default();
+        for (key, value) in &upload.request.headers {
+            if !key.eq_ignore_ascii_case("content-type")
+                && !key.eq_ignore_ascii_case("content-length")
+            {
+                headers.insert(key, value);
+            }
+        }
+        headers.insert("Tus-Resumable", TUS_VERSION);
+        Request {
+            method: method.to_string(),
+            url,
+            body: Vec::new(),
+            headers,
+            #[cfg(target_arch = "wasm32")]
+            mode: upload.request.mode,
+        }
+    }
+
+    /// Sends `request`, with an error for the statuses other than 2xx.
+    async fn fetch(&self, mut request: Request) -> Result<Response, HttpError> {
+        let request_id = self
+            .request_id_header
+            .as_deref()
+            .map(|header| attach_request_id(&mut request, header));
+        let response = self
+            .middleware
+            .fetch(
+                request,
+                FetchOptions {
+                    request_id,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(HttpError::transport)?;
+        if response.ok {
+            Ok(response)
+        } else {
+            Err(HttpError::Status(response.status))
+        }
+    }
+
+    /// Sends a `ResumableUploadProgress` event.
+    fn progress(&self, upload: &ResumableUpload, token: &ResumeToken, sent: u64) {
+        let event = ResumableUploadProgress {
+            from_entity: upload.from_entity,
+            label: upload.label.clone(),
+            token: token.clone(),
+            sent,
+        };
+        let mut command_queue = CommandQueue::default();
+        command_queue.push(move |world: &mut World| {
+            world.send_event(event);
+        });
+        self.sender.send_part(command_queue);
+    }
+}
+
+/// The `Upload-Offset` header of `response`.
+fn upload_offset(response: &Response) -> Result<u64, HttpError> {
+    response
+        .headers
+        .get("Upload-Offset")
+        .and_then(|offset| offset.trim().parse().ok())
+        .ok_or_else(|| HttpError::Other("the upload has no valid Upload-Offset header".to_string()))
+}
+
+/// Whether a part failing with `e` is sent again: after a network error, a server error, or an
+/// offset conflict, which the retry resolves by asking the server for the offset.
+fn is_retryable(e: &HttpError) -> bool {
+    match e {
+        HttpError::Status(status) => *status == 409 || *status >= 500,
+        HttpError::Dns(_) | HttpError::ConnectionRefused(_) | HttpError::Timeout(_) => true,
+        // a missing or invalid Upload-Offset is a protocol error, sending again does not help
+        HttpError::Other(_)
+        | HttpError::TlsError(_)
+        | HttpError::Cancelled(_)
+        | HttpError::Decode(_)
+        | HttpError::Verification(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{harness::HttpTestHarness, mock::Mock};
+
+    fn upload(harness: &mut HttpTestHarness) {
+        harness
+            .mock(
+                Mock::post("https://uploads.example.com/files/")
+                    .status(201)
+                    .header("Location", "/files/42"),
+            )
+            .send(
+                HttpClient::new()
+                    .post("https://uploads.example.com/files/")
+                    .resumable_upload(UploadSource::bytes(b"hello".to_vec())),
+            );
+    }
+
+    #[test]
+    fn progress_and_completion_are_delivered() {
+        let mut harness = HttpTestHarness::new();
+        harness.mock(
+            Mock::new("PATCH", "https://uploads.example.com/files/42")
+                .status(204)
+                .header("Upload-Offset", "5"),
+        );
+        upload(&mut harness);
+        let sent: Vec<_> = harness
+            .drain_events::<ResumableUploadProgress>()
+            .iter()
+            .map(|progress| progress.sent)
+            .collect();
+        assert_eq!(sent, [0, 5]);
+        let complete = harness.drain_events::<ResumableUploadComplete>();
+        assert_eq!(
+            complete[0].token.upload_url,
+            "https://uploads.example.com/files/42"
+        );
+    }
+
+    #[test]
+    fn missing_offset_is_not_retried() {
+        let mut harness = HttpTestHarness::new();
+        harness.mock(Mock::new("PATCH", "https://uploads.example.com/files/42").status(204));
+        upload(&mut harness);
+        let errors = harness.drain_events::<ResumableUploadError>();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].err, HttpError::Other(_)));
+        let patches = harness
+            .sent_requests()
+            .iter()
+            .filter(|request| request.method == "PATCH")
+            .count();
+        assert_eq!(patches, 1);
+    }
+
+    #[test]
+    fn uploads_count_against_their_client() {
+        let mut app = bevy_app::App::new();
+        app.add_plugins(
+            crate::HttpClientPlugin::labeled("uploads").with_setting(HttpClientSetting::new(1)),
+        );
+        let mut harness = HttpTestHarness::with_app(app);
+        harness.mock(
+            Mock::post("https://uploads.example.com/files/")
+                .status(201)
+                .header("Location", "/files/42"),
+        );
+        for _ in 0..2 {
+            harness.send(
+                HttpClient::new()
+                    .post("https://uploads.example.com/files/")
+                    .on_client("uploads")
+                    .resumable_upload(UploadSource::bytes(b"hello".to_vec())),
+            );
+        }
+        // the second upload waits for the first one, held at its part
+        let posts = |harness: &HttpTestHarness| {
+            harness
+                .sent_requests()
+                .iter()
+                .filter(|request| request.method == "POST")
+                .count()
+        };
+        assert_eq!(posts(&harness), 1);
+        harness.respond(
+            Mock::new("PATCH", "https://uploads.example.com/files/42")
+                .status(204)
+                .header("Upload-Offset", "5"),
+        );
+        assert_eq!(posts(&harness), 2);
+    }
+}
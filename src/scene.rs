@@ -0,0 +1,215 @@
+use bevy_app::{App, Plugin, Update};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{prelude::*, reflect::AppTypeRegistry};
+use bevy_hierarchy::DespawnRecursiveExt;
+use bevy_scene::{serde::SceneDeserializer, DynamicScene, InstanceId, SceneSpawner};
+use serde::de::DeserializeSeed;
+
+use crate::{observe::HttpObserved, HttpClient, HttpRequest, HttpResponse, HttpResponseError};
+
+/// Spawns `DynamicScene`s downloaded as `.scn.ron` files, so level layouts can be updated
+/// without a client patch.
+///
+/// Requires the `HttpClientPlugin`, and the asset and scene plugins of Bevy. The components of
+/// the scene must be registered in the `AppTypeRegistry`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(RemoteScenePlugin)
+///     .add_systems(Startup, |mut ev_load: EventWriter<LoadRemoteScene>| {
+///         ev_load.send(LoadRemoteScene::new("https://cdn.example.com/levels/1.scn.ron"));
+///     });
+/// ```
+pub struct RemoteScenePlugin;
+
+impl Plugin for RemoteScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadRemoteScene>()
+            .add_event::<RemoteSceneProgress>()
+            .add_event::<RemoteSceneLoaded>()
+            .add_event::<RemoteSceneError>()
+            .add_systems(Update, handle_load_remote_scene)
+            .add_observer(spawn_remote_scene)
+            .add_observer(fail_remote_scene);
+    }
+}
+
+/// A request to download a scene and spawn it.
+#[derive(Event, Debug, Clone)]
+pub struct LoadRemoteScene {
+    pub request: HttpRequest,
+    /// The entity the scene is spawned as a child of.
+    pub parent: Option<Entity>,
+}
+
+impl LoadRemoteScene {
+    /// Downloads the scene at `url` with a GET request.
+    pub fn new(url: impl ToString) -> Self {
+        Self::from_client(HttpClient::new().get(url))
+    }
+
+    /// Downloads the scene with the request built by `client`, e.g. to set headers.
+    pub fn from_client(client: HttpClient) -> Self {
+        Self {
+            request: client.build(),
+            parent: None,
+        }
+    }
+
+    /// Spawns the scene as a child of `parent`.
+    pub fn child_of(mut self, parent: Entity) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+}
+
+/// A step of a remote scene load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteSceneStage {
+    /// The request was sent.
+    Downloading,
+    /// The scene file of `bytes` was received and is being deserialized.
+    Downloaded { bytes: usize },
+}
+
+/// Sent as a remote scene load goes through its stages.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteSceneProgress {
+    pub url: String,
+    pub stage: RemoteSceneStage,
+}
+
+/// Sent when a remote scene was deserialized and queued for spawning.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteSceneLoaded {
+    pub url: String,
+    /// The scene asset, to spawn it again without downloading it.
+    pub handle: Handle<DynamicScene>,
+    /// The spawned instance, see `SceneSpawner::instance_is_ready`.
+    pub instance_id: InstanceId,
+}
+
+/// Sent when a remote scene could not be downloaded or deserialized.
+#[derive(Event, Debug, Clone)]
+pub struct RemoteSceneError {
+    pub url: String,
+    pub err: String,
+}
+
+/// Tracks the download of a remote scene.
+#[derive(Component, Debug)]
+struct PendingRemoteScene {
+    url: String,
+    parent: Option<Entity>,
+}
+
+/// A system that sends the requests of remote scene loads.
+fn handle_load_remote_scene(
+    mut commands: Commands,
+    mut loads: EventReader<LoadRemoteScene>,
+    mut requests: EventWriter<HttpRequest>,
+    mut progress: EventWriter<RemoteSceneProgress>,
+) {
+    for load in loads.read() {
+        let url = load.request.request.url.clone();
+        let entity = commands
+            .spawn(PendingRemoteScene {
+                url: url.clone(),
+                parent: load.parent,
+            })
+            .id();
+        let mut request = load.request.clone();
+        request.from_entity = Some(entity);
+        requests.send(request);
+        progress.send(RemoteSceneProgress {
+            url,
+            stage: RemoteSceneStage::Downloading,
+        });
+    }
+}
+
+/// Deserializes a downloaded scene and spawns it.
+#[allow(clippy::too_many_arguments)]
+fn spawn_remote_scene(
+    trigger: Trigger<HttpObserved<HttpResponse>>,
+    mut commands: Commands,
+    pending: Query<&PendingRemoteScene>,
+    type_registry: Res<AppTypeRegistry>,
+    mut scenes: ResMut<Assets<DynamicScene>>,
+    mut spawner: ResMut<SceneSpawner>,
+    mut progress: EventWriter<RemoteSceneProgress>,
+    mut loaded: EventWriter<RemoteSceneLoaded>,
+    mut errors: EventWriter<RemoteSceneError>,
+) {
+    let entity = trigger.entity();
+    let Ok(PendingRemoteScene { url, parent }) = pending.get(entity) else {
+        return;
+    };
+    commands.entity(entity).despawn_recursive();
+
+    let response = trigger.event();
+    if !response.ok {
+        errors.send(RemoteSceneError {
+            url: url.clone(),
+            err: format!("HTTP status {} {}", response.status, response.status_text),
+        });
+        return;
+    }
+    progress.send(RemoteSceneProgress {
+        url: url.clone(),
+        stage: RemoteSceneStage::Downloaded {
+            bytes: response.bytes.len(),
+        },
+    });
+
+    let scene = {
+        let type_registry = type_registry.read();
+        ron::de::Deserializer::from_bytes(&response.bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|mut deserializer| {
+                SceneDeserializer {
+                    type_registry: &type_registry,
+                }
+                .deserialize(&mut deserializer)
+                .map_err(|e| deserializer.span_error(e).to_string())
+            })
+    };
+    match scene {
+        Ok(scene) => {
+            let handle = scenes.add(scene);
+            let instance_id = match parent {
+                Some(parent) => spawner.spawn_dynamic_as_child(handle.clone(), *parent),
+                None => spawner.spawn_dynamic(handle.clone()),
+            };
+            loaded.send(RemoteSceneLoaded {
+                url: url.clone(),
+                handle,
+                instance_id,
+            });
+        }
+        Err(err) => {
+            errors.send(RemoteSceneError {
+                url: url.clone(),
+                err,
+            });
+        }
+    }
+}
+
+/// Reports a failed scene download.
+fn fail_remote_scene(
+    trigger: Trigger<HttpObserved<HttpResponseError>>,
+    mut commands: Commands,
+    pending: Query<&PendingRemoteScene>,
+    mut errors: EventWriter<RemoteSceneError>,
+) {
+    let entity = trigger.entity();
+    if let Ok(PendingRemoteScene { url, .. }) = pending.get(entity) {
+        commands.entity(entity).despawn_recursive();
+        errors.send(RemoteSceneError {
+            url: url.clone(),
+            err: trigger.event().err.to_string(),
+        });
+    }
+}
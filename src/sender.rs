@@ -0,0 +1,80 @@
+use bevy_ecs::{prelude::*, world::CommandQueue};
+use crossbeam_channel::{Receiver, Sender};
+use serde::Deserialize;
+
+use crate::{typed::TypedRequest, HttpRequest};
+
+/// Sends requests from outside the ECS, e.g. from background threads, audio callbacks or native
+/// plugins, without world access.
+///
+/// Cloned from the resource added by the `HttpClientPlugin`. The requests are sent as events at
+/// the next update, before `HttpClientSet::Dispatch`, and their responses are delivered as usual.
+///
+/// # Examples
+///
+/// ```
+/// fn start_uploader(sender: Res<HttpRequestSender>) {
+///     let sender = sender.clone();
+///     std::thread::spawn(move || {
+///         for crash_report in crash_reports() {
+///             let request = HttpClient::new()
+///                 .post("https://api.example.com/crashes")
+///                 .body_bytes(crash_report)
+///                 .build();
+///             if !sender.send(request) {
+///                 break;
+///             }
+///         }
+///     });
+/// }
+/// ```
+#[derive(Resource, Debug, Clone)]
+pub struct HttpRequestSender(Sender<CommandQueue>);
+
+impl HttpRequestSender {
+    /// Sends `request`, returning `false` if the app is gone.
+    pub fn send(&self, request: HttpRequest) -> bool {
+        self.send_event(request)
+    }
+
+    /// Sends the typed `request`, returning `false` if the app is gone.
+    ///
+    /// `T` must be registered with `register_request_type`.
+    pub fn send_typed<T>(&self, request: TypedRequest<T>) -> bool
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+    {
+        self.send_event(request)
+    }
+
+    fn send_event(&self, event: impl Event) -> bool {
+        self.send_command(move |world: &mut World| {
+            world.send_event(event);
+        })
+    }
+
+    /// Applies `command` to the world at the next update, returning `false` if the app is gone.
+    pub(crate) fn send_command(&self, command: impl Command) -> bool {
+        let mut command_queue = CommandQueue::default();
+        command_queue.push(command);
+        self.0.send(command_queue).is_ok()
+    }
+}
+
+/// Where the `HttpRequestSender` clones send their requests, drained by
+/// `send_external_requests`.
+#[derive(Resource)]
+pub(crate) struct HttpRequestReceiver(Receiver<CommandQueue>);
+
+/// Creates the sender of the external requests and its receiver.
+pub(crate) fn request_channel() -> (HttpRequestSender, HttpRequestReceiver) {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    (HttpRequestSender(tx), HttpRequestReceiver(rx))
+}
+
+/// A system that sends the requests received from the `HttpRequestSender` clones.
+pub(crate) fn send_external_requests(mut commands: Commands, receiver: Res<HttpRequestReceiver>) {
+    for mut command_queue in receiver.0.try_iter() {
+        commands.append(&mut command_queue);
+    }
+}
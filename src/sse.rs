@@ -0,0 +1,494 @@
+use std::{ops::ControlFlow, sync::Mutex};
+
+use bevy_app::{App, Plugin};
+use bevy_derive::Deref;
+use bevy_ecs::{prelude::*, world::CommandQueue};
+use bevy_utils::HashMap;
+use ehttp::streaming::Part;
+use serde::Deserialize;
+
+use crate::{
+    error::HttpError, http_schedule, middleware::HttpMiddleware, typed::from_json_slice,
+    HttpClient, HttpClientSet, HttpClientSetting, HttpClients, HttpClock, HttpDispatcher,
+    HttpRequest, TaskSender, TaskTags,
+};
+
+/// Opens Server-Sent Events streams with `OpenSseStream` events.
+///
+/// Requires the `HttpClientPlugin`. The events whose name was registered with
+/// `register_sse_event` are deserialized from their JSON data and sent as `SseEvent<T>`, the
+/// others as `SseMessage`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(HttpSsePlugin)
+///     .register_sse_event::<MatchFound>("match_found")
+///     .register_sse_event::<ChatMessage>("chat")
+///     .add_systems(Startup, |mut ev_open: EventWriter<OpenSseStream>| {
+///         ev_open.send(OpenSseStream::new("https://example.com/lobby/events"));
+///     })
+///     .add_systems(Update, |mut ev_match: EventReader<SseEvent<MatchFound>>| {
+///         for event in ev_match.read() {
+///             println!("match {} found", event.match_id);
+///         }
+///     });
+/// ```
+pub struct HttpSsePlugin;
+
+impl Plugin for HttpSsePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SseRegistry>()
+            .add_event::<OpenSseStream>()
+            .add_event::<SseMessage>()
+            .add_event::<SseEventError>()
+            .add_event::<SseStreamClosed>();
+        let schedule = http_schedule(app);
+        app.add_systems(
+            schedule,
+            handle_open_sse_streams.in_set(HttpClientSet::Dispatch),
+        );
+    }
+}
+
+pub trait HttpSseAppExt {
+    /// Deserializes the data of the SSE events named `name` into `T`, sent as `SseEvent<T>`
+    /// events.
+    ///
+    /// The data that fails to deserialize is reported as an `SseEventError`.
+    fn register_sse_event<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + 'static;
+}
+
+impl HttpSseAppExt for App {
+    fn register_sse_event<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + 'static,
+    {
+        self.add_event::<SseEvent<T>>();
+        self.world_mut()
+            .get_resource_or_init::<SseRegistry>()
+            .0
+            .insert(name.to_string(), deliver_typed::<T>);
+        self
+    }
+}
+
+/// How the SSE events are delivered, by name.
+#[derive(Resource, Default)]
+struct SseRegistry(HashMap<String, fn(&mut World, SseMessage)>);
+
+/// A request to open a Server-Sent Events stream.
+///
+/// The events of the stream carry the entity it was opened from, if any. An open stream holds a
+/// slot of its client, the streams over the limit waiting for a free one. The stream is closed by
+/// the server, or with `CancelHttpRequests`, e.g. by its label, which sends `SseStreamClosed` with
+/// `HttpError::Cancelled`.
+#[derive(Event, Debug, Clone)]
+pub struct OpenSseStream {
+    pub request: HttpRequest,
+}
+
+impl OpenSseStream {
+    /// Opens the stream at `url` with a GET request.
+    pub fn new(url: impl ToString) -> Self {
+        Self::from_client(HttpClient::new().get(url))
+    }
+
+    /// Opens the stream with the request built by `client`, e.g. to set headers.
+    pub fn from_client(client: HttpClient) -> Self {
+        Self {
+            request: client.header("Accept", "text/event-stream").build(),
+        }
+    }
+}
+
+/// An SSE event whose name has no registered type.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct SseMessage {
+    /// The URL of the stream.
+    pub url: String,
+    /// The entity the stream was opened from.
+    pub from_entity: Option<Entity>,
+    /// The name of the event, "message" by default.
+    pub event: String,
+    /// The data lines of the event, joined with newlines.
+    pub data: String,
+    /// The ID of the event, or of the last event that had one.
+    pub id: Option<String>,
+}
+
+/// An SSE event whose data was deserialized into `T`, see `register_sse_event`.
+#[derive(Event, Debug, Clone, Deref)]
+pub struct SseEvent<T> {
+    #[deref]
+    pub data: T,
+    /// The URL of the stream.
+    pub url: String,
+    /// The entity the stream was opened from.
+    pub from_entity: Option<Entity>,
+    /// The ID of the event, or of the last event that had one.
+    pub id: Option<String>,
+}
+
+/// Sent when the data of a registered SSE event could not be deserialized.
+#[derive(Event, Debug, Clone)]
+pub struct SseEventError {
+    /// The event as it was received.
+    pub message: SseMessage,
+    pub err: HttpError,
+}
+
+/// Sent when an SSE stream stopped, with the error it stopped with, if any.
+#[derive(Event, Debug, Clone)]
+pub struct SseStreamClosed {
+    pub url: String,
+    pub from_entity: Option<Entity>,
+    pub err: Option<HttpError>,
+}
+
+/// A system that opens the requested SSE streams, or queues them while their client has no free
+/// slot.
+fn handle_open_sse_streams(
+    mut req_res: ResMut<HttpClientSetting>,
+    mut clients: ResMut<HttpClients>,
+    mut opens: EventReader<OpenSseStream>,
+    mut waiting: Local<Vec<HttpRequest>>,
+    mut dispatcher: ResMut<HttpDispatcher>,
+    middleware: Res<HttpMiddleware>,
+    clock: Res<HttpClock>,
+) {
+    // the streams waiting for a free slot go first
+    for request in waiting.iter() {
+        clients
+            .setting_mut(&mut req_res, request.client.as_deref())
+            .queued -= 1;
+    }
+    let waiting_requests = std::mem::take(&mut *waiting);
+    let opens = opens.read().map(|open| open.request.clone());
+    for request in waiting_requests.into_iter().chain(opens) {
+        let setting = clients.setting_mut(&mut req_res, request.client.as_deref());
+        if !setting.try_start(clock.now()) {
+            setting.queued += 1;
+            waiting.push(request);
+            continue;
+        }
+        let url = request.request.url.clone();
+        let from_entity = request.from_entity;
+        let failure_url = url.clone();
+        // the events are sent as parts of the result, which is the end of the stream
+        let sender = dispatcher.start(
+            clock.now(),
+            None,
+            TaskTags {
+                client: request.client,
+                label: request.label,
+                from_entity,
+            },
+            move |world, failure| {
+                world.send_event(SseStreamClosed {
+                    url: failure_url,
+                    from_entity,
+                    err: Some(failure.into()),
+                });
+            },
+        );
+        let stream = SseStream {
+            url,
+            from_entity,
+            sender: Mutex::new(Some(sender)),
+            state: Mutex::default(),
+        };
+        middleware.stream(request.request, move |part| stream.on_data(part));
+    }
+}
+
+/// Parses the parts of an SSE stream into events.
+struct SseStream {
+    url: String,
+    from_entity: Option<Entity>,
+    /// Where the events are sent, taken once the stream stopped.
+    sender: Mutex<Option<TaskSender>>,
+    state: Mutex<SseParser>,
+}
+
+impl SseStream {
+    fn on_data(&self, part: ehttp::Result<Part>) -> ControlFlow<()> {
+        let chunk = match part {
+            Ok(Part::Response(response)) if response.ok => return ControlFlow::Continue(()),
+            Ok(Part::Response(response)) => {
+                return self.close(Some(HttpError::Status(response.status)))
+            }
+            Ok(Part::Chunk(chunk)) if chunk.is_empty() => return self.close(None),
+            Ok(Part::Chunk(chunk)) => chunk,
+            Err(e) => return self.close(Some(HttpError::transport(e))),
+        };
+        let messages = self
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(&chunk);
+        if messages.is_empty() {
+            return ControlFlow::Continue(());
+        }
+        let mut command_queue = CommandQueue::default();
+        for (event, data, id) in messages {
+            let message = SseMessage {
+                url: self.url.clone(),
+                from_entity: self.from_entity,
+                event,
+                data,
+                id,
+            };
+            command_queue.push(move |world: &mut World| deliver(world, message));
+        }
+        // a cancelled stream is closed
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+        match sender.as_ref() {
+            Some(sender) if sender.send_part(command_queue) => ControlFlow::Continue(()),
+            _ => ControlFlow::Break(()),
+        }
+    }
+
+    /// Stops the stream, with `err` if it failed.
+    fn close(&self, err: Option<HttpError>) -> ControlFlow<()> {
+        let closed = SseStreamClosed {
+            url: self.url.clone(),
+            from_entity: self.from_entity,
+            err,
+        };
+        let mut command_queue = CommandQueue::default();
+        command_queue.push(move |world: &mut World| {
+            world.send_event(closed);
+        });
+        // frees the slot of the stream
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(sender) = sender {
+            sender.send(command_queue);
+        }
+        ControlFlow::Break(())
+    }
+}
+
+/// The state of the SSE parser between two chunks, see the `text/event-stream` format of the
+/// HTML standard.
+#[derive(Default)]
+struct SseParser {
+    /// The bytes of the line being received.
+    line: Vec<u8>,
+    /// Whether the last chunk ended with "\r", whose "\n" must not end another line.
+    after_cr: bool,
+    event: String,
+    data: Option<String>,
+    last_id: Option<String>,
+}
+
+impl SseParser {
+    /// Parses `chunk`, returning the name, data and ID of the events it completed.
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, String, Option<String>)> {
+        let mut messages = Vec::new();
+        for &byte in chunk {
+            let after_cr = std::mem::take(&mut self.after_cr);
+            match byte {
+                b'\n' if after_cr => {}
+                b'\r' | b'\n' => {
+                    self.after_cr = byte == b'\r';
+                    let line =
+                        String::from_utf8_lossy(&std::mem::take(&mut self.line)).into_owned();
+                    messages.extend(self.line_ended(&line));
+                }
+                byte => self.line.push(byte),
+            }
+        }
+        messages
+    }
+
+    /// Handles a complete `line`, returning the event it completed, if any.
+    fn line_ended(&mut self, line: &str) -> Option<(String, String, Option<String>)> {
+        if line.is_empty() {
+            let event = std::mem::take(&mut self.event);
+            // an event without data is not dispatched
+            let data = self.data.take()?;
+            let event = if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            };
+            return Some((event, data, self.last_id.clone()));
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            // comments start with ":", and "retry" only matters to reconnecting clients
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Sends `message` as the typed event registered for its name, or as an `SseMessage`.
+fn deliver(world: &mut World, message: SseMessage) {
+    let typed = world
+        .get_resource::<SseRegistry>()
+        .and_then(|registry| registry.0.get(&message.event).copied());
+    match typed {
+        Some(deliver_typed) => deliver_typed(world, message),
+        None => {
+            world.send_event(message);
+        }
+    }
+}
+
+/// Deserializes the data of `message` into `T` and sends it as an `SseEvent<T>`.
+fn deliver_typed<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    world: &mut World,
+    message: SseMessage,
+) {
+    match from_json_slice::<T>(message.data.as_bytes()) {
+        Ok(data) => {
+            world.send_event(SseEvent {
+                data,
+                url: message.url,
+                from_entity: message.from_entity,
+                id: message.id,
+            });
+        }
+        Err(e) => {
+            world.send_event(SseEventError {
+                message,
+                err: HttpError::Decode(e.message),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_utils::Instant;
+
+    use super::*;
+    use crate::{harness::HttpTestHarness, mock::Mock, TaskFailure};
+
+    fn event(name: &str, data: &str, id: Option<&str>) -> (String, String, Option<String>) {
+        (
+            name.to_string(),
+            data.to_string(),
+            id.map(ToString::to_string),
+        )
+    }
+
+    #[test]
+    fn parses_named_and_default_events() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b"event: score\ndata: {\"points\":3}\n\ndata: hi\n\n");
+        assert_eq!(
+            events,
+            vec![
+                event("score", "{\"points\":3}", None),
+                event("message", "hi", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b"data: first\ndata:second\n: comment\n\n");
+        assert_eq!(events, vec![event("message", "first\nsecond", None)]);
+    }
+
+    #[test]
+    fn handles_line_endings_split_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"data: a\r").is_empty());
+        // the "\n" after "\r" ends no other line
+        assert_eq!(parser.push(b"\n\r\n"), vec![event("message", "a", None)]);
+        assert_eq!(
+            parser.push(b"data: b\r\r"),
+            vec![event("message", "b", None)]
+        );
+    }
+
+    #[test]
+    fn keeps_the_last_id() {
+        let mut parser = SseParser::default();
+        let events = parser.push(b"id: 7\ndata: x\n\ndata: y\n\nid: 8\0\ndata: z\n\n");
+        assert_eq!(
+            events,
+            vec![
+                event("message", "x", Some("7")),
+                event("message", "y", Some("7")),
+                event("message", "z", Some("7")),
+            ]
+        );
+    }
+
+    #[test]
+    fn does_not_dispatch_events_without_data() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: ping\n\n").is_empty());
+        // the name of the undispatched event is dropped
+        assert_eq!(
+            parser.push(b"data: hi\n\n"),
+            vec![event("message", "hi", None)]
+        );
+    }
+
+    #[test]
+    fn streams_wait_for_a_free_slot() {
+        let mut app = bevy_app::App::new();
+        app.insert_resource(HttpClientSetting::new(1));
+        let mut harness = HttpTestHarness::with_app(app);
+        harness.app.add_plugins(HttpSsePlugin);
+        harness.mock(Mock::get("https://example.com/*").body("data: hi\n\n"));
+        for url in ["https://example.com/a", "https://example.com/b"] {
+            harness.app.world_mut().send_event(OpenSseStream::new(url));
+        }
+        harness.app.update();
+        // the mocked stream ends right away, its slot being freed once its end is collected
+        assert_eq!(harness.sent_requests().len(), 1);
+        harness.settle();
+        assert_eq!(harness.sent_requests().len(), 2);
+        assert_eq!(harness.drain_events::<SseMessage>().len(), 2);
+        let closed = harness.drain_events::<SseStreamClosed>();
+        assert_eq!(closed.len(), 2);
+        assert!(closed.iter().all(|closed| closed.err.is_none()));
+    }
+
+    #[test]
+    fn cancelled_stream_stops() {
+        let mut setting = HttpClientSetting::default();
+        assert!(setting.try_start(Instant::now()));
+        let mut dispatcher = HttpDispatcher::new(8);
+        let sender = dispatcher.start(Instant::now(), None, TaskTags::default(), |_, _| {});
+        let id = sender.id;
+        let stream = SseStream {
+            url: "https://example.com/events".to_string(),
+            from_entity: None,
+            sender: Mutex::new(Some(sender)),
+            state: Mutex::default(),
+        };
+        let chunk = || Ok(Part::Chunk(b"data: hi\n\n".to_vec()));
+        assert!(stream.on_data(chunk()).is_continue());
+        let world = World::new();
+        let mut queue = CommandQueue::default();
+        dispatcher.fail(
+            id,
+            TaskFailure::Cancelled,
+            &mut Commands::new(&mut queue, &world),
+            &mut setting,
+            &mut HttpClients::default(),
+        );
+        assert!(stream.on_data(chunk()).is_break());
+    }
+}
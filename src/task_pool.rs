@@ -0,0 +1,26 @@
+use bevy_reflect::Reflect;
+use bevy_tasks::{AsyncComputeTaskPool, ComputeTaskPool, IoTaskPool, TaskPool};
+
+/// A task pool of Bevy the requests are handled on, see
+/// `HttpClientSetting::with_task_pools`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum HttpTaskPool {
+    /// The `IoTaskPool`, for work waiting on the network.
+    Io,
+    /// The `AsyncComputeTaskPool`, for CPU-bound work spanning frames.
+    AsyncCompute,
+    /// The `ComputeTaskPool`, shared with the systems of the app.
+    Compute,
+}
+
+impl HttpTaskPool {
+    /// The task pool, created with its default threads if the `TaskPoolPlugin` did not create
+    /// it.
+    pub(crate) fn get(self) -> &'static TaskPool {
+        match self {
+            Self::Io => IoTaskPool::get_or_init(TaskPool::new),
+            Self::AsyncCompute => AsyncComputeTaskPool::get_or_init(TaskPool::new),
+            Self::Compute => ComputeTaskPool::get_or_init(TaskPool::new),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::VecDeque;
+
+use bevy_app::{App, AppExit, Last, Plugin, Startup};
+use bevy_ecs::prelude::*;
+use bevy_utils::{tracing, Duration, Instant};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    observe::HttpObserved, HttpClient, HttpClock, HttpRequest, HttpResponse, HttpResponseError,
+};
+
+/// Sends the events pushed to the `TelemetryQueue` in batches, as JSON arrays POSTed to an
+/// endpoint.
+///
+/// A batch is sent when the queue holds a full batch, when the flush interval elapsed since the
+/// last flush, and on `AppExit`, without waiting for the response. One batch is in flight at a
+/// time. A failed batch is retried up to the retry limit and dropped after that. When the queue
+/// is full, events are dropped following the drop policy.
+///
+/// Requires the `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// app.add_plugins(
+///     TelemetryPlugin::new("https://example.com/events")
+///         .batch_size(50)
+///         .flush_interval(Duration::from_secs(10)),
+/// );
+///
+/// fn track_level_up(mut telemetry: ResMut<TelemetryQueue>) {
+///     telemetry.push(&serde_json::json!({ "event": "level_up", "level": 3 }));
+/// }
+/// ```
+pub struct TelemetryPlugin {
+    client: HttpClient,
+    batch_size: usize,
+    flush_interval: Duration,
+    capacity: usize,
+    retries: u32,
+    drop_policy: DropPolicy,
+}
+
+impl TelemetryPlugin {
+    /// Sends the batches to `url`.
+    pub fn new(url: impl ToString) -> Self {
+        Self::from_client(HttpClient::new().post(url))
+    }
+
+    /// Sends the batches with the request built by `client`, e.g. to set headers.
+    pub fn from_client(client: HttpClient) -> Self {
+        Self {
+            client,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(30),
+            capacity: 10_000,
+            retries: 3,
+            drop_policy: DropPolicy::DropOldest,
+        }
+    }
+
+    /// Sends at most `batch_size` events per request, 100 by default.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sends the queued events at least every `interval`, 30 seconds by default.
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Keeps at most `capacity` events queued, 10000 by default.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Retries a failed batch up to `retries` times, 3 by default.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Which events are dropped when the queue is full.
+    pub fn drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+}
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TelemetryQueue {
+            client: self.client.clone(),
+            events: VecDeque::new(),
+            batch_size: self.batch_size,
+            flush_interval: self.flush_interval,
+            capacity: self.capacity,
+            retries: self.retries,
+            drop_policy: self.drop_policy,
+            dropped: 0,
+            entity: Entity::PLACEHOLDER,
+            in_flight: None,
+            retry: None,
+            last_flush: None,
+        })
+        .add_systems(Startup, spawn_telemetry_sender)
+        .add_systems(Last, flush_telemetry)
+        .add_observer(complete_telemetry_batch)
+        .add_observer(fail_telemetry_batch);
+    }
+}
+
+/// Which events are dropped when the telemetry queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Drop the oldest queued event to make room for the new one.
+    DropOldest,
+    /// Drop the new event.
+    DropNewest,
+}
+
+/// The events waiting to be sent by the `TelemetryPlugin`.
+#[derive(Resource, Debug)]
+pub struct TelemetryQueue {
+    client: HttpClient,
+    events: VecDeque<Value>,
+    batch_size: usize,
+    flush_interval: Duration,
+    capacity: usize,
+    retries: u32,
+    drop_policy: DropPolicy,
+    dropped: u64,
+    /// The entity the batches are sent from, to recognize their responses.
+    entity: Entity,
+    in_flight: Option<Batch>,
+    /// A failed batch, sent again before the queued events.
+    retry: Option<Batch>,
+    last_flush: Option<Instant>,
+}
+
+/// A batch of events sent together.
+#[derive(Debug)]
+struct Batch {
+    events: Vec<Value>,
+    attempts: u32,
+}
+
+impl TelemetryQueue {
+    /// Queues `event` to be sent with the next batch.
+    pub fn push(&mut self, event: &impl Serialize) {
+        let event = match serde_json::to_value(event) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("dropping telemetry event that failed to serialize: {e}");
+                self.dropped += 1;
+                return;
+            }
+        };
+        if self.events.len() >= self.capacity {
+            self.dropped += 1;
+            match self.drop_policy {
+                DropPolicy::DropOldest => {
+                    self.events.pop_front();
+                }
+                DropPolicy::DropNewest => return,
+            }
+        }
+        self.events.push_back(event);
+    }
+
+    /// The number of queued events, not counting the batch in flight.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether no event is queued.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The number of events dropped because the queue was full or a batch failed too many times.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Takes the next batch to send: the batch to retry if any, the oldest events otherwise.
+    fn next_batch(&mut self) -> Option<Batch> {
+        if let Some(batch) = self.retry.take() {
+            return Some(batch);
+        }
+        if self.events.is_empty() {
+            return None;
+        }
+        let count = self.batch_size.min(self.events.len());
+        Some(Batch {
+            events: self.events.drain(..count).collect(),
+            attempts: 0,
+        })
+    }
+
+    /// Sends `batch` from the telemetry entity.
+    fn send(&mut self, batch: Batch) -> HttpRequest {
+        let request = self
+            .client
+            .clone()
+            .entity(self.entity)
+            .json(&batch.events)
+            .build();
+        self.in_flight = Some(batch);
+        request
+    }
+
+    /// Completes the batch in flight, keeping it to be retried if it failed.
+    fn complete(&mut self, ok: bool) {
+        let Some(batch) = self.in_flight.take() else {
+            return;
+        };
+        if ok {
+            return;
+        }
+        if batch.attempts >= self.retries {
+            tracing::warn!(
+                "dropping {} telemetry events after {} attempts",
+                batch.events.len(),
+                batch.attempts + 1
+            );
+            self.dropped += batch.events.len() as u64;
+            return;
+        }
+        self.retry = Some(Batch {
+            events: batch.events,
+            attempts: batch.attempts + 1,
+        });
+    }
+}
+
+/// A system that spawns the entity the telemetry batches are sent from.
+fn spawn_telemetry_sender(mut commands: Commands, mut queue: ResMut<TelemetryQueue>) {
+    queue.entity = commands.spawn_empty().id();
+}
+
+/// A system that sends a batch when full or due, and everything left when the app exits.
+fn flush_telemetry(
+    mut queue: ResMut<TelemetryQueue>,
+    mut requests: EventWriter<HttpRequest>,
+    mut exits: EventReader<AppExit>,
+    clock: Res<HttpClock>,
+) {
+    if exits.read().count() > 0 {
+        // the response will not be awaited, send the batch in flight again with the rest
+        let mut events: Vec<Value> = [queue.in_flight.take(), queue.retry.take()]
+            .into_iter()
+            .flatten()
+            .flat_map(|batch| batch.events)
+            .collect();
+        events.extend(queue.events.drain(..));
+        if !events.is_empty() {
+            let request = queue.send(Batch {
+                events,
+                attempts: 0,
+            });
+            requests.send(request);
+        }
+        return;
+    }
+
+    let now = clock.now();
+    let last_flush = *queue.last_flush.get_or_insert(now);
+    let due = queue.len() >= queue.batch_size || now >= last_flush + queue.flush_interval;
+    if !due || queue.in_flight.is_some() {
+        return;
+    }
+    if let Some(batch) = queue.next_batch() {
+        let request = queue.send(batch);
+        requests.send(request);
+    }
+    queue.last_flush = Some(now);
+}
+
+/// Completes a sent telemetry batch.
+fn complete_telemetry_batch(
+    trigger: Trigger<HttpObserved<HttpResponse>>,
+    mut queue: ResMut<TelemetryQueue>,
+) {
+    if trigger.entity() == queue.entity {
+        queue.complete(trigger.event().ok);
+    }
+}
+
+/// Retries a telemetry batch that failed to send.
+fn fail_telemetry_batch(
+    trigger: Trigger<HttpObserved<HttpResponseError>>,
+    mut queue: ResMut<TelemetryQueue>,
+) {
+    if trigger.entity() == queue.entity {
+        queue.complete(false);
+    }
+}
@@ -0,0 +1,69 @@
+use bevy_ecs::prelude::*;
+use bevy_utils::Duration;
+use ehttp::Headers;
+
+use crate::HttpClient;
+
+/// Shared configuration that concrete requests are instantiated from.
+///
+/// Store it as a resource or a component, and call `instantiate` wherever a request is built
+/// instead of repeating the base URL, headers and timeout.
+///
+/// # Examples
+///
+/// ```
+/// commands.insert_resource(
+///     HttpRequestTemplate::new("https://example.com/api")
+///         .bearer_auth("token")
+///         .timeout(Duration::from_secs(5)),
+/// );
+///
+/// fn poll_status(template: Res<HttpRequestTemplate>, mut ev_request: EventWriter<HttpRequest>) {
+///     ev_request.send(template.instantiate().get("/status").build());
+/// }
+/// ```
+#[derive(Resource, Component, Debug, Clone, Default)]
+pub struct HttpRequestTemplate {
+    /// Base URL that relative request URLs are resolved against.
+    pub base_url: Option<String>,
+    /// Headers of every instantiated request.
+    pub headers: Headers,
+    /// Timeout of every instantiated request.
+    pub timeout: Option<Duration>,
+}
+
+impl HttpRequestTemplate {
+    /// Creates a template resolving relative URLs against `base_url`.
+    pub fn new(base_url: impl ToString) -> Self {
+        Self {
+            base_url: Some(base_url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a header to every instantiated request.
+    pub fn header(mut self, key: impl ToString, value: impl ToString) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Adds an `Authorization: Bearer <token>` header to every instantiated request.
+    pub fn bearer_auth(self, token: impl std::fmt::Display) -> Self {
+        self.header("Authorization", format!("Bearer {token}"))
+    }
+
+    /// Sets the timeout of every instantiated request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Creates a request builder pre-filled from this template.
+    pub fn instantiate(&self) -> HttpClient {
+        let mut client = HttpClient::new();
+        client.headers = Some(self.headers.clone());
+        client.base_url = self.base_url.clone();
+        client.timeout = self.timeout;
+        client
+    }
+}
@@ -0,0 +1,413 @@
+use std::{net::IpAddr, path::PathBuf};
+
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+
+use crate::lifecycle::{RequestInfo, UploadProgress};
+
+/// How a request reaches its host, overriding the proxy of its client, see `HttpClient::proxy`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestProxy {
+    /// Connects to the host directly.
+    Direct,
+    /// Goes through the proxy at this URL.
+    Via(String),
+}
+
+impl RequestProxy {
+    /// The URL of the proxy, `None` to connect directly.
+    pub(crate) fn url(self) -> Option<String> {
+        match self {
+            Self::Direct => None,
+            Self::Via(url) => Some(url),
+        }
+    }
+}
+
+/// A file streamed as the body of a request, see `HttpClient::body_from_file`.
+#[derive(Debug, Clone)]
+pub(crate) struct FileBody {
+    pub(crate) path: PathBuf,
+    /// Where the progress of the upload is sent.
+    pub(crate) progress: Option<Sender<UploadProgress>>,
+    /// The request the progress is about.
+    pub(crate) info: RequestInfo,
+}
+
+impl FileBody {
+    /// Reports that `sent` bytes of the `total` of the file were sent.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    fn report(&self, sent: u64, total: u64) {
+        if let Some(progress) = &self.progress {
+            progress
+                .send(UploadProgress {
+                    request: self.info.clone(),
+                    sent,
+                    total,
+                })
+                .ok();
+        }
+    }
+}
+
+/// Resolves host names for the native backend, e.g. for split-horizon DNS, pinned IPs or
+/// `.local` hosts on a LAN, see `HttpMiddlewareAppExt::set_http_resolver`.
+///
+/// Implemented for closures taking the host name.
+pub trait HttpResolver: Send + Sync + 'static {
+    /// Returns the addresses of `host`, tried in order, or `None` to resolve it with the system
+    /// resolver.
+    fn resolve(&self, host: &str) -> Option<Vec<IpAddr>>;
+}
+
+impl<F> HttpResolver for F
+where
+    F: Fn(&str) -> Option<Vec<IpAddr>> + Send + Sync + 'static,
+{
+    fn resolve(&self, host: &str) -> Option<Vec<IpAddr>> {
+        self(host)
+    }
+}
+
+/// Which IP family the native backend connects over to hosts with both IPv4 and IPv6
+/// addresses, see `HttpMiddlewareAppExt::set_http_ip_family`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpFamily {
+    /// The addresses are tried in the order of the resolver.
+    #[default]
+    System,
+    /// The IPv4 addresses are tried first.
+    PreferIpv4,
+    /// The IPv6 addresses are tried first.
+    PreferIpv6,
+    /// The families alternate, starting with IPv6. This only orders the addresses, they are still
+    /// tried one at a time: `ureq` connects by itself, so connections cannot be raced as Happy
+    /// Eyeballs does, and an unreachable address holds up the next one until it times out.
+    AlternatingFamilies,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use native::NativeTransport;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::{
+        fs::File,
+        io::{self, Read},
+        net::{SocketAddr, ToSocketAddrs},
+        sync::{Arc, Mutex},
+    };
+
+    use bevy_utils::HashMap;
+    use ehttp::{Headers, Request, Response};
+
+    use super::{FileBody, HttpResolver, IpFamily};
+    use crate::error::TransportFailure;
+
+    /// How many bytes of a file body are sent between two progress reports.
+    const PROGRESS_STEP: u64 = 1 << 20;
+
+    /// Sends the requests ehttp cannot, e.g. through a proxy or with a custom resolver, with a
+    /// `ureq` agent per proxy so connections are reused.
+    #[derive(Clone, Default)]
+    pub(crate) struct NativeTransport {
+        /// The agents by proxy, `None` for direct connections.
+        agents: Arc<Mutex<HashMap<Option<String>, ureq::Agent>>>,
+        resolver: Option<Arc<dyn HttpResolver>>,
+        ip_family: IpFamily,
+    }
+
+    impl NativeTransport {
+        /// Whether the requests without a proxy need this transport rather than ehttp.
+        pub(crate) fn is_customized(&self) -> bool {
+            self.resolver.is_some() || self.ip_family != IpFamily::System
+        }
+
+        pub(crate) fn set_resolver(&mut self, resolver: impl HttpResolver) {
+            self.resolver = Some(Arc::new(resolver));
+            // the agents keep the resolver they were built with
+            self.agents = Arc::default();
+        }
+
+        pub(crate) fn set_ip_family(&mut self, ip_family: IpFamily) {
+            self.ip_family = ip_family;
+            self.agents = Arc::default();
+        }
+
+        /// Sends `request` through the proxy at `proxy` if any, like `ehttp::fetch_async`,
+        /// streaming `body_file` as its body if any.
+        pub(crate) async fn fetch(
+            &self,
+            request: Request,
+            proxy: Option<&str>,
+            body_file: Option<FileBody>,
+        ) -> ehttp::Result<Response> {
+            let agent = self.agent(proxy)?;
+            let (tx, rx) = async_channel::bounded(1);
+            std::thread::Builder::new()
+                .name("bevy_http_client".to_string())
+                .spawn(move || {
+                    let result = fetch_blocking(&agent, &request, body_file.as_ref());
+                    tx.send_blocking(result).ok();
+                })
+                .map_err(|e| e.to_string())?;
+            rx.recv().await.map_err(|e| e.to_string())?
+        }
+
+        /// The agent going through `proxy`, built on first use.
+        fn agent(&self, proxy: Option<&str>) -> ehttp::Result<ureq::Agent> {
+            let mut agents = self.agents.lock().unwrap_or_else(|e| e.into_inner());
+            let key = proxy.map(ToString::to_string);
+            if let Some(agent) = agents.get(&key) {
+                return Ok(agent.clone());
+            }
+            let mut builder = ureq::AgentBuilder::new();
+            if let Some(proxy) = proxy {
+                #[cfg(not(feature = "socks"))]
+                if proxy.starts_with("socks") {
+                    let proxy = without_credentials(proxy);
+                    return Err(format!("proxy {proxy:?} needs the `socks` feature"));
+                }
+                builder =
+                    builder.proxy(ureq::Proxy::new(proxy).map_err(|e| {
+                        format!("invalid proxy {:?}: {e}", without_credentials(proxy))
+                    })?);
+            }
+            if self.is_customized() {
+                let resolver = self.resolver.clone();
+                let ip_family = self.ip_family;
+                builder = builder.resolver(move |netloc: &str| {
+                    let addrs = resolve(resolver.as_deref(), netloc)?;
+                    Ok(order(addrs, ip_family))
+                });
+            }
+            let agent = builder.build();
+            agents.insert(key, agent.clone());
+            Ok(agent)
+        }
+    }
+
+    /// Resolves `netloc`, a host and a port, with `resolver`, falling back to the system resolver.
+    fn resolve(resolver: Option<&dyn HttpResolver>, netloc: &str) -> io::Result<Vec<SocketAddr>> {
+        let addrs = netloc.rsplit_once(':').and_then(|(host, port)| {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let port = port.parse().ok()?;
+            let ips = resolver?.resolve(host)?;
+            Some(
+                ips.into_iter()
+                    .map(|ip| SocketAddr::new(ip, port))
+                    .collect(),
+            )
+        });
+        match addrs {
+            Some(addrs) => Ok(addrs),
+            None => netloc.to_socket_addrs().map(Iterator::collect),
+        }
+    }
+
+    /// Orders `addrs` in the order they are tried for `ip_family`.
+    fn order(mut addrs: Vec<SocketAddr>, ip_family: IpFamily) -> Vec<SocketAddr> {
+        match ip_family {
+            IpFamily::System => addrs,
+            IpFamily::PreferIpv4 => {
+                addrs.sort_by_key(SocketAddr::is_ipv6);
+                addrs
+            }
+            IpFamily::PreferIpv6 => {
+                addrs.sort_by_key(SocketAddr::is_ipv4);
+                addrs
+            }
+            IpFamily::AlternatingFamilies => {
+                let (v6, v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(SocketAddr::is_ipv6);
+                if v6.is_empty() || v4.is_empty() {
+                    return [v6, v4].concat();
+                }
+                // the families alternate, starting with IPv6
+                let mut v4 = v4.into_iter();
+                let mut addrs: Vec<_> = v6
+                    .into_iter()
+                    .flat_map(|v6| [Some(v6), v4.next()])
+                    .flatten()
+                    .collect();
+                addrs.extend(v4);
+                addrs
+            }
+        }
+    }
+
+    /// `proxy` without its user and password, for error messages.
+    fn without_credentials(proxy: &str) -> String {
+        let (scheme, rest) = proxy.split_once("://").unwrap_or(("", proxy));
+        let host = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+        if scheme.is_empty() {
+            host.to_string()
+        } else {
+            format!("{scheme}://{host}")
+        }
+    }
+
+    /// Reads a file body, reporting the bytes read.
+    struct ProgressReader<'a> {
+        file: File,
+        body_file: &'a FileBody,
+        sent: u64,
+        reported: u64,
+        total: u64,
+    }
+
+    impl ProgressReader<'_> {
+        fn open(body_file: &FileBody) -> ehttp::Result<ProgressReader<'_>> {
+            let path = body_file.path.display();
+            let file =
+                File::open(&body_file.path).map_err(|e| format!("Failed to open {path}: {e}"))?;
+            let total = file
+                .metadata()
+                .map_err(|e| format!("Failed to read the size of {path}: {e}"))?
+                .len();
+            body_file.report(0, total);
+            Ok(ProgressReader {
+                file,
+                body_file,
+                sent: 0,
+                reported: 0,
+                total,
+            })
+        }
+    }
+
+    impl Read for ProgressReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.file.read(buf)?;
+            self.sent += read as u64;
+            if self.sent - self.reported >= PROGRESS_STEP || (read > 0 && self.sent >= self.total) {
+                self.reported = self.sent;
+                self.body_file.report(self.sent, self.total);
+            }
+            Ok(read)
+        }
+    }
+
+    /// Sends `request` with `agent`, with the body streamed from `body_file` if any, converting
+    /// the result the way ehttp does.
+    fn fetch_blocking(
+        agent: &ureq::Agent,
+        request: &Request,
+        body_file: Option<&FileBody>,
+    ) -> ehttp::Result<Response> {
+        let mut req = agent.request(&request.method, &request.url);
+        for (key, value) in &request.headers {
+            req = req.set(key, value);
+        }
+        let result = if let Some(body_file) = body_file {
+            let reader = ProgressReader::open(body_file)?;
+            req.set("Content-Length", &reader.total.to_string())
+                .send(reader)
+        } else if request.body.is_empty() {
+            req.call()
+        } else {
+            req.send_bytes(&request.body)
+        };
+        let (ok, response) = match result {
+            Ok(response) => (true, response),
+            // the body of an error status is still read
+            Err(ureq::Error::Status(_, response)) => (false, response),
+            Err(ureq::Error::Transport(e)) => return Err(transport_message(&e)),
+        };
+
+        let url = response.get_url().to_string();
+        let status = response.status();
+        let status_text = response.status_text().to_string();
+        let mut headers = Headers::default();
+        for key in response.headers_names() {
+            if let Some(value) = response.header(&key) {
+                headers.insert(key, value);
+            }
+        }
+        headers.sort();
+        let mut bytes = vec![];
+        if let Err(e) = response.into_reader().read_to_end(&mut bytes) {
+            // HEAD responses have no body to read
+            if request.method != "HEAD" || e.kind() != std::io::ErrorKind::UnexpectedEof {
+                return Err(format!("Failed to read response body: {e}"));
+            }
+        }
+        Ok(Response {
+            url,
+            ok,
+            status,
+            status_text,
+            headers,
+            bytes,
+        })
+    }
+
+    /// Flattens `e` into a message, tagged with the kind of failure its `ureq` kind and source
+    /// tell, see `HttpError::transport`.
+    fn transport_message(e: &ureq::Transport) -> String {
+        let io_kind = std::error::Error::source(e)
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .map(io::Error::kind);
+        let failure = match (e.kind(), io_kind) {
+            (ureq::ErrorKind::Dns, _) => TransportFailure::Dns,
+            (_, Some(io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock)) => {
+                TransportFailure::Timeout
+            }
+            (ureq::ErrorKind::ConnectionFailed, Some(io::ErrorKind::ConnectionRefused)) => {
+                TransportFailure::ConnectionRefused
+            }
+            // rustls fails the handshake with invalid data
+            (ureq::ErrorKind::ConnectionFailed, Some(io::ErrorKind::InvalidData)) => {
+                TransportFailure::Tls
+            }
+            _ => return e.to_string(),
+        };
+        failure.tagged(e)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::error::HttpError;
+
+        #[test]
+        fn alternating_families_start_with_ipv6() {
+            let addrs: Vec<SocketAddr> = ["1.1.1.1:80", "2.2.2.2:80", "3.3.3.3:80", "[::1]:80"]
+                .iter()
+                .map(|addr| addr.parse().unwrap())
+                .collect();
+            let ordered: Vec<String> = order(addrs, IpFamily::AlternatingFamilies)
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            assert_eq!(
+                ordered,
+                ["[::1]:80", "1.1.1.1:80", "2.2.2.2:80", "3.3.3.3:80"]
+            );
+        }
+
+        fn transport_error(transport: &NativeTransport, url: &str) -> HttpError {
+            let agent = transport.agent(None).unwrap();
+            let message = fetch_blocking(&agent, &Request::get(url), None).unwrap_err();
+            HttpError::transport(message)
+        }
+
+        #[test]
+        fn transport_errors_are_classified_from_their_kind() {
+            let mut transport = NativeTransport::default();
+            // no address, a lookup failure
+            transport.set_resolver(|_: &str| Some(Vec::new()));
+            let err = transport_error(&transport, "http://timeout.example.com/");
+            assert!(matches!(err, HttpError::Dns(_)), "{err:?}");
+
+            // a port nothing listens on
+            let port = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let transport = NativeTransport::default();
+            let err = transport_error(&transport, &format!("http://127.0.0.1:{port}/dns/tls"));
+            assert!(matches!(err, HttpError::ConnectionRefused(_)), "{err:?}");
+        }
+    }
+}
@@ -1,13 +1,26 @@
-use bevy_app::{App, PreUpdate};
-use bevy_derive::Deref;
-use bevy_ecs::{prelude::*, system::Commands, world::CommandQueue};
-use bevy_hierarchy::DespawnRecursiveExt;
-use bevy_tasks::IoTaskPool;
+use bevy_app::App;
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::{prelude::*, system::Commands};
+use bevy_utils::{Duration, HashMap, HashSet};
 use ehttp::{Request, Response};
-use serde::Deserialize;
-use std::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+use std::{any::TypeId, fmt, marker::PhantomData, ops::Range, path::PathBuf, sync::Arc};
 
-use crate::{HttpClientSetting, RequestTask};
+use crate::{
+    backoff::{HttpBackoffs, RetryPolicy},
+    dispatch::{dispatch_requests, Dispatch, DispatchRequest, ResponseRoute},
+    endpoint::EndpointInfo,
+    error::HttpError,
+    http_schedule,
+    lifecycle::RequestInfo,
+    not_modified::{deliver_not_modified, ConditionalValidators},
+    observe::{deliver, HttpObserved, Recipient},
+    precondition::ResponseETag,
+    redact::{DebugRedaction, RedactDebug},
+    send_status_class_event,
+    transport::RequestProxy,
+    HttpClient, HttpClientSet, RequestCoalescing, RequestDelay,
+};
 
 pub trait HttpTypedRequestTrait {
     /// Registers a new request type `T` to the application.
@@ -15,7 +28,7 @@ pub trait HttpTypedRequestTrait {
     /// This method is used to register a new request type `T` to the application. The request type
     /// `T` must implement the `Deserialize` trait, and be `Send` and `Sync`. This is necessary for
     /// the request type to be safely shared across threads and for it to be deserialized from a
-    /// HTTP response.
+    /// HTTP response. Registering the same type again has no effect.
     ///
     /// # Type Parameters
     ///
@@ -33,18 +46,220 @@ pub trait HttpTypedRequestTrait {
     fn register_request_type<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
         &mut self,
     ) -> &mut Self;
+
+    /// Registers a structured API error type `E` to the application.
+    ///
+    /// Error bodies of typed requests built with `with_types::<T, E>()` are deserialized into `E`
+    /// and sent as `TypedApiError<E>` events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_api_error_type::<MyApiError>();
+    /// ```
+    fn register_api_error_type<E: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+    ) -> &mut Self;
+
+    /// Registers a typed call with request body `Req` and response type `Resp`.
+    ///
+    /// This registers `TypedCall<Req, Resp>` events and the response type `Resp`, so the whole
+    /// endpoint contract is declared in one place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_call_type::<LoginRequest, LoginResponse>();
+    /// ```
+    fn register_call_type<Req, Resp>(&mut self) -> &mut Self
+    where
+        Req: Serialize + Send + Sync + 'static,
+        Resp: for<'a> Deserialize<'a> + Send + Sync + 'static;
+
+    /// Registers a validator that runs on every deserialized response of type `T`.
+    ///
+    /// Validators run in registration order after deserialization succeeded. The first one to
+    /// return `Err` turns the response into a `TypedResponseError<T>` with its message, so
+    /// semantically invalid data never reaches `TypedResponse<T>` readers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_response_validator::<Wallet>(|wallet| {
+    ///     if wallet.balance < 0 {
+    ///         return Err("negative balance".to_string());
+    ///     }
+    ///     Ok(())
+    /// });
+    /// ```
+    fn register_response_validator<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        validator: impl Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Registers the envelope that wraps every response body of type `T`.
+    ///
+    /// The payload is taken from the envelope's data field before deserializing into `T`, and the
+    /// meta field, if configured, is available with `TypedResponse::meta`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // { "data": { "name": "fox" }, "meta": { "request_id": "..." } }
+    /// app.register_response_envelope::<User>(ResponseEnvelope::new("data").with_meta("meta"));
+    /// ```
+    fn register_response_envelope<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        envelope: ResponseEnvelope,
+    ) -> &mut Self;
+
+    /// Registers the request type `T` and inserts every response of type `T` to a request made
+    /// `from_entity` as a component of that entity, replacing the previous one.
+    ///
+    /// The `TypedResponse<T>` event is still sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// app.register_request_type_as_component::<Profile>();
+    ///
+    /// fn show_profile(profiles: Query<&Profile, Changed<Profile>>) {
+    ///     for profile in profiles.iter() {
+    ///         println!("{}", profile.name);
+    ///     }
+    /// }
+    /// ```
+    fn register_request_type_as_component<T>(&mut self) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Component + Clone;
+
+    /// Like `register_request_type_as_component`, for a type `T` that is not a component: the
+    /// responses are inserted wrapped in `HttpData<T>`.
+    fn register_request_type_as_data<T>(&mut self) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + Clone + 'static;
 }
 
 impl HttpTypedRequestTrait for App {
     fn register_request_type<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
         &mut self,
     ) -> &mut Self {
+        // registering twice would dispatch every request twice
+        if self.world().contains_resource::<Events<TypedRequest<T>>>() {
+            return self;
+        }
         self.add_event::<TypedRequest<T>>();
         self.add_event::<TypedResponse<T>>();
         self.add_event::<TypedResponseError<T>>();
-        self.add_systems(PreUpdate, handle_typed_request::<T>);
+        let schedule = http_schedule(self);
+        self.add_systems(
+            schedule,
+            dispatch_requests::<TypedRequest<T>>.in_set(HttpClientSet::Dispatch),
+        );
+        self
+    }
+
+    fn register_api_error_type<E: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+    ) -> &mut Self {
+        self.add_event::<TypedApiError<E>>();
+        self.world_mut()
+            .get_resource_or_init::<ApiErrorTypes>()
+            .0
+            .insert(TypeId::of::<E>());
+        self
+    }
+
+    fn register_call_type<Req, Resp>(&mut self) -> &mut Self
+    where
+        Req: Serialize + Send + Sync + 'static,
+        Resp: for<'a> Deserialize<'a> + Send + Sync + 'static,
+    {
+        self.register_request_type::<Resp>();
+        self.add_event::<TypedCall<Req, Resp>>();
+        let schedule = http_schedule(self);
+        self.add_systems(
+            schedule,
+            handle_typed_call::<Req, Resp>
+                .before(dispatch_requests::<TypedRequest<Resp>>)
+                .in_set(HttpClientSet::Dispatch),
+        );
         self
     }
+
+    fn register_response_validator<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        validator: impl Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ResponseValidators<T>>()
+            .0
+            .push(Box::new(validator));
+        self
+    }
+
+    fn register_response_envelope<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+        &mut self,
+        envelope: ResponseEnvelope,
+    ) -> &mut Self {
+        let mut envelopes = self.world_mut().get_resource_or_init::<ResponseEnvelopes>();
+        Arc::make_mut(&mut envelopes.0).insert(TypeId::of::<T>(), envelope);
+        self
+    }
+
+    fn register_request_type_as_component<T>(&mut self) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Component + Clone,
+    {
+        self.register_request_type::<T>().add_observer(
+            |trigger: Trigger<HttpObserved<TypedResponse<T>>>, mut commands: Commands| {
+                let response = trigger.event();
+                commands
+                    .entity(response.entity())
+                    .insert(T::clone(response));
+            },
+        );
+        self
+    }
+
+    fn register_request_type_as_data<T>(&mut self) -> &mut Self
+    where
+        T: for<'a> Deserialize<'a> + Send + Sync + Clone + 'static,
+    {
+        self.register_request_type::<T>().add_observer(
+            |trigger: Trigger<HttpObserved<TypedResponse<T>>>, mut commands: Commands| {
+                let response = trigger.event();
+                commands
+                    .entity(response.entity())
+                    .insert(HttpData(T::clone(response)));
+            },
+        );
+        self
+    }
+}
+
+/// The latest response of type `T` to the requests made from an entity, see
+/// `HttpTypedRequestTrait::register_request_type_as_data`.
+#[derive(Component, Debug, Clone, Deref, DerefMut)]
+pub struct HttpData<T: Send + Sync + 'static>(pub T);
+
+/// A validator for deserialized responses of type `T`.
+type ResponseValidator<T> = Box<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
+/// Validators registered for the response type `T`.
+#[derive(Resource)]
+struct ResponseValidators<T>(Vec<ResponseValidator<T>>);
+
+impl<T> Default for ResponseValidators<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T> ResponseValidators<T> {
+    fn validate(&self, value: &T) -> Result<(), String> {
+        self.0.iter().try_for_each(|validator| validator(value))
+    }
 }
 
 /// A struct that represents a typed HTTP request.
@@ -69,21 +284,269 @@ impl HttpTypedRequestTrait for App {
 /// let request = Request::new();
 /// let typed_request = TypedRequest::new(request);
 /// ```
-#[derive(Debug, Event)]
+#[derive(Event)]
 pub struct TypedRequest<T>
 where
     T: for<'a> Deserialize<'a>,
 {
     pub from_entity: Option<Entity>,
     pub request: Request,
+    /// Route non-2xx responses to `TypedResponseError` without attempting deserialization.
+    pub error_for_status: bool,
+    /// Decodes error bodies into the API error type set with `with_error_type`.
+    api_error: Option<ApiErrorDecoder>,
+    /// The logical endpoint the request was built from, if any.
+    pub endpoint: Option<EndpointInfo>,
+    /// Time after which the request fails with a timeout error.
+    pub timeout: Option<Duration>,
+    /// Deliver the results only to the observers of `from_entity`, see
+    /// `HttpClient::observers_only`.
+    pub observers_only: bool,
+    /// The label of the client sending the request, see `HttpClient::on_client`.
+    pub client: Option<String>,
+    /// The label delivered with the results, see `HttpClient::label`.
+    pub label: Option<String>,
+    /// When the request is sent, see `HttpClient::send_after`.
+    pub delay: Option<RequestDelay>,
+    /// How the request is debounced or throttled, see `HttpClient::debounce`.
+    pub coalescing: Option<RequestCoalescing>,
+    /// The URLs tried in order after a network error, see `HttpClient::fallback_urls`.
+    pub fallback_urls: Vec<String>,
+    /// How the request is retried after a network error, see `HttpClient::retry`.
+    pub retry: Option<RetryPolicy>,
+    /// The proxy of the request instead of the one of its client, see `HttpClient::proxy`.
+    pub proxy: Option<RequestProxy>,
+    /// The file streamed as the body, see `HttpClient::body_from_file`.
+    pub body_file: Option<PathBuf>,
     inner: PhantomData<T>,
 }
 
+impl<T: for<'a> Deserialize<'a>> RedactDebug for TypedRequest<T> {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>, redaction: &DebugRedaction) -> fmt::Result {
+        f.debug_struct("TypedRequest")
+            .field("from_entity", &self.from_entity)
+            .field("request", &redaction.request(&self.request))
+            .field("error_for_status", &self.error_for_status)
+            .field("api_error", &self.api_error.is_some())
+            .field("endpoint", &self.endpoint)
+            .field("timeout", &self.timeout)
+            .field("observers_only", &self.observers_only)
+            .field("client", &self.client)
+            .field("label", &self.label)
+            .field("delay", &self.delay)
+            .field("coalescing", &self.coalescing)
+            .field("fallback_urls", &self.fallback_urls)
+            .field("retry", &self.retry)
+            .field("proxy", &self.proxy)
+            .field("body_file", &self.body_file)
+            .finish()
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> fmt::Debug for TypedRequest<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_redacted(f, &DebugRedaction::default())
+    }
+}
+
+impl<T: for<'a> Deserialize<'a>> Clone for TypedRequest<T> {
+    fn clone(&self) -> Self {
+        Self {
+            from_entity: self.from_entity,
+            request: self.request.clone(),
+            error_for_status: self.error_for_status,
+            api_error: self.api_error,
+            endpoint: self.endpoint,
+            timeout: self.timeout,
+            observers_only: self.observers_only,
+            client: self.client.clone(),
+            label: self.label.clone(),
+            delay: self.delay,
+            coalescing: self.coalescing,
+            fallback_urls: self.fallback_urls.clone(),
+            retry: self.retry.clone(),
+            proxy: self.proxy.clone(),
+            body_file: self.body_file.clone(),
+            inner: PhantomData,
+        }
+    }
+}
+
+/// Tries to deliver an error response as a `TypedApiError` with its request id and label, giving
+/// the response back on failure.
+type ApiErrorDecoder = fn(
+    &mut World,
+    Recipient,
+    Arc<Response>,
+    &Option<String>,
+    &Option<String>,
+) -> Result<(), Arc<Response>>;
+
+/// The API error types registered with `register_api_error_type`, checked by
+/// `HttpClient::try_with_types`.
+#[derive(Resource, Debug, Default)]
+pub struct ApiErrorTypes(HashSet<TypeId>);
+
+impl ApiErrorTypes {
+    /// Whether `E` is registered.
+    pub fn contains<E: 'static>(&self) -> bool {
+        self.0.contains(&TypeId::of::<E>())
+    }
+}
+
 impl<T: for<'a> serde::Deserialize<'a>> TypedRequest<T> {
     pub fn new(request: Request, from_entity: Option<Entity>) -> Self {
         TypedRequest {
             from_entity,
             request,
+            error_for_status: true,
+            api_error: None,
+            endpoint: None,
+            timeout: None,
+            observers_only: false,
+            client: None,
+            label: None,
+            delay: None,
+            coalescing: None,
+            fallback_urls: Vec::new(),
+            retry: None,
+            proxy: None,
+            body_file: None,
+            inner: PhantomData,
+        }
+    }
+
+    /// Sets the structured API error type `E` for non-2xx response bodies.
+    ///
+    /// Error bodies that deserialize into `E` are sent as `TypedApiError<E>` events, any other
+    /// error body still ends up in `TypedResponseError<T>`. The type `E` must be registered with
+    /// `register_api_error_type`, error bodies of an unregistered type are sent as
+    /// `TypedResponseError<T>` too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("http://example.com")
+    ///     .with_type::<MyResponseType>()
+    ///     .with_error_type::<MyApiError>();
+    /// ```
+    pub fn with_error_type<E: for<'a> Deserialize<'a> + Send + Sync + 'static>(mut self) -> Self {
+        self.error_for_status = true;
+        self.api_error = Some(send_api_error::<E>);
+        self
+    }
+
+    /// Sets whether non-2xx responses are treated as errors.
+    ///
+    /// When enabled (the default), a response whose status is not 2xx is sent as a
+    /// `TypedResponseError` carrying the status and the raw response, and the body is never
+    /// deserialized. Disable it for APIs that return a meaningful `T` with an error status.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let request = HttpClient::new()
+    ///     .get("http://example.com")
+    ///     .with_type::<MyResponseType>()
+    ///     .error_for_status(false);
+    /// ```
+    pub fn error_for_status(mut self, enabled: bool) -> Self {
+        self.error_for_status = enabled;
+        self
+    }
+}
+
+/// Describes the envelope object that wraps API payloads, e.g. `{ "data": ..., "meta": ... }`.
+#[derive(Debug, Clone)]
+pub struct ResponseEnvelope {
+    /// Name of the field holding the payload.
+    pub data: String,
+    /// Name of the field holding metadata, if any.
+    pub meta: Option<String>,
+}
+
+impl ResponseEnvelope {
+    pub fn new(data: impl ToString) -> Self {
+        Self {
+            data: data.to_string(),
+            meta: None,
+        }
+    }
+
+    /// Sets the field whose value is surfaced as `TypedResponse::meta`.
+    pub fn with_meta(mut self, meta: impl ToString) -> Self {
+        self.meta = Some(meta.to_string());
+        self
+    }
+}
+
+/// The envelopes registered with `register_response_envelope`, by response type, shared with the
+/// tasks deserializing the responses.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct ResponseEnvelopes(Arc<HashMap<TypeId, ResponseEnvelope>>);
+
+/// A typed request for arbitrary JSON, registered by `HttpClientPlugin`.
+///
+/// # Examples
+///
+/// ```
+/// fn send_request(mut ev_request: EventWriter<DynamicJsonRequest>) {
+///     ev_request.send(
+///         HttpClient::new()
+///             .get("https://api.ipify.org?format=json")
+///             .with_type::<serde_json::Value>(),
+///     );
+/// }
+///
+/// fn handle_response(mut ev_response: EventReader<DynamicJsonResponse>) {
+///     for response in ev_response.read() {
+///         println!("ip: {}", response["ip"]);
+///     }
+/// }
+/// ```
+pub type DynamicJsonRequest = TypedRequest<serde_json::Value>;
+
+/// The response of a `DynamicJsonRequest`.
+pub type DynamicJsonResponse = TypedResponse<serde_json::Value>;
+
+/// The error of a `DynamicJsonRequest`.
+pub type DynamicJsonResponseError = TypedResponseError<serde_json::Value>;
+
+/// A typed HTTP call with a request body of type `Req` and a response of type `Resp`.
+///
+/// The body is serialized to JSON when the call is dispatched, and the response goes through the
+/// same pipeline as `TypedRequest<Resp>`, so results arrive as `TypedResponse<Resp>` or
+/// `TypedResponseError<Resp>` events.
+///
+/// # Examples
+///
+/// ```
+/// app.register_call_type::<LoginRequest, LoginResponse>();
+///
+/// fn login(mut ev_call: EventWriter<TypedCall<LoginRequest, LoginResponse>>) {
+///     ev_call.send(
+///         HttpClient::new()
+///             .post("http://example.com/login")
+///             .call(LoginRequest::default()),
+///     );
+/// }
+/// ```
+#[derive(Debug, Event)]
+pub struct TypedCall<Req, Resp>
+where
+    Resp: for<'a> Deserialize<'a>,
+{
+    pub client: HttpClient,
+    pub body: Req,
+    inner: PhantomData<Resp>,
+}
+
+impl<Req, Resp: for<'a> Deserialize<'a>> TypedCall<Req, Resp> {
+    pub fn new(client: HttpClient, body: Req) -> Self {
+        TypedCall {
+            client,
+            body,
             inner: PhantomData,
         }
     }
@@ -116,20 +579,118 @@ where
 {
     #[deref]
     inner: T,
+    meta: Option<serde_json::Value>,
+    request_id: Option<String>,
+    label: Option<String>,
+    duration: Duration,
+    pub(crate) etag: Option<String>,
 }
 
 impl<T: for<'a> serde::Deserialize<'a>> TypedResponse<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Self {
+            inner,
+            meta: None,
+            etag: None,
+            request_id: None,
+            label: None,
+            duration: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub(crate) fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    pub(crate) fn with_label(mut self, label: Option<String>) -> Self {
+        self.label = label;
+        self
+    }
+
     /// Consumes the HTTP response and returns the inner data.
     pub fn into_inner(self) -> T {
         self.inner
     }
+
+    /// The envelope metadata, if a `ResponseEnvelope` with a meta field is registered for `T`.
+    pub fn meta(&self) -> Option<&serde_json::Value> {
+        self.meta.as_ref()
+    }
+
+    /// The correlation ID of the request, see `HttpClientSetting::with_request_id_header`.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// The label of the request, see `HttpClient::label`.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The time from starting the request to receiving the response, retries included.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// What made a typed request fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedErrorKind {
+    /// The request failed without a response, e.g. with a network error or a timeout.
+    Network(HttpError),
+    /// The response has an error status, see `status`.
+    Status,
+    /// The body did not deserialize into the requested type, see `path` and `byte_range`.
+    Decode,
+    /// The deserialized body was rejected by a response validator.
+    Validation,
+    /// The response is 412 Precondition Failed: the resource changed since the ETag sent with
+    /// `HttpClient::if_match_from` was read.
+    PreconditionFailed,
+    /// Any other failure, e.g. a request body that failed to serialize.
+    Other,
 }
 
+/// The failure of a typed request.
+///
+/// # Examples
+///
+/// ```
+/// fn handle_error(mut ev_error: EventReader<TypedResponseError<Profile>>) {
+///     for error in ev_error.read() {
+///         match &error.kind {
+///             TypedErrorKind::Network(_) => retry_later(),
+///             TypedErrorKind::Decode => report_bug(&error.err, error.byte_range.clone()),
+///             _ => println!("request failed: {}", error.err),
+///         }
+///     }
+/// }
+/// ```
 #[derive(Event, Debug, Clone, Deref)]
 pub struct TypedResponseError<T> {
     #[deref]
     pub err: String,
-    pub response: Option<Response>,
+    pub kind: TypedErrorKind,
+    /// The response, shared with the status-class event sent along with it.
+    pub response: Option<Arc<Response>>,
+    /// The status of the response, if any.
+    pub status: Option<u16>,
+    /// JSON path to the field that failed to deserialize, e.g. `data.items[3].price`.
+    pub path: Option<String>,
+    /// The part of the body around the position where deserialization failed.
+    pub byte_range: Option<Range<usize>>,
+    /// The correlation ID of the request, see `HttpClientSetting::with_request_id_header`.
+    pub request_id: Option<String>,
+    /// The label of the request, see `HttpClient::label`.
+    pub label: Option<String>,
+    /// The time from starting the request to its failure, if it was started.
+    pub duration: Option<Duration>,
     phantom: PhantomData<T>,
 }
 
@@ -137,86 +698,533 @@ impl<T> TypedResponseError<T> {
     pub fn new(err: String) -> Self {
         Self {
             err,
+            kind: TypedErrorKind::Other,
             response: None,
+            status: None,
+            path: None,
+            byte_range: None,
+            request_id: None,
+            label: None,
+            duration: None,
             phantom: Default::default(),
         }
     }
 
-    pub fn response(mut self, response: Response) -> Self {
+    /// An error for a request that failed without a response.
+    pub fn network(err: HttpError) -> Self {
+        Self::new(err.to_string()).kind(TypedErrorKind::Network(err))
+    }
+
+    pub fn kind(mut self, kind: TypedErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn path(mut self, path: String) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn byte_range(mut self, byte_range: Option<Range<usize>>) -> Self {
+        self.byte_range = byte_range;
+        self
+    }
+
+    /// Sets the response, and the status from it.
+    pub fn response(mut self, response: impl Into<Arc<Response>>) -> Self {
+        let response = response.into();
+        self.status = Some(response.status);
         self.response = Some(response);
         self
     }
+
+    pub fn request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    pub fn label(mut self, label: Option<String>) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+}
+
+/// A structured error body returned by the server for a non-2xx response.
+///
+/// Sent for typed requests built with `with_types::<T, E>()` when the error body deserializes
+/// into `E`.
+///
+/// # Examples
+///
+/// ```
+/// fn handle_api_error(mut ev_error: EventReader<TypedApiError<MyApiError>>) {
+///     for error in ev_error.read() {
+///         println!("{}: {}", error.response.status, error.message);
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone, Deref)]
+pub struct TypedApiError<E> {
+    #[deref]
+    pub inner: E,
+    /// The response, shared with the status-class event sent along with it.
+    pub response: Arc<Response>,
+    /// The id of the request, see `HttpClientSetting::with_request_id_header`.
+    pub request_id: Option<String>,
+    /// The label of the request, see `HttpClient::label`.
+    pub label: Option<String>,
+}
+
+impl<E> TypedApiError<E> {
+    /// Consumes the error and returns the deserialized error body.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+fn send_api_error<E: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+    world: &mut World,
+    recipient: Recipient,
+    response: Arc<Response>,
+    request_id: &Option<String>,
+    label: &Option<String>,
+) -> Result<(), Arc<Response>> {
+    // an unregistered error type falls back to the untyped error
+    if !world.contains_resource::<Events<TypedApiError<E>>>() {
+        return Err(response);
+    }
+    match serde_json::from_slice::<E>(response.bytes.as_slice()) {
+        Ok(inner) => {
+            deliver(
+                world,
+                recipient,
+                TypedApiError {
+                    inner,
+                    response,
+                    request_id: request_id.clone(),
+                    label: label.clone(),
+                },
+            );
+            Ok(())
+        }
+        Err(_) => Err(response),
+    }
 }
 
-/// A system that handles typed HTTP requests.
-fn handle_typed_request<T: for<'a> Deserialize<'a> + Send + Sync + 'static>(
+/// A system that serializes typed calls and forwards them as typed requests.
+fn handle_typed_call<Req, Resp>(
     mut commands: Commands,
-    mut req_res: ResMut<HttpClientSetting>,
-    mut requests: EventReader<TypedRequest<T>>,
-) {
-    let thread_pool = IoTaskPool::get();
-    for request in requests.read() {
-        if req_res.is_available() {
-            let (entity, has_from_entity) = if let Some(entity) = request.from_entity {
-                (entity, true)
-            } else {
-                (commands.spawn_empty().id(), false)
-            };
-            let req = request.request.clone();
-            let (tx, rx) = crossbeam_channel::bounded(1);
-
-            thread_pool
-                .spawn(async move {
-                    let mut command_queue = CommandQueue::default();
-
-                    let response = ehttp::fetch_async(req).await;
-                    command_queue.push(move |world: &mut World| {
-                        match response {
-                            Ok(response) => {
-                                let result: Result<T, _> =
-                                    serde_json::from_slice(response.bytes.as_slice());
-
-                                match result {
-                                    // deserialize success, send response
-                                    Ok(inner) => {
-                                        world
-                                            .get_resource_mut::<Events<TypedResponse<T>>>()
-                                            .unwrap()
-                                            .send(TypedResponse { inner });
-                                    }
-                                    // deserialize error, send error + response
-                                    Err(e) => {
-                                        world
-                                            .get_resource_mut::<Events<TypedResponseError<T>>>()
-                                            .unwrap()
-                                            .send(
-                                                TypedResponseError::new(e.to_string())
-                                                    .response(response),
-                                            );
-                                    }
-                                }
+    mut calls: EventReader<TypedCall<Req, Resp>>,
+    mut requests: EventWriter<TypedRequest<Resp>>,
+) where
+    Req: Serialize + Send + Sync + 'static,
+    Resp: for<'a> Deserialize<'a> + Send + Sync + 'static,
+{
+    for call in calls.read() {
+        match serde_json::to_vec(&call.body) {
+            Ok(body) => {
+                requests.send(call.client.clone().json_bytes(body).with_type::<Resp>());
+            }
+            Err(e) => {
+                // delivered like the other failures, to the entity and with the label of the call
+                let recipient = Recipient {
+                    entity: call.client.from_entity,
+                    observers_only: call.client.observers_only,
+                };
+                let error =
+                    TypedResponseError::<Resp>::new(e.to_string()).label(call.client.label.clone());
+                commands.queue(move |world: &mut World| deliver(world, recipient, error));
+            }
+        }
+    }
+}
+
+impl<T: for<'a> Deserialize<'a> + Send + Sync + 'static> DispatchRequest for TypedRequest<T> {
+    type Route = TypedResponseRoute<T>;
+
+    fn client(&self) -> Option<&str> {
+        self.client.as_deref()
+    }
+
+    fn take_delay(&mut self) -> Option<RequestDelay> {
+        self.delay.take()
+    }
+
+    fn take_coalescing(&mut self) -> Option<RequestCoalescing> {
+        self.coalescing.take()
+    }
+
+    fn retry(&self, backoffs: &HttpBackoffs) -> Option<Self> {
+        // the request and its body are only cloned when it has a retry left
+        let mut policy = self.retry.clone()?;
+        let delay = policy.next_retry(backoffs)?;
+        let mut retry = self.clone();
+        retry.retry = Some(policy);
+        retry.delay = Some(RequestDelay::After(delay));
+        Some(retry)
+    }
+
+    fn info(&self) -> RequestInfo {
+        RequestInfo {
+            method: self.request.method.clone(),
+            url: self.request.url.clone(),
+            from_entity: self.from_entity,
+            label: self.label.clone(),
+        }
+    }
+
+    fn into_dispatch(self) -> (Dispatch, TypedResponseRoute<T>) {
+        let dispatch = Dispatch {
+            request: self.request,
+            from_entity: self.from_entity,
+            timeout: self.timeout,
+            observers_only: self.observers_only,
+            endpoint: self.endpoint,
+            client: self.client,
+            fallback_urls: self.fallback_urls,
+            proxy: self.proxy,
+            body_file: self.body_file,
+        };
+        let route = TypedResponseRoute {
+            validators: ConditionalValidators::of(&dispatch.request),
+            error_for_status: self.error_for_status,
+            api_error: self.api_error,
+            label: self.label,
+            decoded: None,
+        };
+        (dispatch, route)
+    }
+}
+
+/// Deserializes responses into `TypedResponse<T>`, or delivers a `TypedResponseError<T>`.
+pub(crate) struct TypedResponseRoute<T> {
+    /// The validators of a conditional request, whose 304 is sent as `HttpNotModified`.
+    validators: Option<ConditionalValidators>,
+    error_for_status: bool,
+    api_error: Option<ApiErrorDecoder>,
+    label: Option<String>,
+    /// The body deserialized by `decode`, off the main thread.
+    decoded: Option<Result<(T, Option<serde_json::Value>), DecodeError>>,
+}
+
+impl<T> Clone for TypedResponseRoute<T> {
+    /// Clones the route without its decoded body.
+    fn clone(&self) -> Self {
+        Self {
+            validators: self.validators.clone(),
+            error_for_status: self.error_for_status,
+            api_error: self.api_error,
+            label: self.label.clone(),
+            decoded: None,
+        }
+    }
+}
+
+impl<T: for<'a> Deserialize<'a> + Send + Sync + 'static> ResponseRoute for TypedResponseRoute<T> {
+    fn decode(&mut self, result: &Result<Response, HttpError>, envelopes: &ResponseEnvelopes) {
+        // the error statuses are not deserialized
+        let Ok(response) = result else {
+            return;
+        };
+        if response.status == 304 || (self.error_for_status && !response.ok) {
+            return;
+        }
+        self.decoded = Some(decode_typed(response.bytes.as_slice(), envelopes));
+    }
+
+    fn deliver(
+        self,
+        world: &mut World,
+        recipient: Recipient,
+        result: Result<Response, HttpError>,
+        request_id: Option<String>,
+        duration: Duration,
+    ) {
+        let result = match result {
+            Ok(response) => match deliver_not_modified(
+                world,
+                recipient,
+                self.validators.as_ref(),
+                response,
+                &self.label,
+                duration,
+            ) {
+                Ok(()) => return,
+                Err(response) => Ok(response),
+            },
+            Err(e) => Err(e),
+        };
+        // shared by the status-class event and the typed event
+        let result = result.map(Arc::new);
+        // only redirects and error statuses have a status-class event
+        match &result {
+            Ok(response) if recipient.broadcast() && (300..=599).contains(&response.status) => {
+                send_status_class_event(world, response);
+            }
+            _ => {}
+        }
+        match result {
+            // error status, send error + response without deserializing
+            Ok(response) if self.error_for_status && response.status == 412 => {
+                deliver(
+                    world,
+                    recipient,
+                    TypedResponseError::<T>::new(format!(
+                        "HTTP status 412 {}",
+                        response.status_text
+                    ))
+                    .kind(TypedErrorKind::PreconditionFailed)
+                    .response(response)
+                    .request_id(request_id)
+                    .label(self.label)
+                    .duration(duration),
+                );
+            }
+            Ok(response) if self.error_for_status && !response.ok => {
+                // structured api error first, plain status error otherwise
+                let sent = match self.api_error {
+                    Some(send_api_error) => {
+                        send_api_error(world, recipient, response, &request_id, &self.label)
+                    }
+                    None => Err(response),
+                };
+                if let Err(response) = sent {
+                    deliver(
+                        world,
+                        recipient,
+                        TypedResponseError::<T>::new(format!(
+                            "HTTP status {} {}",
+                            response.status, response.status_text
+                        ))
+                        .kind(TypedErrorKind::Status)
+                        .response(response)
+                        .request_id(request_id)
+                        .label(self.label)
+                        .duration(duration),
+                    );
+                }
+            }
+            Ok(response) => {
+                let result = match self.decoded {
+                    Some(decoded) => decoded,
+                    None => decode_typed(
+                        response.bytes.as_slice(),
+                        &world.get_resource_or_init::<ResponseEnvelopes>(),
+                    ),
+                };
+
+                match result {
+                    // deserialize success, validate and send response
+                    Ok((inner, meta)) => {
+                        let validation = world
+                            .get_resource::<ResponseValidators<T>>()
+                            .map_or(Ok(()), |validators| validators.validate(&inner));
+                        match validation {
+                            Ok(()) => {
+                                deliver(
+                                    world,
+                                    recipient,
+                                    TypedResponse {
+                                        inner,
+                                        meta,
+                                        etag: response.etag().map(ToString::to_string),
+                                        request_id,
+                                        label: self.label,
+                                        duration,
+                                    },
+                                );
                             }
                             Err(e) => {
-                                world
-                                    .get_resource_mut::<Events<TypedResponseError<T>>>()
-                                    .unwrap()
-                                    .send(TypedResponseError::new(e.to_string()));
+                                deliver(
+                                    world,
+                                    recipient,
+                                    TypedResponseError::<T>::new(format!("validation failed: {e}"))
+                                        .kind(TypedErrorKind::Validation)
+                                        .response(response)
+                                        .request_id(request_id)
+                                        .label(self.label)
+                                        .duration(duration),
+                                );
                             }
                         }
+                    }
+                    // deserialize error, send error + response
+                    Err(e) => {
+                        deliver(
+                            world,
+                            recipient,
+                            e.into_error::<T>()
+                                .response(response)
+                                .request_id(request_id)
+                                .label(self.label)
+                                .duration(duration),
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                deliver(
+                    world,
+                    recipient,
+                    TypedResponseError::<T>::network(e)
+                        .request_id(request_id)
+                        .label(self.label)
+                        .duration(duration),
+                );
+            }
+        }
+    }
+}
 
-                        if has_from_entity {
-                            world.entity_mut(entity).remove::<RequestTask>();
-                        } else {
-                            world.entity_mut(entity).despawn_recursive();
-                        }
-                    });
+/// A JSON deserialization failure with the path to the failing field.
+pub(crate) struct DecodeError {
+    pub(crate) message: String,
+    pub(crate) path: String,
+    /// The part of the body around the failure, if known.
+    pub(crate) byte_range: Option<Range<usize>>,
+}
 
-                    tx.send(command_queue).unwrap()
-                })
-                .detach();
+impl DecodeError {
+    /// The typed error reporting this failure.
+    pub(crate) fn into_error<T>(self) -> TypedResponseError<T> {
+        TypedResponseError::new(self.message)
+            .kind(TypedErrorKind::Decode)
+            .path(self.path)
+            .byte_range(self.byte_range)
+    }
+}
+
+/// Deserializes a JSON body, reporting the path to the failing field and a snippet of the body
+/// around the error position.
+pub(crate) fn from_json_slice<T: for<'a> Deserialize<'a>>(bytes: &[u8]) -> Result<T, DecodeError> {
+    let report = |path: String, e: serde_json::Error| {
+        let byte_range = json_snippet_range(bytes, e.line(), e.column());
+        let snippet = String::from_utf8_lossy(&bytes[byte_range.clone()]);
+        DecodeError {
+            message: format!("{e} (at `{path}`, near `{snippet}`)"),
+            path,
+            byte_range: Some(byte_range),
+        }
+    };
 
-            commands.entity(entity).insert(RequestTask(rx));
-            req_res.current_clients += 1;
+    let de = &mut serde_json::Deserializer::from_slice(bytes);
+    match serde_path_to_error::deserialize(&mut *de) {
+        Ok(value) => de
+            .end()
+            .map(|_| value)
+            .map_err(|e| report(".".to_string(), e)),
+        Err(e) => {
+            let path = e.path().to_string();
+            Err(report(path, e.into_inner()))
         }
     }
 }
+
+/// Deserializes a typed body, unwrapping the envelope registered for `T` if any.
+fn decode_typed<T: for<'a> Deserialize<'a> + 'static>(
+    bytes: &[u8],
+    envelopes: &ResponseEnvelopes,
+) -> Result<(T, Option<serde_json::Value>), DecodeError> {
+    match envelopes.0.get(&TypeId::of::<T>()) {
+        Some(envelope) => from_enveloped_json(bytes, envelope),
+        None => from_json_slice(bytes).map(|inner| (inner, None)),
+    }
+}
+
+/// Deserializes the payload of an enveloped JSON body, returning it with the envelope metadata.
+fn from_enveloped_json<T: for<'a> Deserialize<'a>>(
+    bytes: &[u8],
+    envelope: &ResponseEnvelope,
+) -> Result<(T, Option<serde_json::Value>), DecodeError> {
+    let serde_json::Value::Object(mut object) = from_json_slice(bytes)? else {
+        return Err(DecodeError {
+            message: "expected an envelope object".to_string(),
+            path: ".".to_string(),
+            byte_range: None,
+        });
+    };
+    let data = object.remove(&envelope.data).ok_or_else(|| DecodeError {
+        message: format!("missing envelope field `{}`", envelope.data),
+        path: ".".to_string(),
+        byte_range: None,
+    })?;
+    let meta = envelope.meta.as_ref().and_then(|meta| object.remove(meta));
+
+    serde_path_to_error::deserialize(data)
+        .map(|inner| (inner, meta))
+        .map_err(|e| {
+            let path = match e.path().to_string().as_str() {
+                "." => envelope.data.clone(),
+                path => format!("{}.{}", envelope.data, path),
+            };
+            DecodeError {
+                message: format!("{} (at `{path}`)", e.inner()),
+                path,
+                byte_range: None,
+            }
+        })
+}
+
+/// Returns the range of `bytes` around the 1-based `line` and `column` reported by serde_json.
+fn json_snippet_range(bytes: &[u8], line: usize, column: usize) -> Range<usize> {
+    const CONTEXT: usize = 24;
+
+    let line_start: usize = bytes
+        .split(|b| *b == b'\n')
+        .take(line.saturating_sub(1))
+        .map(|l| l.len() + 1)
+        .sum();
+    let offset = (line_start + column.saturating_sub(1)).min(bytes.len());
+    let start = offset.saturating_sub(CONTEXT);
+    let end = (offset + CONTEXT).min(bytes.len());
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::harness::HttpTestHarness;
+
+    /// A body serde_json cannot serialize, its keys are not strings.
+    type BadBody = BTreeMap<(u8, u8), u8>;
+
+    #[derive(Resource, Default)]
+    struct Observed(usize);
+
+    #[test]
+    fn unserializable_call_body_fails_with_the_call_label_and_entity() {
+        let mut harness = HttpTestHarness::new();
+        harness
+            .app
+            .register_call_type::<BadBody, serde_json::Value>()
+            .init_resource::<Observed>();
+        let entity = harness
+            .app
+            .world_mut()
+            .spawn_empty()
+            .observe(
+                |_: Trigger<HttpObserved<TypedResponseError<serde_json::Value>>>,
+                 mut observed: ResMut<Observed>| observed.0 += 1,
+            )
+            .id();
+        harness.send(
+            HttpClient::new()
+                .post("https://example.com/saves")
+                .entity(entity)
+                .label("save")
+                .call::<_, serde_json::Value>(BadBody::from([((1, 2), 3)])),
+        );
+        let errors = harness.drain_events::<TypedResponseError<serde_json::Value>>();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].label.as_deref(), Some("save"));
+        assert_eq!(harness.app.world().resource::<Observed>().0, 1);
+        assert!(harness.sent_requests().is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use bevy_ecs::prelude::*;
+
+use crate::middleware::HttpMiddleware;
+
+/// The Bevy version the crate is built against, kept in sync with `Cargo.toml`.
+const BEVY_VERSION: &str = "0.15";
+
+/// The `User-Agent` header sent with every request that does not set one.
+///
+/// The header names the app, when set, then the client, the engine and the OS, e.g.
+/// `MyGame/1.2 (bevy_http_client/0.7.0; bevy/0.15; windows)`. A request can override it with its
+/// own `User-Agent` header.
+///
+/// Browsers do not let a page set the header, so it is not sent on wasm.
+///
+/// # Examples
+///
+/// ```
+/// app.insert_resource(HttpUserAgent::new("MyGame", "1.2"));
+///
+/// // overridden for one request
+/// let request = HttpClient::new()
+///     .get("https://example.com")
+///     .header("User-Agent", "MyGame-Launcher/3.0")
+///     .build();
+/// ```
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HttpUserAgent {
+    /// name of the app, e.g. "MyGame"
+    pub app_name: Option<String>,
+    /// version of the app, e.g. "1.2", sent with the app name only
+    pub app_version: Option<String>,
+    /// send no default `User-Agent` header at all
+    pub disabled: bool,
+}
+
+impl HttpUserAgent {
+    /// A user agent naming the app `app_name` at version `app_version`.
+    pub fn new(app_name: impl ToString, app_version: impl ToString) -> Self {
+        Self {
+            app_name: Some(app_name.to_string()),
+            app_version: Some(app_version.to_string()),
+            disabled: false,
+        }
+    }
+
+    /// A user agent sending no default header, leaving it to the platform.
+    pub fn disabled() -> Self {
+        Self {
+            disabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// The value of the header, `None` if disabled.
+    pub fn header_value(&self) -> Option<String> {
+        if self.disabled {
+            return None;
+        }
+        let client = format!("bevy_http_client/{}", env!("CARGO_PKG_VERSION"));
+        let platform = format!("bevy/{BEVY_VERSION}; {}", std::env::consts::OS);
+        Some(match (&self.app_name, &self.app_version) {
+            (Some(name), Some(version)) => format!("{name}/{version} ({client}; {platform})"),
+            (Some(name), None) => format!("{name} ({client}; {platform})"),
+            (None, _) => format!("{client} ({platform})"),
+        })
+    }
+}
+
+/// Hands the header of the `HttpUserAgent` over to the middleware sending the requests.
+pub(crate) fn sync_user_agent(agent: Res<HttpUserAgent>, mut middleware: ResMut<HttpMiddleware>) {
+    middleware.user_agent = if cfg!(target_arch = "wasm32") {
+        None
+    } else {
+        agent.header_value()
+    };
+}
@@ -0,0 +1,144 @@
+use std::{
+    ops::ControlFlow,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use bevy_utils::Duration;
+use ehttp::{Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    middleware::{RequestMiddleware, ResponseMiddleware},
+    persist::{RecordedBody, RecordedResponse},
+};
+
+/// Record-and-replay middleware for deterministic tests.
+///
+/// In record mode, real responses are written to a fixture file as they arrive. In replay mode,
+/// the recorded responses are served without network access, keyed by method, URL and body. A
+/// request recorded several times is answered with its responses in order, the last one being
+/// repeated. A request without recording fails with a network error.
+///
+/// Register the same `Vcr` as request and response middleware.
+///
+/// # Examples
+///
+/// ```
+/// let vcr = if std::env::var("RECORD").is_ok() {
+///     Vcr::record("tests/fixtures/login.json")
+/// } else {
+///     Vcr::replay("tests/fixtures/login.json").unwrap()
+/// };
+/// app.add_request_middleware(vcr.clone())
+///     .add_response_middleware(vcr);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Vcr {
+    mode: VcrMode,
+    cassette: Arc<Mutex<Vec<Interaction>>>,
+}
+
+#[derive(Debug, Clone)]
+enum VcrMode {
+    Record(PathBuf),
+    Replay,
+}
+
+impl Vcr {
+    /// Records the responses to the fixture file `path`, replacing its contents.
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            mode: VcrMode::Record(path.into()),
+            cassette: Default::default(),
+        }
+    }
+
+    /// Replays the responses recorded in the fixture file `path`.
+    pub fn replay(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path.into())?;
+        Self::replay_from_str(&json).map_err(std::io::Error::other)
+    }
+
+    /// Replays the responses recorded in the fixture `json`, e.g. from `include_str!`.
+    pub fn replay_from_str(json: &str) -> serde_json::Result<Self> {
+        Ok(Self {
+            mode: VcrMode::Replay,
+            cassette: Arc::new(Mutex::new(serde_json::from_str(json)?)),
+        })
+    }
+
+    /// Writes the recorded interactions to the fixture file.
+    fn save(&self, path: &PathBuf, cassette: &[Interaction]) {
+        let written = serde_json::to_string_pretty(cassette)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = written {
+            bevy_utils::tracing::error!("failed to write HTTP fixture {}: {e}", path.display());
+        }
+    }
+}
+
+impl RequestMiddleware for Vcr {
+    fn handle_request(&self, request: &mut Request) -> ControlFlow<ehttp::Result<Response>> {
+        if let VcrMode::Record(_) = self.mode {
+            return ControlFlow::Continue(());
+        }
+
+        let mut cassette = self.cassette.lock().unwrap();
+        let matching: Vec<usize> = cassette
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.matches(request))
+            .map(|(i, _)| i)
+            .collect();
+        let result = match matching.as_slice() {
+            [] => Err(format!(
+                "no recorded response for {} {}",
+                request.method, request.url
+            )),
+            // keep the last recording to answer later repetitions
+            [last] => Ok(cassette[*last].response.to_response()),
+            [first, ..] => Ok(cassette.remove(*first).response.to_response()),
+        };
+        ControlFlow::Break(result)
+    }
+}
+
+impl ResponseMiddleware for Vcr {
+    fn handle_response(
+        &self,
+        request: &Request,
+        result: &mut ehttp::Result<Response>,
+        _elapsed: Duration,
+    ) -> ControlFlow<()> {
+        if let (VcrMode::Record(path), Ok(response)) = (&self.mode, result) {
+            let mut cassette = self.cassette.lock().unwrap();
+            cassette.push(Interaction {
+                method: request.method.clone(),
+                url: request.url.clone(),
+                body: RecordedBody::new(&request.body),
+                response: RecordedResponse::from(&*response),
+            });
+            self.save(path, &cassette);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// A recorded request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    method: String,
+    url: String,
+    body: RecordedBody,
+    response: RecordedResponse,
+}
+
+impl Interaction {
+    fn matches(&self, request: &Request) -> bool {
+        self.method.eq_ignore_ascii_case(&request.method)
+            && self.url == request.url
+            && self.body.to_bytes() == request.body
+    }
+}
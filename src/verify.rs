@@ -0,0 +1,106 @@
+use ehttp::{Request, Response};
+
+/// The start of the transport error of a request whose response failed verification.
+pub(crate) const VERIFICATION_FAILED: &str = "response verification failed";
+
+/// Checks responses before they are delivered, e.g. that they are signed by a trusted key.
+///
+/// Verifiers run on every response of the plugin, mocked ones included, before the response
+/// middleware. Verification fails closed: a response rejected by any verifier is never
+/// delivered, the request fails with `HttpError::Verification` instead.
+///
+/// Implemented for closures taking the request and its response.
+pub trait ResponseVerifier: Send + Sync + 'static {
+    /// Checks `response` to `request`, returning why it is rejected.
+    ///
+    /// `request` is the request as last sent, after the request middleware and failover.
+    fn verify(&self, request: &Request, response: &Response) -> Result<(), String>;
+}
+
+impl<F> ResponseVerifier for F
+where
+    F: Fn(&Request, &Response) -> Result<(), String> + Send + Sync + 'static,
+{
+    fn verify(&self, request: &Request, response: &Response) -> Result<(), String> {
+        self(request, response)
+    }
+}
+
+/// Verifies the Ed25519 signature of response bodies, sent base64-encoded in a header.
+///
+/// Responses without a valid signature are rejected, including those without the header.
+///
+/// # Examples
+///
+/// ```
+/// app.add_response_verifier(
+///     Ed25519Verifier::from_base64(CONFIG_PUBLIC_KEY)
+///         .unwrap()
+///         .header("X-Config-Signature")
+///         .url_prefix("https://config.example.com/"),
+/// );
+/// ```
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Clone)]
+pub struct Ed25519Verifier {
+    public_key: Vec<u8>,
+    header: String,
+    url_prefix: Option<String>,
+}
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Verifier {
+    /// Verifies signatures with the raw 32-byte `public_key`, from the "X-Signature" header.
+    pub fn new(public_key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            public_key: public_key.into(),
+            header: "X-Signature".to_string(),
+            url_prefix: None,
+        }
+    }
+
+    /// Verifies signatures with a base64-encoded public key.
+    pub fn from_base64(public_key: &str) -> Result<Self, base64::DecodeError> {
+        use base64::Engine;
+
+        base64::engine::general_purpose::STANDARD
+            .decode(public_key.trim())
+            .map(Self::new)
+    }
+
+    /// Reads the signature from the header `name`.
+    pub fn header(mut self, name: impl ToString) -> Self {
+        self.header = name.to_string();
+        self
+    }
+
+    /// Only verifies responses to requests whose URL starts with `prefix`.
+    pub fn url_prefix(mut self, prefix: impl ToString) -> Self {
+        self.url_prefix = Some(prefix.to_string());
+        self
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl ResponseVerifier for Ed25519Verifier {
+    fn verify(&self, request: &Request, response: &Response) -> Result<(), String> {
+        use base64::Engine;
+        use ring::signature::{UnparsedPublicKey, ED25519};
+
+        if let Some(prefix) = &self.url_prefix {
+            if !request.url.starts_with(prefix.as_str()) {
+                return Ok(());
+            }
+        }
+        let signature = response
+            .headers
+            .get(&self.header)
+            .ok_or_else(|| format!("missing {} header", self.header))?;
+        let signature = base64::engine::general_purpose::STANDARD
+            .decode(signature.trim())
+            .map_err(|e| format!("invalid {} header: {e}", self.header))?;
+        UnparsedPublicKey::new(&ED25519, &self.public_key)
+            .verify(&response.bytes, &signature)
+            .map_err(|_| "invalid Ed25519 signature".to_string())
+    }
+}